@@ -0,0 +1,37 @@
+use bencher::{benchmark_group, benchmark_main, Bencher};
+use sprs::smmp::{set_thread_threading_strategy, ThreadingStrategy};
+use sprs::{CsMat, TriMat};
+
+/// Banded matrix whose bandwidth grows with the row index, so that an even
+/// split of the rows does not give an even split of the workload.
+fn growing_band_matrix(n: usize) -> CsMat<f64> {
+    let mut tri = TriMat::new((n, n));
+    for row in 0..n {
+        let band = 1 + 32 * row / n;
+        let start = row.saturating_sub(band);
+        let stop = (row + band + 1).min(n);
+        for col in start..stop {
+            tri.add_triplet(row, col, 1. / (1 + row + col) as f64);
+        }
+    }
+    tri.to_csr()
+}
+
+fn banded_squared_single_thread(bench: &mut Bencher) {
+    let mat = growing_band_matrix(20_000);
+    set_thread_threading_strategy(ThreadingStrategy::Fixed(1));
+    bench.iter(|| &mat * &mat);
+}
+
+fn banded_squared_multi_thread(bench: &mut Bencher) {
+    let mat = growing_band_matrix(20_000);
+    set_thread_threading_strategy(ThreadingStrategy::Automatic);
+    bench.iter(|| &mat * &mat);
+}
+
+benchmark_group!(
+    benches,
+    banded_squared_single_thread,
+    banded_squared_multi_thread
+);
+benchmark_main!(benches);
@@ -0,0 +1,6 @@
+%%MatrixMarket matrix array real general
+2 2
+1.0
+2.0
+3.0
+4.0
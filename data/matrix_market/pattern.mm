@@ -0,0 +1,8 @@
+%%MatrixMarket matrix coordinate pattern general
+% a 4x5 matrix, only storing the sparsity pattern
+4 5 5
+1 1
+1 5
+2 2
+3 4
+4 2
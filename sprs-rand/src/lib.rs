@@ -18,6 +18,9 @@ pub mod rand_distr {
     pub use rand_distr::*;
 }
 
+#[cfg(feature = "proptest")]
+pub mod strategy;
+
 /// Generate a random sparse matrix matching the given density and sampling
 /// the values of its non-zero elements from the provided distribution.
 pub fn rand_csr<R, N, D, I>(
@@ -84,6 +87,106 @@ where
     CsMatI::new(shape, indptr, indices, data)
 }
 
+/// Generate a random banded sparse matrix, whose non-zero elements are
+/// located at most `bandwidth` positions away from the diagonal, eg to
+/// mimic the structure of discretized PDE operators.
+///
+/// Each location inside the band holds a non-zero element with probability
+/// `density`, its value being sampled from the provided distribution. The
+/// inner indices of the result are sorted, without duplicates.
+///
+/// # Panics
+///
+/// If `density` is not in `[0, 1]`.
+pub fn rand_csr_banded<R, N, D, I>(
+    rng: &mut R,
+    dist: D,
+    shape: (usize, usize),
+    bandwidth: usize,
+    density: f64,
+) -> CsMatI<N, I>
+where
+    R: Rng + ?Sized,
+    D: Distribution<N>,
+    I: SpIndex,
+{
+    assert!((0.0..=1.0).contains(&density));
+    let (nrows, ncols) = shape;
+    let mut indptr = Vec::with_capacity(nrows + 1);
+    let mut indices = Vec::new();
+    let mut data = Vec::new();
+    indptr.push(I::zero());
+    for row in 0..nrows {
+        let start = row.saturating_sub(bandwidth);
+        let end = ncols.min(row.saturating_add(bandwidth).saturating_add(1));
+        for col in start..end {
+            if rng.gen_bool(density) {
+                indices.push(I::from_usize(col));
+                data.push(dist.sample(rng));
+            }
+        }
+        indptr.push(I::from_usize(indices.len()));
+    }
+    CsMatI::new(shape, indptr, indices, data)
+}
+
+/// Generate a random sparse matrix of random shape and storage, meant as an
+/// input for property-based tests.
+///
+/// The number of rows and columns are sampled uniformly in
+/// `0..=max_shape.0` and `0..=max_shape.1`, the density in
+/// `[0, max_density]`, and the storage is either CSR or CSC. Each location
+/// then holds a non-zero element with probability the sampled density, its
+/// value being sampled from the provided distribution.
+///
+/// The result is always structurally valid: its indptr is non-decreasing,
+/// and its inner indices are in bounds and sorted, without duplicates.
+///
+/// For property-based tests using `proptest`, the `proptest` feature
+/// provides [`strategy::csmat`](strategy/fn.csmat.html), which generates
+/// matrices with the same guarantees and can shrink failing inputs.
+///
+/// # Panics
+///
+/// If `max_density` is not in `[0, 1]`.
+pub fn rand_csr_arbitrary<R, N, D, I>(
+    rng: &mut R,
+    dist: D,
+    max_shape: (usize, usize),
+    max_density: f64,
+) -> CsMatI<N, I>
+where
+    R: Rng + ?Sized,
+    D: Distribution<N>,
+    I: SpIndex,
+{
+    assert!((0.0..=1.0).contains(&max_density));
+    let nrows = rng.gen_range(0, max_shape.0 + 1);
+    let ncols = rng.gen_range(0, max_shape.1 + 1);
+    let density = rng.gen::<f64>() * max_density;
+    let csr = rng.gen_bool(0.5);
+    let (outer_dims, inner_dims) =
+        if csr { (nrows, ncols) } else { (ncols, nrows) };
+    let mut indptr = Vec::with_capacity(outer_dims + 1);
+    let mut indices = Vec::new();
+    let mut data = Vec::new();
+    indptr.push(I::zero());
+    for _ in 0..outer_dims {
+        for inner in 0..inner_dims {
+            if rng.gen_bool(density) {
+                indices.push(I::from_usize(inner));
+                data.push(dist.sample(rng));
+            }
+        }
+        indptr.push(I::from_usize(indices.len()));
+    }
+    if csr {
+        CsMatI::new((nrows, ncols), indptr, indices, data)
+    } else {
+        CsMatI::new_csc((nrows, ncols), indptr, indices, data)
+    }
+}
+
 /// Convenient wrapper for the common case of sampling a matrix with standard
 /// normal distribution of the nnz values, using a lightweight rng.
 pub fn rand_csr_std(shape: (usize, usize), density: f64) -> CsMat<f64> {
@@ -119,6 +222,43 @@ mod tests {
         assert!(mat.density() < 0.32);
     }
 
+    #[test]
+    fn random_csr_banded() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1234);
+        let mat: CsMat<f64> =
+            super::rand_csr_banded(&mut rng, Standard, (200, 150), 2, 0.5);
+        assert_eq!(mat.shape(), (200, 150));
+        for (_, (row, col)) in mat.iter() {
+            assert!(row <= col + 2 && col <= row + 2);
+        }
+        // number of locations in the band
+        let band_density = mat.nnz() as f64 / 747.;
+        assert!(band_density > 0.45 && band_density < 0.55);
+
+        let full: CsMat<f64> =
+            super::rand_csr_banded(&mut rng, Standard, (4, 6), 1, 1.);
+        assert_eq!(full.indptr().raw_storage(), &[0, 2, 5, 8, 11]);
+        let empty: CsMat<f64> =
+            super::rand_csr_banded(&mut rng, Standard, (4, 6), 1, 0.);
+        assert_eq!(empty.nnz(), 0);
+    }
+
+    #[test]
+    fn random_csr_arbitrary() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1234);
+        let (mut nb_csr, mut nb_empty) = (0, 0);
+        for _ in 0..200 {
+            let mat: CsMat<f64> =
+                super::rand_csr_arbitrary(&mut rng, Standard, (6, 4), 0.8);
+            assert!(mat.rows() <= 6 && mat.cols() <= 4);
+            assert!(mat.check_compressed_structure().is_ok());
+            nb_csr += mat.is_csr() as usize;
+            nb_empty += (mat.nnz() == 0) as usize;
+        }
+        assert!(nb_csr > 50 && nb_csr < 150);
+        assert!(nb_empty > 0 && nb_empty < 200);
+    }
+
     #[test]
     fn random_csr_std() {
         let mat = super::rand_csr_std((100, 1000), 0.2);
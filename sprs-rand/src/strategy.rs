@@ -0,0 +1,118 @@
+//! `proptest` strategies generating sparse matrices
+//!
+//! This module is only available with the `proptest` feature.
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::sample::subsequence;
+use sprs::indexing::SpIndex;
+use sprs::CsMatI;
+
+/// A strategy generating sparse matrices of random shape and storage,
+/// whose non-zero values are generated by `values`.
+///
+/// The number of rows and columns are in `0..=max_shape.0` and
+/// `0..=max_shape.1`, the storage is either CSR or CSC, and each outer
+/// slice holds at most `max_density` times the inner dimension
+/// non-zeros, rounded up.
+///
+/// The generated matrices are always structurally valid: their indptr is
+/// non-decreasing and consistent with the number of non-zeros, and their
+/// inner indices are in bounds and sorted, without duplicates. Failing
+/// inputs shrink towards matrices with fewer non-zeros.
+///
+/// # Example
+///
+/// ```rust
+/// use proptest::prelude::*;
+/// use sprs::CsMat;
+///
+/// proptest! {
+///     fn transpose_twice(
+///         mat in sprs_rand::strategy::csmat((10, 10), 0.5, -10..10),
+///     ) {
+///         let mat: CsMat<i32> = mat;
+///         let twice = mat.transpose_view().to_owned().transpose_into();
+///         prop_assert_eq!(twice, mat);
+///     }
+/// }
+/// # fn main() { transpose_twice(); }
+/// ```
+///
+/// # Panics
+///
+/// If `max_density` is not in `[0, 1]`.
+pub fn csmat<N, I, Iptr, S>(
+    max_shape: (usize, usize),
+    max_density: f64,
+    values: S,
+) -> impl Strategy<Value = CsMatI<N, I, Iptr>>
+where
+    N: Clone + std::fmt::Debug,
+    I: SpIndex,
+    Iptr: SpIndex,
+    S: Strategy<Value = N> + Clone,
+{
+    assert!((0.0..=1.0).contains(&max_density));
+    (0..=max_shape.0, 0..=max_shape.1, any::<bool>())
+        .prop_flat_map(move |(nrows, ncols, csr)| {
+            let (outer_dims, inner_dims) =
+                if csr { (nrows, ncols) } else { (ncols, nrows) };
+            let max_nnz = (inner_dims as f64 * max_density).ceil() as usize;
+            let inner_pattern =
+                subsequence((0..inner_dims).collect::<Vec<_>>(), 0..=max_nnz);
+            (Just((nrows, ncols, csr)), vec(inner_pattern, outer_dims))
+        })
+        .prop_flat_map(move |(shape, patterns)| {
+            let nnz = patterns.iter().map(Vec::len).sum::<usize>();
+            (Just(shape), Just(patterns), vec(values.clone(), nnz))
+        })
+        .prop_map(|((nrows, ncols, csr), patterns, data)| {
+            let mut indptr = Vec::with_capacity(patterns.len() + 1);
+            let mut indices = Vec::with_capacity(data.len());
+            indptr.push(Iptr::zero());
+            for pattern in patterns {
+                indices.extend(pattern.into_iter().map(I::from_usize));
+                indptr.push(Iptr::from_usize(indices.len()));
+            }
+            if csr {
+                CsMatI::new((nrows, ncols), indptr, indices, data)
+            } else {
+                CsMatI::new_csc((nrows, ncols), indptr, indices, data)
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use sprs::{CsMat, CsMatI};
+
+    proptest! {
+        #[test]
+        fn csmat_is_valid(mat in super::csmat((8, 5), 0.6, -5i32..5)) {
+            let mat: CsMat<i32> = mat;
+            prop_assert!(mat.rows() <= 8 && mat.cols() <= 5);
+            prop_assert!(mat.check_compressed_structure().is_ok());
+            let max_nnz = (mat.inner_dims() as f64 * 0.6).ceil() as usize;
+            for outer in mat.outer_iterator() {
+                prop_assert!(outer.nnz() <= max_nnz);
+            }
+        }
+
+        #[test]
+        fn csmat_index_types(
+            mat in super::csmat((300, 3), 1., Just(1u8)),
+        ) {
+            let mat: CsMatI<u8, u16, u32> = mat;
+            prop_assert!(mat.check_compressed_structure().is_ok());
+            prop_assert!(mat.data().iter().all(|&x| x == 1));
+        }
+
+        #[test]
+        fn csmat_empty_density(mat in super::csmat((4, 4), 0., any::<f64>())) {
+            let mat: CsMat<f64> = mat;
+            prop_assert_eq!(mat.nnz(), 0);
+        }
+    }
+}
@@ -1,10 +1,33 @@
 //! Error type for sprs
 
 #[derive(PartialEq, Debug, Copy, Clone)]
+#[non_exhaustive]
 pub enum StructureError {
     Unsorted(&'static str),
     SizeMismatch(&'static str),
     OutOfRange(&'static str),
+    /// The indptr decreases between `position` and `position + 1`
+    DecreasingIndptr {
+        position: usize,
+    },
+    /// The number of non-zeros described by the indptr does not match the
+    /// length of the indices
+    IndptrNnzMismatch {
+        indptr_nnz: usize,
+        nnz: usize,
+    },
+    /// The inner index `index` in the outer slice `outer` is not lower
+    /// than the inner dimension `bound`
+    IndexOutOfBounds {
+        outer: usize,
+        index: usize,
+        bound: usize,
+    },
+    /// The inner indices of the outer slice `outer` are not sorted, or
+    /// contain duplicates
+    UnsortedIndices {
+        outer: usize,
+    },
 }
 
 #[derive(PartialEq, Debug, Copy, Clone)]
@@ -18,17 +41,27 @@ pub enum StructureErrorKind {
 impl StructureError {
     pub fn kind(&self) -> StructureErrorKind {
         match self {
-            StructureError::Unsorted(_) => StructureErrorKind::Unsorted,
-            StructureError::SizeMismatch(_) => StructureErrorKind::SizeMismatch,
-            StructureError::OutOfRange(_) => StructureErrorKind::OutOfRange,
+            StructureError::Unsorted(_)
+            | StructureError::DecreasingIndptr { .. }
+            | StructureError::UnsortedIndices { .. } => {
+                StructureErrorKind::Unsorted
+            }
+            StructureError::SizeMismatch(_)
+            | StructureError::IndptrNnzMismatch { .. } => {
+                StructureErrorKind::SizeMismatch
+            }
+            StructureError::OutOfRange(_)
+            | StructureError::IndexOutOfBounds { .. } => {
+                StructureErrorKind::OutOfRange
+            }
         }
     }
 
     fn kind_str(&self) -> &str {
-        match self {
-            StructureError::Unsorted(_) => "unsorted",
-            StructureError::SizeMismatch(_) => "size mismatch",
-            StructureError::OutOfRange(_) => "out of range",
+        match self.kind() {
+            StructureErrorKind::Unsorted => "unsorted",
+            StructureErrorKind::SizeMismatch => "size mismatch",
+            StructureErrorKind::OutOfRange => "out of range",
         }
     }
 
@@ -37,13 +70,42 @@ impl StructureError {
             StructureError::Unsorted(s)
             | StructureError::SizeMismatch(s)
             | StructureError::OutOfRange(s) => s,
+            StructureError::DecreasingIndptr { .. } => "Unsorted indptr",
+            StructureError::IndptrNnzMismatch { .. } => {
+                "Indices length and indptr's nnz do not match"
+            }
+            StructureError::IndexOutOfBounds { .. } => {
+                "Index is larger than inner dimension"
+            }
+            StructureError::UnsortedIndices { .. } => "Indices are not sorted",
         }
     }
 }
 
 impl std::fmt::Display for StructureError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "Structure Error ({}): {}", self.kind_str(), self.msg())
+        write!(f, "Structure Error ({}): {}", self.kind_str(), self.msg())?;
+        match self {
+            StructureError::DecreasingIndptr { position } => {
+                write!(f, " (indptr[{}] > indptr[{}])", position, position + 1)
+            }
+            StructureError::IndptrNnzMismatch { indptr_nnz, nnz } => {
+                write!(f, " (indptr: {}, indices: {})", indptr_nnz, nnz)
+            }
+            StructureError::IndexOutOfBounds {
+                outer,
+                index,
+                bound,
+            } => write!(
+                f,
+                " (index {} in outer slice {}, inner dimension is {})",
+                index, outer, bound
+            ),
+            StructureError::UnsortedIndices { outer } => {
+                write!(f, " (in outer slice {})", outer)
+            }
+            _ => Ok(()),
+        }
     }
 }
 
@@ -61,6 +123,12 @@ pub struct SingularMatrixInfo {
     pub reason: &'static str,
 }
 
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct BreakdownInfo {
+    pub iteration: usize,
+    pub reason: &'static str,
+}
+
 #[derive(PartialEq, Debug, Clone)]
 #[non_exhaustive]
 pub enum LinalgError {
@@ -68,6 +136,8 @@ pub enum LinalgError {
     NonSquareMatrix,
     SingularMatrix(SingularMatrixInfo),
     ThirdPartyError(isize, &'static str),
+    Breakdown(BreakdownInfo),
+    NotPositiveDefinite(usize),
 }
 
 impl std::fmt::Display for LinalgError {
@@ -94,20 +164,77 @@ impl std::fmt::Display for LinalgError {
             LinalgError::ThirdPartyError(code, msg) => {
                 write!(f, "Third party error: {} (code {})", msg, code,)
             }
+            LinalgError::Breakdown(info) => {
+                write!(
+                    f,
+                    "Solver breakdown at iteration {} ({})",
+                    info.iteration, info.reason,
+                )
+            }
+            LinalgError::NotPositiveDefinite(index) => {
+                write!(
+                    f,
+                    "Matrix is not positive definite (non-positive pivot at index {})",
+                    index,
+                )
+            }
         }
     }
 }
 
 impl std::error::Error for LinalgError {}
 
-/// Convenience wrapper around more precise error types. Not returned by
-/// functions in this crate, but can be easily obtained from any error
-/// returned in this crate using `Into` and `From`.
+/// A value of a matrix that could not be converted to another scalar type,
+/// along with its location in the matrix
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub struct CastError<N> {
+    pub value: N,
+    pub row: usize,
+    pub col: usize,
+}
+
+impl<N: std::fmt::Debug> std::fmt::Display for CastError<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Cannot convert value {:?} at ({}, {}) to the requested type",
+            self.value, self.row, self.col,
+        )
+    }
+}
+
+impl<N: std::fmt::Debug> std::error::Error for CastError<N> {}
+
+/// Errors arising from arithmetic operations on the values of a matrix
+#[derive(PartialEq, Debug, Copy, Clone)]
+#[non_exhaustive]
+pub enum ArithmeticError {
+    /// The computation of the value at `(row, col)` in the result
+    /// overflowed the scalar type
+    Overflow { row: usize, col: usize },
+}
+
+impl std::fmt::Display for ArithmeticError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ArithmeticError::Overflow { row, col } => {
+                write!(f, "Arithmetic overflow at ({}, {})", row, col)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ArithmeticError {}
+
+/// Convenience wrapper around more precise error types. Returned by a few
+/// functions of this crate, such as `CsMatI::scaled_add`, and can be easily
+/// obtained from any error returned in this crate using `Into` and `From`.
 #[derive(PartialEq, Debug, Clone)]
 #[non_exhaustive]
 pub enum SprsError {
     Structure(StructureError),
     Linalg(LinalgError),
+    Arithmetic(ArithmeticError),
 }
 
 impl From<StructureError> for SprsError {
@@ -122,11 +249,18 @@ impl From<LinalgError> for SprsError {
     }
 }
 
+impl From<ArithmeticError> for SprsError {
+    fn from(e: ArithmeticError) -> Self {
+        Self::Arithmetic(e)
+    }
+}
+
 impl std::fmt::Display for SprsError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             Self::Structure(e) => write!(f, "Structure error: {}", e),
             Self::Linalg(e) => write!(f, "Linalg error: {}", e),
+            Self::Arithmetic(e) => write!(f, "Arithmetic error: {}", e),
         }
     }
 }
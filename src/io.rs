@@ -13,11 +13,18 @@ use crate::indexing::SpIndex;
 use crate::num_kinds::{NumKind, PrimitiveKind};
 use crate::sparse::{SparseMat, TriMatI};
 
+#[cfg(feature = "npz")]
+mod npz;
+#[cfg(feature = "npz")]
+pub use self::npz::{read_npz, write_npz, NpyScalar};
+
 #[derive(Debug)]
 pub enum IoError {
     Io(io::Error),
     BadMatrixMarketFile,
     UnsupportedMatrixMarketFormat,
+    BadNpzFile,
+    UnsupportedNpzFormat,
 }
 
 use self::IoError::*;
@@ -26,9 +33,12 @@ impl fmt::Display for IoError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Self::Io(ref err) => err.fmt(f),
-            Self::BadMatrixMarketFile | Self::UnsupportedMatrixMarketFormat => {
-                write!(f, "Bad matrix market file.")
+            Self::BadMatrixMarketFile => write!(f, "Bad matrix market file."),
+            Self::UnsupportedMatrixMarketFormat => {
+                write!(f, "Unsupported matrix market format.")
             }
+            Self::BadNpzFile => write!(f, "Bad npz file."),
+            Self::UnsupportedNpzFormat => write!(f, "Unsupported npz format."),
         }
     }
 }
@@ -50,6 +60,10 @@ impl PartialEq for IoError {
             Self::UnsupportedMatrixMarketFormat => {
                 matches!(*rhs, Self::UnsupportedMatrixMarketFormat)
             }
+            Self::BadNpzFile => matches!(*rhs, Self::BadNpzFile),
+            Self::UnsupportedNpzFormat => {
+                matches!(*rhs, Self::UnsupportedNpzFormat)
+            }
             Self::Io(..) => false,
         }
     }
@@ -60,6 +74,7 @@ enum DataType {
     Integer,
     Real,
     Complex,
+    Pattern,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -80,6 +95,8 @@ fn parse_header(header: &str) -> Result<(SymmetryMode, DataType), IoError> {
         DataType::Integer
     } else if header.contains("complex") {
         DataType::Complex
+    } else if header.contains("pattern") {
+        DataType::Pattern
     } else {
         return Err(BadMatrixMarketFile);
     };
@@ -100,8 +117,9 @@ fn parse_header(header: &str) -> Result<(SymmetryMode, DataType), IoError> {
 /// Read a sparse matrix file in the Matrix Market format and return a
 /// corresponding triplet matrix.
 ///
-/// Presently, only general matrices are supported, but symmetric and hermitian
-/// matrices should be supported in the future.
+/// Presently, general, symmetric and skew-symmetric matrices are supported,
+/// with integer, real or pattern entries. For pattern matrices, all
+/// non-zero entries are set to one.
 pub fn read_matrix_market<N, I, P>(mm_file: P) -> Result<TriMatI<N, I>, IoError>
 where
     I: SpIndex,
@@ -117,8 +135,7 @@ where
 /// Read a sparse matrix in the Matrix Market format from an `io::BufRead` and return a
 /// corresponding triplet matrix.
 ///
-/// Presently, only general matrices are supported, but symmetric and hermitian
-/// matrices should be supported in the future.
+/// See `read_matrix_market` for the supported formats.
 pub fn read_matrix_market_from_bufread<N, I, R>(
     reader: &mut R,
 ) -> Result<TriMatI<N, I>, IoError>
@@ -146,7 +163,11 @@ where
     'header: loop {
         line.clear();
         let len = reader.read_line(&mut line)?;
-        if len == 0 || line.starts_with('%') {
+        if len == 0 {
+            // reached end of file without finding the shape line
+            return Err(BadMatrixMarketFile);
+        }
+        if line.starts_with('%') {
             continue 'header;
         }
         break;
@@ -189,9 +210,11 @@ where
         }
         // Non-zero entries are lines of the form:
         // row col value
-        // if the data type is integer of real, and
+        // if the data type is integer of real,
         // row col real imag
-        // if the data type is complex.
+        // if the data type is complex, and
+        // row col
+        // if the data type is pattern.
         // Again, this is with arbitrary amounts of whitespace
         let mut entry = line.split_whitespace();
         let row = entry
@@ -211,15 +234,16 @@ where
                     entry.next().ok_or(BadMatrixMarketFile).and_then(|s| {
                         s.parse::<isize>().or(Err(BadMatrixMarketFile))
                     })?;
-                NumCast::from(val).unwrap()
+                NumCast::from(val).ok_or(BadMatrixMarketFile)?
             }
             DataType::Real => {
                 let val =
                     entry.next().ok_or(BadMatrixMarketFile).and_then(|s| {
                         s.parse::<f64>().or(Err(BadMatrixMarketFile))
                     })?;
-                NumCast::from(val).unwrap()
+                NumCast::from(val).ok_or(BadMatrixMarketFile)?
             }
+            DataType::Pattern => NumCast::from(1).ok_or(BadMatrixMarketFile)?,
             DataType::Complex => unreachable!(),
         };
         row_inds.push(I::from_usize(row));
@@ -495,6 +519,75 @@ mod test {
         assert_eq!(res.unwrap_err(), IoError::BadMatrixMarketFile);
     }
 
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn matrix_market_read_fail_bad_header() {
+        let path = "data/matrix_market/bad_files/bad_header.mm";
+        let res = read_matrix_market::<f64, i32, _>(path);
+        assert_eq!(res.unwrap_err(), IoError::BadMatrixMarketFile);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn matrix_market_read_fail_non_numeric_entry() {
+        let path = "data/matrix_market/bad_files/non_numeric_entry.mm";
+        let res = read_matrix_market::<f64, i32, _>(path);
+        assert_eq!(res.unwrap_err(), IoError::BadMatrixMarketFile);
+    }
+
+    #[test]
+    fn matrix_market_read_fail_value_out_of_range() {
+        let mm = "%%MatrixMarket matrix coordinate integer general\n\
+                  2 2 1\n\
+                  1 1 300\n";
+        let res =
+            read_matrix_market_from_bufread::<u8, usize, _>(&mut mm.as_bytes());
+        assert_eq!(res.unwrap_err(), IoError::BadMatrixMarketFile);
+    }
+
+    #[test]
+    fn matrix_market_read_fail_missing_shape() {
+        let mm = "%%MatrixMarket matrix coordinate real general\n% comment\n";
+        let res = read_matrix_market_from_bufread::<f64, usize, _>(
+            &mut mm.as_bytes(),
+        );
+        assert_eq!(res.unwrap_err(), IoError::BadMatrixMarketFile);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn pattern_matrix_market_read() {
+        let path = "data/matrix_market/pattern.mm";
+        let mat = read_matrix_market::<f64, usize, _>(path).unwrap();
+        let expected = CsMat::new(
+            (4, 5),
+            vec![0, 2, 3, 4, 5],
+            vec![0, 4, 1, 3, 1],
+            vec![1.; 5],
+        );
+        assert_eq!(mat.to_csr(), expected);
+    }
+
+    #[test]
+    fn symmetric_pattern_matrix_market_read() {
+        let mm = "%%MatrixMarket matrix coordinate pattern symmetric\n\
+                  3 3 3\n\
+                  1 1\n\
+                  3 1\n\
+                  3 2\n";
+        let mat = read_matrix_market_from_bufread::<i32, usize, _>(
+            &mut mm.as_bytes(),
+        )
+        .unwrap();
+        let expected = CsMat::new(
+            (3, 3),
+            vec![0, 2, 3, 5],
+            vec![0, 2, 2, 0, 1],
+            vec![1; 5],
+        );
+        assert_eq!(mat.to_csr(), expected);
+    }
+
     #[test]
     #[cfg_attr(miri, ignore)]
     fn read_write_read_matrix_market() {
@@ -523,6 +616,33 @@ mod test {
         assert_eq!(csc, mat2.to_csc());
     }
 
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn write_read_eye_matrix_market() {
+        let eye: CsMat<f64> = CsMat::eye(5);
+        let tmp_dir = tempdir().unwrap();
+        let save_path = tmp_dir.path().join("eye.mm");
+        write_matrix_market(&save_path, &eye).unwrap();
+        let mat = read_matrix_market::<f64, usize, _>(&save_path).unwrap();
+        assert_eq!(eye, mat.to_csr());
+
+        // non-symmetric matrix, written from both storages
+        let mat = CsMat::new(
+            (3, 4),
+            vec![0, 2, 3, 4],
+            vec![1, 3, 0, 2],
+            vec![1., 2., 3., 4.],
+        );
+        let save_path = tmp_dir.path().join("rect.mm");
+        write_matrix_market(&save_path, &mat).unwrap();
+        let mat2 = read_matrix_market::<f64, usize, _>(&save_path).unwrap();
+        assert_eq!(mat, mat2.to_csr());
+        let csc = mat.to_csc();
+        write_matrix_market(&save_path, &csc).unwrap();
+        let mat3 = read_matrix_market::<f64, usize, _>(&save_path).unwrap();
+        assert_eq!(mat, mat3.to_csr());
+    }
+
     #[test]
     #[cfg_attr(miri, ignore)]
     fn read_symmetric_matrix_market() {
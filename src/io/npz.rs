@@ -0,0 +1,687 @@
+//! Interchange of compressed matrices with scipy, using its NPZ format
+//!
+//! `scipy.sparse.save_npz` writes a CSR or CSC matrix as a zip archive of
+//! numpy arrays: `format` (`b'csr'` or `b'csc'`), `shape`, `indptr`,
+//! `indices` and `data`, each of them stored in the NPY format. The
+//! functions of this module read and write such archives, which can thus
+//! be exchanged with `scipy.sparse.load_npz` and `save_npz`.
+
+use std::convert::TryFrom;
+use std::fs;
+use std::io;
+use std::mem::size_of;
+use std::ops::Deref;
+use std::path::Path;
+
+use num_complex::{Complex32, Complex64};
+
+use super::IoError;
+use crate::indexing::SpIndex;
+use crate::sparse::{CsMatBase, CsMatI};
+use crate::CompressedStorage::{self, CSC, CSR};
+
+/// Scalar types that can be stored in a numpy array
+pub trait NpyScalar: Sized {
+    /// The numpy dtype descriptor of this type, eg `<f8` for `f64`
+    const DESCR: &'static str;
+
+    /// Append the little-endian representation of `self` to `out`
+    fn write_le(&self, out: &mut Vec<u8>);
+
+    /// Read a value from its little-endian representation, whose length
+    /// is the size of `Self`
+    fn read_le(bytes: &[u8]) -> Self;
+}
+
+macro_rules! npy_scalar_impl {
+    ($scalar: ty, $descr: expr) => {
+        impl NpyScalar for $scalar {
+            const DESCR: &'static str = $descr;
+
+            fn write_le(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+
+            fn read_le(bytes: &[u8]) -> Self {
+                let mut buf = [0; size_of::<$scalar>()];
+                buf.copy_from_slice(bytes);
+                Self::from_le_bytes(buf)
+            }
+        }
+    };
+}
+
+npy_scalar_impl!(i8, "|i1");
+npy_scalar_impl!(u8, "|u1");
+npy_scalar_impl!(i16, "<i2");
+npy_scalar_impl!(u16, "<u2");
+npy_scalar_impl!(i32, "<i4");
+npy_scalar_impl!(u32, "<u4");
+npy_scalar_impl!(i64, "<i8");
+npy_scalar_impl!(u64, "<u8");
+npy_scalar_impl!(f32, "<f4");
+npy_scalar_impl!(f64, "<f8");
+
+macro_rules! npy_complex_impl {
+    ($complex: ty, $descr: expr) => {
+        impl NpyScalar for $complex {
+            const DESCR: &'static str = $descr;
+
+            fn write_le(&self, out: &mut Vec<u8>) {
+                self.re.write_le(out);
+                self.im.write_le(out);
+            }
+
+            fn read_le(bytes: &[u8]) -> Self {
+                let (re, im) = bytes.split_at(bytes.len() / 2);
+                Self::new(NpyScalar::read_le(re), NpyScalar::read_le(im))
+            }
+        }
+    };
+}
+
+npy_complex_impl!(Complex32, "<c8");
+npy_complex_impl!(Complex64, "<c16");
+
+/// Write a compressed matrix into `path`, in the NPZ format of
+/// `scipy.sparse.save_npz`. The archive is not compressed, and the index
+/// arrays are written as 64 bits integers.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use sprs::CsMat;
+/// # fn main() -> Result<(), sprs::io::IoError> {
+/// let eye: CsMat<f64> = CsMat::eye(5);
+/// sprs::io::write_npz("/tmp/identity5.npz", &eye)?;
+/// let read: CsMat<f64> = sprs::io::read_npz("/tmp/identity5.npz")?;
+/// assert_eq!(read, eye);
+/// # Ok(())
+/// # }
+/// ```
+pub fn write_npz<N, I, Iptr, IptrStorage, IndStorage, DataStorage, P>(
+    path: P,
+    mat: &CsMatBase<N, I, IptrStorage, IndStorage, DataStorage, Iptr>,
+) -> Result<(), IoError>
+where
+    N: NpyScalar,
+    I: SpIndex,
+    Iptr: SpIndex,
+    IptrStorage: Deref<Target = [Iptr]>,
+    IndStorage: Deref<Target = [I]>,
+    DataStorage: Deref<Target = [N]>,
+    P: AsRef<Path>,
+{
+    let indices: Vec<i64> =
+        mat.indices().iter().map(|i| i.index() as i64).collect();
+    let indices = npy_array(&indices, &[mat.nnz()]);
+    let indptr: Vec<i64> = mat
+        .proper_indptr()
+        .iter()
+        .map(|i| i.index() as i64)
+        .collect();
+    let indptr = npy_array(&indptr, &[indptr.len()]);
+    let format = match mat.storage() {
+        CSR => b"csr",
+        CSC => b"csc",
+    };
+    let format = npy_bytes("|S3", &[], format);
+    let shape = npy_array(&[mat.rows() as i64, mat.cols() as i64], &[2]);
+    let data = npy_array(mat.data(), &[mat.nnz()]);
+    let zip = zip_archive(&[
+        ("indices.npy", indices),
+        ("indptr.npy", indptr),
+        ("format.npy", format),
+        ("shape.npy", shape),
+        ("data.npy", data),
+    ])?;
+    fs::write(path, zip)?;
+    Ok(())
+}
+
+/// Read a compressed matrix from `path`, in the NPZ format of
+/// `scipy.sparse.save_npz`. Both compressed and uncompressed archives are
+/// supported.
+///
+/// The dtype of the data must match the scalar type `N`, and the indices
+/// can be stored as 32 or 64 bits integers. The structure of the matrix is
+/// checked.
+///
+/// # Errors
+///
+/// - `IoError::BadNpzFile` if the file is not a valid NPZ file, or if it
+///   does not describe a valid matrix
+/// - `IoError::UnsupportedNpzFormat` if the matrix is not a CSR or CSC
+///   matrix, or its arrays have an unsupported dtype or layout
+pub fn read_npz<N, I, Iptr, P>(path: P) -> Result<CsMatI<N, I, Iptr>, IoError>
+where
+    N: NpyScalar,
+    I: SpIndex,
+    Iptr: SpIndex,
+    P: AsRef<Path>,
+{
+    let archive = fs::read(path)?;
+    let entries = read_zip_entries(&archive)?;
+    let array = |name: &str| -> Result<NpyArray, IoError> {
+        let file_name = format!("{}.npy", name);
+        let bytes = entries
+            .iter()
+            .find(|(entry, _)| *entry == file_name)
+            .map(|(_, bytes)| bytes)
+            .ok_or(IoError::BadNpzFile)?;
+        parse_npy(bytes)
+    };
+
+    let format = array("format")?;
+    if !format.descr.starts_with("|S") {
+        return Err(IoError::UnsupportedNpzFormat);
+    }
+    let storage = match format.data.as_slice() {
+        b"csr" => CSR,
+        b"csc" => CSC,
+        _ => return Err(IoError::UnsupportedNpzFormat),
+    };
+    let shape = read_index_array(&array("shape")?)?;
+    if shape.len() != 2 {
+        return Err(IoError::BadNpzFile);
+    }
+    let indptr = read_index_array(&array("indptr")?)?
+        .into_iter()
+        .map(Iptr::try_from_usize)
+        .collect::<Option<Vec<_>>>()
+        .ok_or(IoError::BadNpzFile)?;
+    let indices = read_index_array(&array("indices")?)?
+        .into_iter()
+        .map(I::try_from_usize)
+        .collect::<Option<Vec<_>>>()
+        .ok_or(IoError::BadNpzFile)?;
+    let data = array("data")?;
+    if data.descr != N::DESCR {
+        return Err(IoError::UnsupportedNpzFormat);
+    }
+    let data = data
+        .checked_data(size_of::<N>())?
+        .chunks_exact(size_of::<N>())
+        .map(N::read_le)
+        .collect();
+    new_cs(storage, (shape[0], shape[1]), indptr, indices, data)
+}
+
+fn new_cs<N, I: SpIndex, Iptr: SpIndex>(
+    storage: CompressedStorage,
+    shape: (usize, usize),
+    indptr: Vec<Iptr>,
+    indices: Vec<I>,
+    data: Vec<N>,
+) -> Result<CsMatI<N, I, Iptr>, IoError> {
+    let res = match storage {
+        CSR => CsMatI::try_new(shape, indptr, indices, data),
+        CSC => CsMatI::try_new_csc(shape, indptr, indices, data),
+    };
+    res.map_err(|_| IoError::BadNpzFile)
+}
+
+/// A numpy array, whose data is stored as raw bytes
+struct NpyArray {
+    descr: String,
+    shape: Vec<usize>,
+    data: Vec<u8>,
+}
+
+impl NpyArray {
+    /// The raw data of the array, checked to hold exactly the number of
+    /// elements given by its shape, each element taking `elem_size` bytes
+    fn checked_data(&self, elem_size: usize) -> Result<&[u8], IoError> {
+        let len = self
+            .shape
+            .iter()
+            .try_fold(elem_size, |len, &dim| len.checked_mul(dim));
+        if len != Some(self.data.len()) {
+            return Err(IoError::BadNpzFile);
+        }
+        Ok(&self.data)
+    }
+}
+
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+
+/// Serialize an array in the version 1.0 of the NPY format
+fn npy_bytes(descr: &str, shape: &[usize], data: &[u8]) -> Vec<u8> {
+    let shape = match shape {
+        [] => "()".to_string(),
+        [len] => format!("({},)", len),
+        _ => {
+            let dims: Vec<_> = shape.iter().map(|d| d.to_string()).collect();
+            format!("({})", dims.join(", "))
+        }
+    };
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+        descr, shape,
+    );
+    // The data is aligned on 64 bytes, the header ending with a newline
+    let prefix_len = NPY_MAGIC.len() + 4;
+    let unpadded_len = prefix_len + header.len() + 1;
+    let padding = (64 - unpadded_len % 64) % 64;
+    for _ in 0..padding {
+        header.push(' ');
+    }
+    header.push('\n');
+    let mut bytes = Vec::with_capacity(prefix_len + header.len() + data.len());
+    bytes.extend_from_slice(NPY_MAGIC);
+    bytes.extend_from_slice(&[1, 0]);
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+fn npy_array<N: NpyScalar>(values: &[N], shape: &[usize]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(std::mem::size_of_val(values));
+    for val in values {
+        val.write_le(&mut data);
+    }
+    npy_bytes(N::DESCR, shape, &data)
+}
+
+/// Find the value associated to `key` in the header dictionary
+fn header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let key = format!("'{}':", key);
+    let start = header.find(&key)? + key.len();
+    Some(header[start..].trim_start())
+}
+
+fn parse_npy(bytes: &[u8]) -> Result<NpyArray, IoError> {
+    if !bytes.starts_with(NPY_MAGIC) || bytes.len() < 10 {
+        return Err(IoError::BadNpzFile);
+    }
+    let (header_len, header_start) = match bytes[6] {
+        1 => (read_u16(bytes, 8)? as usize, 10),
+        2 | 3 => (read_u32(bytes, 8)? as usize, 12),
+        _ => return Err(IoError::UnsupportedNpzFormat),
+    };
+    let header = bytes
+        .get(header_start..header_start + header_len)
+        .and_then(|header| std::str::from_utf8(header).ok())
+        .ok_or(IoError::BadNpzFile)?;
+
+    let descr = header_value(header, "descr")
+        .and_then(|val| val.strip_prefix('\''))
+        .and_then(|val| val.split('\'').next())
+        .ok_or(IoError::BadNpzFile)?;
+    let fortran_order =
+        header_value(header, "fortran_order").ok_or(IoError::BadNpzFile)?;
+    if !fortran_order.starts_with("False") {
+        return Err(IoError::UnsupportedNpzFormat);
+    }
+    let shape = header_value(header, "shape")
+        .and_then(|val| val.strip_prefix('('))
+        .and_then(|val| val.split(')').next())
+        .ok_or(IoError::BadNpzFile)?
+        .split(',')
+        .map(str::trim)
+        .filter(|dim| !dim.is_empty())
+        .map(|dim| dim.parse().map_err(|_| IoError::BadNpzFile))
+        .collect::<Result<Vec<usize>, _>>()?;
+
+    Ok(NpyArray {
+        descr: descr.to_string(),
+        shape,
+        data: bytes[header_start + header_len..].to_vec(),
+    })
+}
+
+/// Read a one dimensional array of non-negative integers
+fn read_index_array(array: &NpyArray) -> Result<Vec<usize>, IoError> {
+    fn convert<N, F>(
+        array: &NpyArray,
+        to_usize: F,
+    ) -> Result<Vec<usize>, IoError>
+    where
+        N: NpyScalar,
+        F: Fn(N) -> Option<usize>,
+    {
+        array
+            .checked_data(size_of::<N>())?
+            .chunks_exact(size_of::<N>())
+            .map(|bytes| to_usize(N::read_le(bytes)).ok_or(IoError::BadNpzFile))
+            .collect()
+    }
+    if array.shape.len() != 1 {
+        return Err(IoError::BadNpzFile);
+    }
+    let values = match array.descr.as_str() {
+        "<i4" => convert(array, |i: i32| SpIndex::try_index(i))?,
+        "<i8" => convert(array, |i: i64| SpIndex::try_index(i))?,
+        "<u4" => convert(array, |i: u32| SpIndex::try_index(i))?,
+        "<u8" => convert(array, |i: u64| SpIndex::try_index(i))?,
+        _ => return Err(IoError::UnsupportedNpzFormat),
+    };
+    Ok(values)
+}
+
+fn read_bytes(bytes: &[u8], pos: usize, len: usize) -> Result<&[u8], IoError> {
+    pos.checked_add(len)
+        .and_then(|end| bytes.get(pos..end))
+        .ok_or(IoError::BadNpzFile)
+}
+
+fn read_u16(bytes: &[u8], pos: usize) -> Result<u16, IoError> {
+    let mut buf = [0; 2];
+    buf.copy_from_slice(read_bytes(bytes, pos, 2)?);
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32(bytes: &[u8], pos: usize) -> Result<u32, IoError> {
+    let mut buf = [0; 4];
+    buf.copy_from_slice(read_bytes(bytes, pos, 4)?);
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(bytes: &[u8], pos: usize) -> Result<u64, IoError> {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(read_bytes(bytes, pos, 8)?);
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+const LOCAL_HEADER_SIG: u32 = 0x0403_4b50;
+const CENTRAL_HEADER_SIG: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIR_SIG: u32 = 0x0605_4b50;
+const ZIP_VERSION: u16 = 20;
+/// The DOS date of 1980-01-01
+const ZIP_DATE: u16 = 0x21;
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+/// Build a zip archive storing the given files without compression
+fn zip_archive(files: &[(&str, Vec<u8>)]) -> Result<Vec<u8>, IoError> {
+    let too_large = || {
+        io::Error::new(io::ErrorKind::InvalidInput, "Matrix too large for npz")
+    };
+    let to_u32 = |len: usize| -> Result<u32, IoError> {
+        if len > u32::MAX as usize {
+            Err(too_large().into())
+        } else {
+            Ok(len as u32)
+        }
+    };
+    let mut archive = Vec::new();
+    let mut central_dir = Vec::new();
+    for (name, contents) in files {
+        let offset = to_u32(archive.len())?;
+        let size = to_u32(contents.len())?;
+        let crc = crc32(contents);
+        // fields shared by the local and central headers
+        let mut common = Vec::with_capacity(26);
+        common.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes()); // flags
+        common.extend_from_slice(&STORED.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes()); // time
+        common.extend_from_slice(&ZIP_DATE.to_le_bytes());
+        common.extend_from_slice(&crc.to_le_bytes());
+        common.extend_from_slice(&size.to_le_bytes()); // compressed
+        common.extend_from_slice(&size.to_le_bytes()); // uncompressed
+        common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes()); // extra field
+
+        archive.extend_from_slice(&LOCAL_HEADER_SIG.to_le_bytes());
+        archive.extend_from_slice(&common);
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(contents);
+
+        central_dir.extend_from_slice(&CENTRAL_HEADER_SIG.to_le_bytes());
+        central_dir.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+        central_dir.extend_from_slice(&common);
+        central_dir.extend_from_slice(&0u16.to_le_bytes()); // comment
+        central_dir.extend_from_slice(&0u16.to_le_bytes()); // disk
+        central_dir.extend_from_slice(&0u16.to_le_bytes()); // internal attr
+        central_dir.extend_from_slice(&0u32.to_le_bytes()); // external attr
+        central_dir.extend_from_slice(&offset.to_le_bytes());
+        central_dir.extend_from_slice(name.as_bytes());
+    }
+    let central_dir_offset = to_u32(archive.len())?;
+    let central_dir_size = to_u32(central_dir.len())?;
+    archive.extend_from_slice(&central_dir);
+    let nb_files = (files.len() as u16).to_le_bytes();
+    archive.extend_from_slice(&END_OF_CENTRAL_DIR_SIG.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes()); // disk
+    archive.extend_from_slice(&0u16.to_le_bytes()); // central dir disk
+    archive.extend_from_slice(&nb_files);
+    archive.extend_from_slice(&nb_files);
+    archive.extend_from_slice(&central_dir_size.to_le_bytes());
+    archive.extend_from_slice(&central_dir_offset.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes()); // comment
+    Ok(archive)
+}
+
+/// Extract the files of a zip archive, as `(name, contents)` pairs
+fn read_zip_entries(archive: &[u8]) -> Result<Vec<(String, Vec<u8>)>, IoError> {
+    // The end of central directory record is followed by a comment of at
+    // most 65535 bytes
+    const EOCD_LEN: usize = 22;
+    let search_start = archive.len().saturating_sub(EOCD_LEN + 0xFFFF);
+    let eocd = (search_start..=archive.len().saturating_sub(EOCD_LEN))
+        .rev()
+        .find(|&pos| {
+            read_u32(archive, pos).ok() == Some(END_OF_CENTRAL_DIR_SIG)
+        })
+        .ok_or(IoError::BadNpzFile)?;
+    let nb_files = read_u16(archive, eocd + 10)? as usize;
+    let mut pos = read_u32(archive, eocd + 16)? as usize;
+    if nb_files == 0xFFFF || pos == 0xFFFF_FFFF {
+        // zip64 archives are only needed for huge matrices
+        return Err(IoError::UnsupportedNpzFormat);
+    }
+
+    let mut entries = Vec::with_capacity(nb_files);
+    for _ in 0..nb_files {
+        if read_u32(archive, pos)? != CENTRAL_HEADER_SIG {
+            return Err(IoError::BadNpzFile);
+        }
+        let method = read_u16(archive, pos + 10)?;
+        let crc = read_u32(archive, pos + 16)?;
+        let mut compressed_size = u64::from(read_u32(archive, pos + 20)?);
+        let mut size = u64::from(read_u32(archive, pos + 24)?);
+        let name_len = read_u16(archive, pos + 28)? as usize;
+        let extra_len = read_u16(archive, pos + 30)? as usize;
+        let comment_len = read_u16(archive, pos + 32)? as usize;
+        let mut offset = u64::from(read_u32(archive, pos + 42)?);
+        let name = archive
+            .get(pos + 46..pos + 46 + name_len)
+            .ok_or(IoError::BadNpzFile)?;
+        let name = String::from_utf8_lossy(name).into_owned();
+
+        // Values that do not fit in 32 bits are stored in the zip64 extra
+        // field, numpy always writing this field for its entries
+        let mut extra_pos = pos + 46 + name_len;
+        let extra_end = extra_pos + extra_len;
+        while extra_pos + 4 <= extra_end {
+            let id = read_u16(archive, extra_pos)?;
+            let len = read_u16(archive, extra_pos + 2)? as usize;
+            if id == 1 {
+                let mut field = extra_pos + 4;
+                for val in &mut [&mut size, &mut compressed_size, &mut offset] {
+                    if **val == 0xFFFF_FFFF {
+                        **val = read_u64(archive, field)?;
+                        field += 8;
+                    }
+                }
+            }
+            extra_pos += 4 + len;
+        }
+        pos = extra_end + comment_len;
+
+        let offset =
+            usize::try_from(offset).map_err(|_| IoError::BadNpzFile)?;
+        if read_u32(archive, offset)? != LOCAL_HEADER_SIG {
+            return Err(IoError::BadNpzFile);
+        }
+        let local_name_len = read_u16(archive, offset + 26)? as usize;
+        let local_extra_len = read_u16(archive, offset + 28)? as usize;
+        let start = offset
+            .checked_add(30 + local_name_len + local_extra_len)
+            .ok_or(IoError::BadNpzFile)?;
+        let end = usize::try_from(compressed_size)
+            .ok()
+            .and_then(|size| start.checked_add(size))
+            .ok_or(IoError::BadNpzFile)?;
+        let raw = archive.get(start..end).ok_or(IoError::BadNpzFile)?;
+        let contents = match method {
+            STORED => raw.to_vec(),
+            DEFLATED => miniz_oxide::inflate::decompress_to_vec(raw)
+                .map_err(|_| IoError::BadNpzFile)?,
+            _ => return Err(IoError::UnsupportedNpzFormat),
+        };
+        if contents.len() as u64 != size || crc32(&contents) != crc {
+            return Err(IoError::BadNpzFile);
+        }
+        entries.push((name, contents));
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{read_npz, write_npz};
+    use crate::io::IoError;
+    use crate::test_data::{mat1, mat1_csc};
+    use crate::CsMat;
+    use num_complex::Complex64;
+    use tempfile::tempdir;
+
+    #[test]
+    fn crc32() {
+        assert_eq!(super::crc32(b""), 0);
+        assert_eq!(super::crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn npy_header() {
+        let npy = super::npy_array(&[1i64, 2], &[2]);
+        assert_eq!(npy.len(), 128 + 16);
+        let header = std::str::from_utf8(&npy[10..128]).unwrap();
+        assert!(header.starts_with(
+            "{'descr': '<i8', 'fortran_order': False, 'shape': (2,), }"
+        ));
+        assert!(header.ends_with(" \n"));
+        let array = super::parse_npy(&npy).unwrap();
+        assert_eq!(array.descr, "<i8");
+        assert_eq!(array.shape, vec![2]);
+        assert_eq!(super::read_index_array(&array).unwrap(), vec![1, 2]);
+
+        // the data has to match the shape and dtype exactly
+        let mut truncated = npy.clone();
+        truncated.pop();
+        let array = super::parse_npy(&truncated).unwrap();
+        assert_eq!(super::read_index_array(&array), Err(IoError::BadNpzFile));
+        let mut trailing = npy;
+        trailing.push(0);
+        let array = super::parse_npy(&trailing).unwrap();
+        assert_eq!(super::read_index_array(&array), Err(IoError::BadNpzFile));
+        assert_eq!(array.checked_data(8), Err(IoError::BadNpzFile));
+    }
+
+    #[test]
+    fn zip_entry_size_overflow() {
+        // a local header, followed by a central directory entry whose
+        // compressed size, stored in the zip64 extra field, is u64::MAX
+        let mut archive = vec![0; 30];
+        archive[..4].copy_from_slice(&super::LOCAL_HEADER_SIG.to_le_bytes());
+        let central_dir = archive.len();
+        let mut entry = vec![0; 46];
+        entry[..4].copy_from_slice(&super::CENTRAL_HEADER_SIG.to_le_bytes());
+        entry[20..28].copy_from_slice(&[0xFF; 8]);
+        entry[30..32].copy_from_slice(&20u16.to_le_bytes());
+        entry.extend_from_slice(&1u16.to_le_bytes());
+        entry.extend_from_slice(&16u16.to_le_bytes());
+        entry.extend_from_slice(&[0xFF; 16]);
+        archive.extend_from_slice(&entry);
+        let mut eocd = vec![0; 22];
+        eocd[..4].copy_from_slice(&super::END_OF_CENTRAL_DIR_SIG.to_le_bytes());
+        eocd[10..12].copy_from_slice(&1u16.to_le_bytes());
+        eocd[16..20].copy_from_slice(&(central_dir as u32).to_le_bytes());
+        archive.extend_from_slice(&eocd);
+        assert_eq!(super::read_zip_entries(&archive), Err(IoError::BadNpzFile));
+    }
+
+    #[test]
+    fn zip_entry_offset_overflow() {
+        // a central directory entry whose local header offset, stored in
+        // the zip64 extra field, is u64::MAX
+        let mut entry = vec![0; 46];
+        entry[..4].copy_from_slice(&super::CENTRAL_HEADER_SIG.to_le_bytes());
+        entry[30..32].copy_from_slice(&12u16.to_le_bytes());
+        entry[42..46].copy_from_slice(&[0xFF; 4]);
+        entry.extend_from_slice(&1u16.to_le_bytes());
+        entry.extend_from_slice(&8u16.to_le_bytes());
+        entry.extend_from_slice(&[0xFF; 8]);
+        let mut archive = entry;
+        let mut eocd = vec![0; 22];
+        eocd[..4].copy_from_slice(&super::END_OF_CENTRAL_DIR_SIG.to_le_bytes());
+        eocd[10..12].copy_from_slice(&1u16.to_le_bytes());
+        archive.extend_from_slice(&eocd);
+        assert_eq!(super::read_zip_entries(&archive), Err(IoError::BadNpzFile));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn npz_round_trip() {
+        let tmp_dir = tempdir().unwrap();
+        let path = tmp_dir.path().join("mat.npz");
+        for mat in &[mat1(), mat1_csc()] {
+            write_npz(&path, mat).unwrap();
+            let read: CsMat<f64> = read_npz(&path).unwrap();
+            assert_eq!(&read, mat);
+        }
+        let sliced = mat1();
+        let sliced = sliced.slice_outer(2..4);
+        write_npz(&path, &sliced).unwrap();
+        let read: CsMat<f64> = read_npz(&path).unwrap();
+        assert_eq!(read.to_dense(), sliced.to_dense());
+
+        let mat = CsMat::new(
+            (1, 3),
+            vec![0, 2],
+            vec![0, 2],
+            vec![Complex64::new(1., 2.), Complex64::new(-1., 0.5)],
+        );
+        write_npz(&path, &mat).unwrap();
+        assert_eq!(read_npz(&path), Ok(mat));
+        // the dtype has to match
+        assert_eq!(
+            read_npz::<f64, usize, usize, _>(&path),
+            Err(IoError::UnsupportedNpzFormat)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn read_scipy_npz() {
+        // Written by scipy.sparse.save_npz, which compresses the archive
+        // and uses int32 indices
+        let mat: CsMat<f64> = read_npz("data/npz/scipy_csr.npz").unwrap();
+        assert_eq!(mat, mat1());
+        let mat: CsMat<f64> = read_npz("data/npz/scipy_csc.npz").unwrap();
+        assert_eq!(mat, mat1_csc());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn read_bad_npz() {
+        let res =
+            read_npz::<f64, usize, usize, _>("data/matrix_market/simple.mm");
+        assert_eq!(res, Err(IoError::BadNpzFile));
+    }
+}
@@ -80,6 +80,7 @@ mod dense_vector;
 pub mod errors;
 pub mod indexing;
 pub mod io;
+mod modulus;
 mod mul_acc;
 pub mod num_kinds;
 mod range;
@@ -92,9 +93,12 @@ pub type Ix2 = ndarray::Ix2;
 pub use crate::indexing::SpIndex;
 
 pub use crate::sparse::{
+    bsr::{BsrMat, BsrMatI},
     csmat::CsIter,
+    dia::DiaMat,
     indptr::{IndPtr, IndPtrBase, IndPtrView},
     kronecker::kronecker_product,
+    prod::outer_product,
     CsMat, CsMatBase, CsMatI, CsMatVecView, CsMatView, CsMatViewI,
     CsMatViewMut, CsMatViewMutI, CsStructure, CsStructureI, CsStructureView,
     CsStructureViewI, CsVec, CsVecBase, CsVecI, CsVecView, CsVecViewI,
@@ -103,9 +107,10 @@ pub use crate::sparse::{
 };
 
 pub use crate::dense_vector::{DenseVector, DenseVectorMut};
-pub use crate::mul_acc::MulAcc;
+pub use crate::modulus::Modulus;
+pub use crate::mul_acc::{CheckedMulAcc, MulAcc};
 
-pub use crate::sparse::symmetric::is_symmetric;
+pub use crate::sparse::symmetric::{is_symmetric, SymCsMat, SymCsMatI};
 
 pub use crate::sparse::permutation::{
     perm_is_valid, transform_mat_papt, PermOwned, PermOwnedI, PermView,
@@ -115,8 +120,10 @@ pub use crate::sparse::permutation::{
 pub use crate::sparse::CompressedStorage::{self, CSC, CSR};
 
 pub use crate::sparse::binop;
+pub use crate::sparse::graph;
 pub use crate::sparse::linalg;
 pub use crate::sparse::prod;
+pub use crate::sparse::reorder;
 pub use crate::sparse::smmp;
 pub use crate::sparse::special_mats;
 pub use crate::sparse::visu;
@@ -130,7 +137,10 @@ pub mod vec {
     };
 }
 
-pub use crate::sparse::construct::{bmat, hstack, vstack};
+pub use crate::sparse::construct::{
+    block_diag, bmat, concatenate, hstack, hstack_csvec, spdiags, vstack,
+    vstack_csvec,
+};
 
 pub use crate::sparse::to_dense::assign_to_dense;
 
@@ -168,10 +178,30 @@ pub enum FillInReduction {
 #[cfg(feature = "approx")]
 /// Traits for comparing vectors and matrices using the approx traits
 ///
+/// The sparsity patterns of the compared matrices do not need to match: an
+/// entry stored in only one of them is compared against an implicit zero.
+///
 /// Comparisons of sparse matrices with different storages might be slow.
 /// It is advised to compare using the same storage order for efficiency
 ///
 /// These traits requires the `approx` feature to be activated
+///
+/// # Example
+///
+/// ```rust
+/// use sprs::approx::AbsDiffEq;
+/// use sprs::CsMat;
+/// let a = CsMat::new((2, 2), vec![0, 1, 2], vec![0, 1], vec![1., 2.]);
+/// let b = CsMat::new(
+///     (2, 2),
+///     vec![0, 2, 3],
+///     vec![0, 1, 1],
+///     vec![1. + 1e-9, -1e-10, 2.],
+/// );
+/// assert!(a.abs_diff_eq(&b, 1e-8));
+/// assert!(!a.abs_diff_eq(&b, 1e-12));
+/// assert!(a.abs_diff_eq(&b.to_csc(), 1e-8));
+/// ```
 pub mod approx {
     pub use approx::{AbsDiffEq, RelativeEq, UlpsEq};
 }
@@ -0,0 +1,59 @@
+//! Modulus trait and implementations
+//! This trait gives access to the squared modulus of real and complex
+//! scalars, which is the building block of norm computations.
+
+use num_complex::{Complex32, Complex64};
+
+/// Trait for scalar types having a modulus, such as real or complex
+/// numbers.
+pub trait Modulus {
+    /// The real type in which the modulus is expressed
+    type Real;
+
+    /// The squared modulus of the scalar, ie `x * x` for a real number,
+    /// or `re * re + im * im` for a complex number.
+    fn squared_modulus(&self) -> Self::Real;
+}
+
+macro_rules! real_modulus_impl {
+    ($real: ty) => {
+        impl Modulus for $real {
+            type Real = $real;
+
+            fn squared_modulus(&self) -> $real {
+                self * self
+            }
+        }
+    };
+}
+
+real_modulus_impl!(f32);
+real_modulus_impl!(f64);
+
+macro_rules! complex_modulus_impl {
+    ($complex: ty, $real: ty) => {
+        impl Modulus for $complex {
+            type Real = $real;
+
+            fn squared_modulus(&self) -> $real {
+                self.norm_sqr()
+            }
+        }
+    };
+}
+
+complex_modulus_impl!(Complex32, f32);
+complex_modulus_impl!(Complex64, f64);
+
+#[cfg(test)]
+mod tests {
+    use super::Modulus;
+    use num_complex::Complex64;
+
+    #[test]
+    fn squared_modulus() {
+        assert_eq!((-3f64).squared_modulus(), 9.);
+        assert_eq!(2f32.squared_modulus(), 4.);
+        assert_eq!(Complex64::new(3., -4.).squared_modulus(), 25.);
+    }
+}
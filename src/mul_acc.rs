@@ -29,9 +29,31 @@ where
     }
 }
 
+/// Trait for types that have a multiply-accumulate operation which can
+/// detect overflows, as required by [`checked_mul`] on integer matrices.
+///
+/// [`checked_mul`]: crate::CsMatBase::checked_mul
+pub trait CheckedMulAcc<A = Self, B = A> {
+    /// Multiply and accumulate in this variable, formally `*self += a * b`,
+    /// returning `None` if an overflow occurred. In that case, the value of
+    /// `self` is left unchanged.
+    fn checked_mul_acc(&mut self, a: &A, b: &B) -> Option<()>;
+}
+
+/// Default for types which support checked multiplication and addition
+impl<N> CheckedMulAcc for N
+where
+    N: num_traits::CheckedMul + num_traits::CheckedAdd,
+{
+    fn checked_mul_acc(&mut self, a: &N, b: &N) -> Option<()> {
+        *self = self.checked_add(&a.checked_mul(b)?)?;
+        Some(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::MulAcc;
+    use super::{CheckedMulAcc, MulAcc};
 
     #[test]
     fn mul_acc_f64() {
@@ -59,4 +81,17 @@ mod tests {
         a.mul_acc(&b, &c);
         assert_eq!(a.0, 0x40060007i32);
     }
+
+    #[test]
+    fn checked_mul_acc_i8() {
+        let mut a = 1i8;
+        assert_eq!(a.checked_mul_acc(&2, &3), Some(()));
+        assert_eq!(a, 7);
+        assert_eq!(a.checked_mul_acc(&16, &8), None);
+        assert_eq!(a, 7);
+        assert_eq!(a.checked_mul_acc(&10, &12), Some(()));
+        assert_eq!(a, 127);
+        assert_eq!(a.checked_mul_acc(&1, &1), None);
+        assert_eq!(a, 127);
+    }
 }
@@ -356,6 +356,68 @@ pub(crate) mod utils {
         Ok(())
     }
 
+    /// Check the structure of `CsMat` components, reporting the location
+    /// of the first violation found. The checks are performed in order on
+    /// the indptr, on the number of non-zeros, and then on the indices of
+    /// each outer slice. Remaining checks are delegated to
+    /// [`check_compressed_structure`](check_compressed_structure).
+    pub(crate) fn check_compressed_structure_detailed<
+        I: SpIndex,
+        Iptr: SpIndex,
+    >(
+        inner: usize,
+        outer: usize,
+        indptr: &[Iptr],
+        indices: &[I],
+    ) -> Result<(), StructureError> {
+        if indptr.len() != outer + 1 {
+            return Err(StructureError::SizeMismatch(
+                "Indptr length does not match dimension",
+            ));
+        }
+        let indptr_usize = |i: Iptr| {
+            i.try_index().ok_or(StructureError::OutOfRange(
+                "Indptr value out of range of usize",
+            ))
+        };
+        for (position, w) in indptr.windows(2).enumerate() {
+            if indptr_usize(w[0])? > indptr_usize(w[1])? {
+                return Err(StructureError::DecreasingIndptr { position });
+            }
+        }
+        let indptr_nnz =
+            indptr_usize(indptr[outer])? - indptr_usize(indptr[0])?;
+        if indptr_nnz != indices.len() {
+            return Err(StructureError::IndptrNnzMismatch {
+                indptr_nnz,
+                nnz: indices.len(),
+            });
+        }
+        let offset = indptr[0].index();
+        for (outer_ind, w) in indptr.windows(2).enumerate() {
+            let range = w[0].index() - offset..w[1].index() - offset;
+            let indices = &indices[range];
+            for i in indices {
+                let index = i.try_index().ok_or(StructureError::OutOfRange(
+                    "Indices value out of range of usize",
+                ))?;
+                if index >= inner {
+                    return Err(StructureError::IndexOutOfBounds {
+                        outer: outer_ind,
+                        index,
+                        bound: inner,
+                    });
+                }
+            }
+            if !sorted_indices(indices) {
+                return Err(StructureError::UnsortedIndices {
+                    outer: outer_ind,
+                });
+            }
+        }
+        check_compressed_structure(inner, outer, indptr, indices)
+    }
+
     pub fn sorted_indices<I: SpIndex>(indices: &[I]) -> bool {
         for w in indices.windows(2) {
             // w will always be a size 2
@@ -413,14 +475,18 @@ pub(crate) mod utils {
 }
 
 pub mod binop;
+pub mod bsr;
 pub mod compressed;
 pub mod construct;
 pub mod csmat;
+pub mod dia;
+pub mod graph;
 pub mod indptr;
 pub mod kronecker;
 pub mod linalg;
 pub mod permutation;
 pub mod prod;
+pub mod reorder;
 pub mod slicing;
 pub mod smmp;
 pub mod special_mats;
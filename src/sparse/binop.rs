@@ -2,10 +2,10 @@
 
 use std::ops::{Add, Deref, Mul, Sub};
 
-use crate::errors::StructureError;
+use crate::errors::{SprsError, StructureError};
 use crate::indexing::SpIndex;
 use crate::sparse::compressed::SpMatView;
-use crate::sparse::csmat::CompressedStorage;
+use crate::sparse::csmat::{raw, CompressedStorage};
 use crate::sparse::prelude::*;
 use crate::sparse::vec::NnzEither::{Both, Left, Right};
 use crate::sparse::vec::SparseIterTools;
@@ -17,6 +17,9 @@ use num_traits::Num;
 
 use crate::Ix2;
 
+/// Add two sparse matrices of the same shape, which may have different
+/// storages. The result has the storage of the left operand, the right
+/// operand being converted if its storage differs.
 impl<
         'a,
         'b,
@@ -64,6 +67,9 @@ where
     }
 }
 
+/// Subtract two sparse matrices of the same shape, which may have different
+/// storages. The result has the storage of the left operand, the right
+/// operand being converted if its storage differs.
 impl<
         'a,
         'b,
@@ -111,6 +117,186 @@ where
     }
 }
 
+impl<Lhs, I, Iptr, IpStorage, IStorage, DStorage>
+    CsMatBase<Lhs, I, IpStorage, IStorage, DStorage, Iptr>
+where
+    I: SpIndex,
+    Iptr: SpIndex,
+    IpStorage: Deref<Target = [Iptr]>,
+    IStorage: Deref<Target = [I]>,
+    DStorage: Deref<Target = [Lhs]>,
+{
+    /// Applies a binary operation to matching elements of two sparse
+    /// matrices, iterating over the union of their non-zero patterns. When
+    /// only one of the matrices has a non-zero at a given location, `0` is
+    /// inferred for the value of the other matrix.
+    ///
+    /// If `prune` is `true`, the entries for which `binop` returns zero are
+    /// not stored in the result. Otherwise the result has exactly the union
+    /// of the non-zero patterns of the two matrices.
+    ///
+    /// The result has the storage of `self`, `rhs` is converted if needed.
+    ///
+    /// # Panics
+    ///
+    /// - if `self` and `rhs` do not have the same shape
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let a = CsMat::new((2, 2), vec![0, 1, 2], vec![0, 1], vec![1, -3]);
+    /// let b = CsMat::new((2, 2), vec![0, 1, 2], vec![1, 1], vec![2, 5]);
+    /// let max = a.binop(&b, |x, y| *x.max(y), false);
+    /// let expected =
+    ///     CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 1], vec![1, 2, 5]);
+    /// assert_eq!(max, expected);
+    /// ```
+    pub fn binop<Rhs, Res, IpS2, IS2, DS2, F>(
+        &self,
+        rhs: &CsMatBase<Rhs, I, IpS2, IS2, DS2, Iptr>,
+        binop: F,
+        prune: bool,
+    ) -> CsMatI<Res, I, Iptr>
+    where
+        Lhs: num_traits::Zero + PartialEq + Clone,
+        Rhs: num_traits::Zero + PartialEq + Clone,
+        Res: num_traits::Zero + PartialEq + Clone,
+        IpS2: Deref<Target = [Iptr]>,
+        IS2: Deref<Target = [I]>,
+        DS2: Deref<Target = [Rhs]>,
+        F: Fn(&Lhs, &Rhs) -> Res,
+    {
+        if self.shape() != rhs.shape() {
+            panic!("Dimension mismatch");
+        }
+        if self.storage() != rhs.storage() {
+            // Converted by hand as to_other_storage requires Rhs: Default
+            let mut indptr = vec![Iptr::zero(); rhs.inner_dims() + 1];
+            let mut indices = vec![I::zero(); rhs.nnz()];
+            let mut data = vec![Rhs::zero(); rhs.nnz()];
+            raw::convert_mat_storage(
+                rhs.view(),
+                &mut indptr,
+                &mut indices,
+                &mut data,
+            );
+            let rhs = CsMatI::new_trusted(
+                self.storage(),
+                rhs.shape(),
+                indptr,
+                indices,
+                data,
+            );
+            return csmat_binop_impl(self.view(), rhs.view(), binop, prune);
+        }
+        csmat_binop_impl(self.view(), rhs.view(), binop, prune)
+    }
+
+    /// Compute the element-wise (Hadamard) product of two sparse matrices.
+    ///
+    /// As the product vanishes wherever one of the operands is zero, the
+    /// pattern of the result is the intersection of the patterns of the two
+    /// matrices, which is cheaper to compute than the union traversed by
+    /// `binop`. The result has sorted inner indices.
+    ///
+    /// # Panics
+    ///
+    /// - on incompatible dimensions
+    /// - on incompatible storage
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let a = CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 1], vec![1, 2, 3]);
+    /// let b = CsMat::new((2, 2), vec![0, 1, 2], vec![1, 0], vec![4, 5]);
+    /// let prod = a.hadamard(&b.view());
+    /// let expected = CsMat::new((2, 2), vec![0, 1, 1], vec![1], vec![8]);
+    /// assert_eq!(prod, expected);
+    /// ```
+    pub fn hadamard(
+        &self,
+        other: &CsMatViewI<Lhs, I, Iptr>,
+    ) -> CsMatI<Lhs, I, Iptr>
+    where
+        Lhs: Mul<Output = Lhs> + Clone,
+    {
+        assert_eq!(self.shape(), other.shape(), "Dimension mismatch");
+        assert_eq!(self.storage(), other.storage(), "Storage mismatch");
+        let max_nnz = self.nnz().min(other.nnz());
+        let mut indptr = Vec::with_capacity(self.outer_dims() + 1);
+        let mut indices = Vec::with_capacity(max_nnz);
+        let mut data = Vec::with_capacity(max_nnz);
+        indptr.push(Iptr::zero());
+        for (lvec, rvec) in self.outer_iterator().zip(other.outer_iterator()) {
+            for (ind, lval, rval) in lvec.iter().nnz_zip(rvec.iter()) {
+                indices.push(I::from_usize(ind));
+                data.push(lval.clone() * rval.clone());
+            }
+            indptr.push(Iptr::from_usize(indices.len()));
+        }
+        CsMatI::new_trusted(self.storage(), self.shape(), indptr, indices, data)
+    }
+}
+
+impl<N, I: SpIndex, Iptr: SpIndex> CsMatI<N, I, Iptr> {
+    /// Compute `self += alpha * other`, in place.
+    ///
+    /// The pattern of `self` becomes the union of the patterns of the two
+    /// matrices, so new non-zeros coming from `other` make the storage of
+    /// `self` grow. The entries cancelling out are kept as explicit zeros,
+    /// which ensures the pattern does not depend on the values, eg when
+    /// assembling an operator repeatedly.
+    ///
+    /// The result keeps the storage of `self`, `other` is converted if
+    /// needed.
+    ///
+    /// Returns an error if the shapes of the matrices differ, in which case
+    /// `self` is left unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let mut a = CsMat::new((2, 2), vec![0, 1, 2], vec![0, 1], vec![1., 2.]);
+    /// let b = CsMat::new((2, 2), vec![0, 1, 2], vec![1, 1], vec![3., 4.]);
+    /// a.scaled_add(0.5, &b.view()).unwrap();
+    /// let expected = CsMat::new(
+    ///     (2, 2),
+    ///     vec![0, 2, 3],
+    ///     vec![0, 1, 1],
+    ///     vec![1., 1.5, 4.],
+    /// );
+    /// assert_eq!(a, expected);
+    /// ```
+    pub fn scaled_add(
+        &mut self,
+        alpha: N,
+        other: &CsMatViewI<N, I, Iptr>,
+    ) -> Result<(), SprsError>
+    where
+        N: crate::MulAcc + num_traits::Zero + PartialEq + Clone,
+    {
+        if self.shape() != other.shape() {
+            return Err(
+                StructureError::SizeMismatch("Dimension mismatch").into()
+            );
+        }
+        let res = self.binop(
+            other,
+            |x, y| {
+                let mut res = x.clone();
+                res.mul_acc(&alpha, y);
+                res
+            },
+            false,
+        );
+        *self = res;
+        Ok(())
+    }
+}
+
 /// Sparse matrix scalar multiplication, with same storage type
 pub fn mul_mat_same_storage<Lhs, Rhs, Res, I, Iptr, Mat1, Mat2>(
     lhs: &Mat1,
@@ -178,6 +364,23 @@ pub fn csmat_binop<Lhs, Rhs, Res, I, Iptr, F>(
     rhs: CsMatViewI<Rhs, I, Iptr>,
     binop: F,
 ) -> CsMatI<Res, I, Iptr>
+where
+    Lhs: num_traits::Zero + PartialEq + Clone,
+    Rhs: num_traits::Zero + PartialEq + Clone,
+    Res: num_traits::Zero + PartialEq + Clone,
+    I: SpIndex,
+    Iptr: SpIndex,
+    F: Fn(&Lhs, &Rhs) -> Res,
+{
+    csmat_binop_impl(lhs, rhs, binop, true)
+}
+
+fn csmat_binop_impl<Lhs, Rhs, Res, I, Iptr, F>(
+    lhs: CsMatViewI<Lhs, I, Iptr>,
+    rhs: CsMatViewI<Rhs, I, Iptr>,
+    binop: F,
+    prune: bool,
+) -> CsMatI<Res, I, Iptr>
 where
     Lhs: num_traits::Zero + PartialEq + Clone,
     Rhs: num_traits::Zero + PartialEq + Clone,
@@ -201,13 +404,14 @@ where
     let mut out_indices = vec![I::zero(); max_nnz];
     let mut out_data = vec![Res::zero(); max_nnz];
 
-    let nnz = csmat_binop_same_storage_raw(
+    let nnz = csmat_binop_same_storage_raw_impl(
         lhs,
         rhs,
         binop,
         &mut out_indptr[..],
         &mut out_indices[..],
         &mut out_data[..],
+        prune,
     );
     out_indices.truncate(nnz);
     out_data.truncate(nnz);
@@ -233,6 +437,34 @@ pub fn csmat_binop_same_storage_raw<Lhs, Rhs, Res, I, Iptr, F>(
     out_indices: &mut [I],
     out_data: &mut [Res],
 ) -> usize
+where
+    Lhs: num_traits::Zero + PartialEq,
+    Rhs: num_traits::Zero + PartialEq,
+    Res: num_traits::Zero + PartialEq,
+    I: SpIndex,
+    Iptr: SpIndex,
+    F: Fn(&Lhs, &Rhs) -> Res,
+{
+    csmat_binop_same_storage_raw_impl(
+        lhs,
+        rhs,
+        binop,
+        out_indptr,
+        out_indices,
+        out_data,
+        true,
+    )
+}
+
+fn csmat_binop_same_storage_raw_impl<Lhs, Rhs, Res, I, Iptr, F>(
+    lhs: CsMatViewI<Lhs, I, Iptr>,
+    rhs: CsMatViewI<Rhs, I, Iptr>,
+    binop: F,
+    out_indptr: &mut [Iptr],
+    out_indices: &mut [I],
+    out_data: &mut [Res],
+    prune: bool,
+) -> usize
 where
     Lhs: num_traits::Zero + PartialEq,
     Rhs: num_traits::Zero + PartialEq,
@@ -258,7 +490,7 @@ where
                 Right((ind, val)) => (ind, binop(&Lhs::zero(), val)),
                 Both((ind, lval, rval)) => (ind, binop(lval, rval)),
             };
-            if binop_val != Res::zero() {
+            if !prune || binop_val != Res::zero() {
                 out_indices[nnz] = I::from_usize_unchecked(ind);
                 out_data[nnz] = binop_val;
                 nnz += 1;
@@ -481,6 +713,7 @@ fn csvec_fix_zeros<Lhs, Rhs, I: SpIndex>(
 
 #[cfg(test)]
 mod test {
+    use crate::errors::{SprsError, StructureError};
     use crate::sparse::CsMat;
     use crate::sparse::CsVec;
     use crate::test_data::{mat1, mat1_times_2, mat2, mat_dense1};
@@ -532,6 +765,43 @@ mod test {
         assert_eq!(c, &a + &b);
     }
 
+    #[test]
+    fn add_sub_mixed_storage() {
+        let a = mat1();
+        let b = mat2();
+        let sum = &a + &b.to_csc();
+        assert!(sum.is_csr());
+        assert_eq!(sum, mat1_plus_mat2());
+        let sum = &a.to_csc() + &b;
+        assert!(sum.is_csc());
+        assert_eq!(sum, mat1_plus_mat2().to_csc());
+        let sum = &a.view() + &b.to_csc().view();
+        assert_eq!(sum, mat1_plus_mat2());
+
+        let diff = &a - &b.to_csc();
+        assert!(diff.is_csr());
+        assert_eq!(diff, mat1_minus_mat2());
+        let diff = &a.to_csc() - &b.view();
+        assert!(diff.is_csc());
+        assert_eq!(diff, mat1_minus_mat2().to_csc());
+
+        // rectangular matrices
+        let a = CsMat::new((2, 3), vec![0, 2, 3], vec![0, 2, 1], vec![1, 2, 4]);
+        let b = CsMat::new((2, 3), vec![0, 2, 2], vec![0, 2], vec![3, 2]);
+        let expected =
+            CsMat::new((2, 3), vec![0, 1, 2], vec![0, 1], vec![-2, 4]);
+        assert_eq!(&a - &b.to_csc(), expected);
+        assert_eq!(&a.to_csc() - &b, expected.to_csc());
+    }
+
+    #[test]
+    #[should_panic(expected = "Dimension mismatch")]
+    fn add_mixed_storage_fail_dim() {
+        let a = mat1();
+        let b = CsMat::<f64>::zero((5, 4)).into_csc();
+        let _ = &a + &b;
+    }
+
     #[test]
     fn test_sub1() {
         let a = mat1();
@@ -542,6 +812,92 @@ mod test {
         assert_eq!(c, c_true);
     }
 
+    #[test]
+    fn binop_closure() {
+        let a = mat1();
+        let b = mat2();
+
+        let c = a.binop(&b, |x, y| x + y, true);
+        assert_eq!(c, mat1_plus_mat2());
+        let c = a.binop(&b.to_csc(), |x, y| x - y, true);
+        assert_eq!(c, mat1_minus_mat2());
+
+        // | 1 0 2 |   | 3 0 2 |
+        // | 0 4 0 | , | 0 0 0 |
+        let a = CsMat::new((2, 3), vec![0, 2, 3], vec![0, 2, 1], vec![1, 2, 4]);
+        let b = CsMat::new((2, 3), vec![0, 2, 2], vec![0, 2], vec![3, 2]);
+        let max = a.binop(&b, |x, y| *x.max(y), false);
+        let expected =
+            CsMat::new((2, 3), vec![0, 2, 3], vec![0, 2, 1], vec![3, 2, 4]);
+        assert_eq!(max, expected);
+
+        let diff = a.binop(&b, |x, y| x - y, false);
+        let expected =
+            CsMat::new((2, 3), vec![0, 2, 3], vec![0, 2, 1], vec![-2, 0, 4]);
+        assert_eq!(diff, expected);
+        let diff = a.binop(&b, |x, y| x - y, true);
+        let expected =
+            CsMat::new((2, 3), vec![0, 1, 2], vec![0, 1], vec![-2, 4]);
+        assert_eq!(diff, expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "Dimension mismatch")]
+    fn binop_fail_dimension_mismatch() {
+        let a = mat1();
+        let b = CsMat::<f64>::eye_csc(4);
+        let _ = a.binop(&b, |x, y| x + y, true);
+    }
+
+    #[test]
+    fn hadamard() {
+        let a = mat1();
+        let b = mat2();
+        let c = a.hadamard(&b.view());
+        assert_eq!(c, mat1_times_mat2());
+        assert!(c.is_sorted());
+        let dense = &a.to_dense() * &b.to_dense();
+        assert_eq!(c.to_dense(), dense);
+        let c = a.to_csc().hadamard(&b.to_csc().view());
+        assert_eq!(c.to_dense(), dense);
+        assert_eq!(c.storage(), crate::CSC);
+    }
+
+    #[test]
+    #[should_panic]
+    fn hadamard_storage_mismatch() {
+        let a = mat1();
+        let _ = a.hadamard(&mat2().to_csc().view());
+    }
+
+    #[test]
+    fn scaled_add() {
+        let a = mat1();
+        let b = mat2();
+        let expected = &a + &b.map(|x| 2. * x);
+        let mut c = a.clone();
+        c.scaled_add(2., &b.view()).unwrap();
+        assert_eq!(c, expected);
+        let mut c = a.clone();
+        c.scaled_add(2., &b.to_csc().view()).unwrap();
+        assert!(c.is_csr());
+        assert_eq!(c, expected);
+        // cancelling entries are kept as explicit zeros
+        let mut c = a.clone();
+        c.scaled_add(-1., &a.view()).unwrap();
+        assert_eq!(c.nnz(), a.nnz());
+        assert!(c.data().iter().all(|&x| x == 0.));
+        let mut c = a.clone();
+        let res = c.scaled_add(1., &CsMat::zero((5, 4)).view());
+        assert_eq!(
+            res.unwrap_err(),
+            SprsError::Structure(StructureError::SizeMismatch(
+                "Dimension mismatch"
+            ))
+        );
+        assert_eq!(c, a);
+    }
+
     #[test]
     fn test_mul1() {
         let a = mat1();
@@ -0,0 +1,341 @@
+//! Block sparse row (BSR) matrices
+//!
+//! In the BSR format, a matrix is partitioned into dense blocks of a fixed
+//! shape `(R, C)`, and only the blocks containing non-zero values are
+//! stored. The block structure is compressed along the block rows, as for
+//! a CSR matrix. This format is well suited to matrices arising from
+//! the discretization of vector valued problems, where each unknown is made
+//! of a small number of coupled components.
+
+use ndarray::{Array, ArrayView1, Ix1};
+use num_traits::{Num, Zero};
+use std::ops::Deref;
+
+use crate::errors::StructureError;
+use crate::indexing::SpIndex;
+use crate::sparse::{CsMatBase, CsMatI};
+use crate::{MulAcc, CSR};
+
+/// A sparse matrix in the block sparse row format.
+///
+/// The non-zero blocks are stored in `data`, each block occupying
+/// `R * C` consecutive values in row-major order. The block column of the
+/// `k`-th block is `indices[k]`, and the blocks of block row `i` are the
+/// blocks `indptr[i]..indptr[i + 1]`, sorted by block column.
+///
+/// The block shape is a runtime value rather than const generic parameters,
+/// as const generics need a more recent compiler than the minimum supported
+/// Rust version of this crate (1.49). This also lets the block shape be
+/// picked from the problem at hand, eg the number of components of a field
+/// read from a file, at the price of dense block operations whose size
+/// is unknown at compile time.
+#[derive(PartialEq, Debug, Clone)]
+pub struct BsrMatI<N, I: SpIndex, Iptr: SpIndex = I> {
+    nrows: usize,
+    ncols: usize,
+    block_shape: (usize, usize),
+    indptr: Vec<Iptr>,
+    indices: Vec<I>,
+    data: Vec<N>,
+}
+
+/// A block sparse row matrix using `usize` indices, see `BsrMatI`
+pub type BsrMat<N> = BsrMatI<N, usize>;
+
+impl<N, I: SpIndex, Iptr: SpIndex> BsrMatI<N, I, Iptr> {
+    /// The number of rows of the matrix
+    pub fn rows(&self) -> usize {
+        self.nrows
+    }
+
+    /// The number of cols of the matrix
+    pub fn cols(&self) -> usize {
+        self.ncols
+    }
+
+    /// The shape of the matrix, as a `(rows, cols)` tuple
+    pub fn shape(&self) -> (usize, usize) {
+        (self.nrows, self.ncols)
+    }
+
+    /// The shape of the dense blocks, as a `(rows, cols)` tuple
+    pub fn block_shape(&self) -> (usize, usize) {
+        self.block_shape
+    }
+
+    /// The number of stored blocks
+    pub fn nnz_blocks(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// The number of stored values, ie the number of stored blocks times
+    /// the size of a block.
+    pub fn nnz(&self) -> usize {
+        self.data.len()
+    }
+
+    /// The block index pointer, of length `rows() / block_shape().0 + 1`
+    pub fn indptr(&self) -> &[Iptr] {
+        &self.indptr
+    }
+
+    /// The block column of each stored block
+    pub fn indices(&self) -> &[I] {
+        &self.indices
+    }
+
+    /// The values of the stored blocks
+    pub fn data(&self) -> &[N] {
+        &self.data
+    }
+
+    /// The values of the `k`-th stored block, in row-major order
+    ///
+    /// # Panics
+    ///
+    /// If `k >= self.nnz_blocks()`
+    pub fn block(&self, k: usize) -> &[N] {
+        let block_size = self.block_shape.0 * self.block_shape.1;
+        &self.data[k * block_size..(k + 1) * block_size]
+    }
+
+    /// Compute the product of this matrix with the dense vector `x`, by
+    /// performing a small dense matrix-vector product for each block.
+    ///
+    /// # Panics
+    ///
+    /// If the length of `x` does not match the number of columns.
+    pub fn matvec(&self, x: ArrayView1<N>) -> Array<N, Ix1>
+    where
+        N: Num + Clone + MulAcc,
+    {
+        assert_eq!(self.cols(), x.len(), "Dimension mismatch");
+        let (block_rows, block_cols) = self.block_shape;
+        let mut y = Array::from_elem(self.rows(), N::zero());
+        for (brow, w) in self.indptr.windows(2).enumerate() {
+            let y_block = brow * block_rows;
+            for k in w[0].index()..w[1].index() {
+                let x_block = self.indices[k].index() * block_cols;
+                let block = self.block(k);
+                for (r, block_row) in block.chunks(block_cols).enumerate() {
+                    let y_r = &mut y[y_block + r];
+                    for (c, val) in block_row.iter().enumerate() {
+                        y_r.mul_acc(val, &x[x_block + c]);
+                    }
+                }
+            }
+        }
+        y
+    }
+
+    /// Convert this matrix to the CSR format. The zero values inside
+    /// the stored blocks are not kept.
+    pub fn to_csr(&self) -> CsMatI<N, I, Iptr>
+    where
+        N: Zero + Clone,
+    {
+        let (block_rows, block_cols) = self.block_shape;
+        let mut indptr = Vec::with_capacity(self.nrows + 1);
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        indptr.push(Iptr::zero());
+        for w in self.indptr.windows(2) {
+            for r in 0..block_rows {
+                for k in w[0].index()..w[1].index() {
+                    let col_block = self.indices[k].index() * block_cols;
+                    let row = &self.block(k)[r * block_cols..][..block_cols];
+                    for (c, val) in row.iter().enumerate() {
+                        if !val.is_zero() {
+                            indices.push(I::from_usize(col_block + c));
+                            data.push(val.clone());
+                        }
+                    }
+                }
+                indptr.push(Iptr::from_usize(indices.len()));
+            }
+        }
+        CsMatI::new_trusted(CSR, self.shape(), indptr, indices, data)
+    }
+}
+
+/// # Conversion to the block sparse row format
+impl<N, I, Iptr, IptrStorage, IndStorage, DataStorage>
+    CsMatBase<N, I, IptrStorage, IndStorage, DataStorage, Iptr>
+where
+    I: SpIndex,
+    Iptr: SpIndex,
+    IptrStorage: Deref<Target = [Iptr]>,
+    IndStorage: Deref<Target = [I]>,
+    DataStorage: Deref<Target = [N]>,
+{
+    /// Convert this matrix to the block sparse row format, with blocks
+    /// of shape `block_shape`. A block is stored as soon as one of its
+    /// values is stored in this matrix. See [`BsrMatI`] for why the block
+    /// shape is not a compile time parameter.
+    ///
+    /// Returns an error if the shape of the matrix is not a multiple of
+    /// the block shape.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// use ndarray::arr1;
+    /// let mat = CsMat::new(
+    ///     (4, 4),
+    ///     vec![0, 2, 3, 4, 5],
+    ///     vec![0, 1, 0, 2, 3],
+    ///     vec![1., 2., 3., 4., 5.],
+    /// );
+    /// let bsr = mat.to_bsr((2, 2)).unwrap();
+    /// assert_eq!(bsr.nnz_blocks(), 2);
+    /// assert_eq!(bsr.block(0), &[1., 2., 3., 0.]);
+    /// let x = arr1(&[1., 2., 3., 4.]);
+    /// assert_eq!(bsr.matvec(x.view()), &mat * &x);
+    /// assert!(mat.to_bsr((3, 2)).is_err());
+    /// ```
+    pub fn to_bsr(
+        &self,
+        block_shape: (usize, usize),
+    ) -> Result<BsrMatI<N, I, Iptr>, StructureError>
+    where
+        N: Zero + Clone,
+    {
+        let (block_rows, block_cols) = block_shape;
+        // checked_rem also rejects empty blocks
+        if self.rows().checked_rem(block_rows) != Some(0)
+            || self.cols().checked_rem(block_cols) != Some(0)
+        {
+            return Err(StructureError::SizeMismatch(
+                "Matrix shape is not a multiple of the block shape",
+            ));
+        }
+        let nb_block_rows = self.rows() / block_rows;
+        let nb_block_cols = self.cols() / block_cols;
+        let block_size = block_rows * block_cols;
+
+        // Gather the non-zeros of each block row, regardless of the storage
+        let mut block_row_entries = vec![Vec::new(); nb_block_rows];
+        for (val, (row, col)) in self.iter() {
+            let (row, col) = (row.index(), col.index());
+            block_row_entries[row / block_rows].push((row, col, val));
+        }
+
+        let mut indptr = Vec::with_capacity(nb_block_rows + 1);
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        indptr.push(Iptr::zero());
+        // position of each block column in the current block row
+        let mut block_pos = vec![usize::MAX; nb_block_cols];
+        for entries in &block_row_entries {
+            let start = indices.len();
+            for &(_, col, _) in entries {
+                let bcol = col / block_cols;
+                if block_pos[bcol] == usize::MAX {
+                    block_pos[bcol] = 0;
+                    indices.push(I::from_usize(bcol));
+                }
+            }
+            indices[start..].sort_unstable();
+            for (k, bcol) in indices[start..].iter().enumerate() {
+                block_pos[bcol.index()] = start + k;
+            }
+            data.resize(indices.len() * block_size, N::zero());
+            for &(row, col, val) in entries {
+                let k = block_pos[col / block_cols];
+                let r = row % block_rows;
+                let c = col % block_cols;
+                data[k * block_size + r * block_cols + c] = val.clone();
+            }
+            for bcol in &indices[start..] {
+                block_pos[bcol.index()] = usize::MAX;
+            }
+            indptr.push(Iptr::from_usize(indices.len()));
+        }
+
+        Ok(BsrMatI {
+            nrows: self.rows(),
+            ncols: self.cols(),
+            block_shape,
+            indptr,
+            indices,
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::errors::StructureErrorKind;
+    use crate::sparse::CsMat;
+    use ndarray::arr1;
+
+    fn mat() -> CsMat<f64> {
+        // | 1 2 . . 3 . |
+        // | . 4 . . . . |
+        // | . . . . 5 6 |
+        // | . . . . 7 . |
+        CsMat::new(
+            (4, 6),
+            vec![0, 3, 4, 6, 7],
+            vec![0, 1, 4, 1, 4, 5, 4],
+            vec![1., 2., 3., 4., 5., 6., 7.],
+        )
+    }
+
+    #[test]
+    fn to_bsr() {
+        let mat = mat();
+        let bsr = mat.to_bsr((2, 2)).unwrap();
+        assert_eq!(bsr.shape(), (4, 6));
+        assert_eq!(bsr.block_shape(), (2, 2));
+        assert_eq!(bsr.indptr(), &[0, 2, 3]);
+        assert_eq!(bsr.indices(), &[0, 2, 2]);
+        assert_eq!(bsr.nnz_blocks(), 3);
+        assert_eq!(bsr.nnz(), 12);
+        assert_eq!(bsr.block(0), &[1., 2., 0., 4.]);
+        assert_eq!(bsr.block(1), &[3., 0., 0., 0.]);
+        assert_eq!(bsr.block(2), &[5., 6., 7., 0.]);
+        assert_eq!(mat.to_csc().to_bsr((2, 2)), Ok(bsr.clone()));
+        assert_eq!(bsr.to_csr(), mat);
+
+        let bsr = mat.to_bsr((1, 3)).unwrap();
+        assert_eq!(bsr.indptr(), &[0, 2, 3, 4, 5]);
+        assert_eq!(bsr.indices(), &[0, 1, 0, 1, 1]);
+        assert_eq!(bsr.to_csr(), mat);
+
+        let bsr = mat.to_bsr((4, 6)).unwrap();
+        assert_eq!(bsr.nnz_blocks(), 1);
+        assert_eq!(bsr.to_csr(), mat);
+    }
+
+    #[test]
+    fn to_bsr_shape_mismatch() {
+        let mat = mat();
+        for &block_shape in &[(3, 2), (2, 4), (0, 2), (2, 0)] {
+            let err = mat.to_bsr(block_shape).unwrap_err();
+            assert_eq!(err.kind(), StructureErrorKind::SizeMismatch);
+        }
+    }
+
+    #[test]
+    fn bsr_matvec() {
+        let mat = mat();
+        let x = arr1(&[1., -1., 2., 0.5, 3., -2.]);
+        let expected = &mat * &x;
+        for &block_shape in &[(1, 1), (2, 2), (2, 3), (4, 1), (4, 6)] {
+            let bsr = mat.to_bsr(block_shape).unwrap();
+            assert_eq!(bsr.matvec(x.view()), expected);
+        }
+    }
+
+    #[test]
+    fn bsr_empty() {
+        let mat = CsMat::<f64>::zero((4, 4));
+        let bsr = mat.to_bsr((2, 2)).unwrap();
+        assert_eq!(bsr.nnz_blocks(), 0);
+        assert_eq!(bsr.indptr(), &[0, 0, 0]);
+        assert_eq!(bsr.matvec(arr1(&[1., 2., 3., 4.]).view()), arr1(&[0.; 4]));
+        assert_eq!(bsr.to_csr(), mat);
+    }
+}
@@ -1,8 +1,11 @@
 //! High level construction of sparse matrices by stacking, by block, ...
 
+use crate::errors::StructureError;
 use crate::indexing::SpIndex;
 use crate::sparse::prelude::*;
-use std::cmp;
+use crate::CompressedStorage;
+use ndarray::Axis;
+use num_traits::Num;
 use std::default::Default;
 
 /// Stack the given matrices into a new one, using the most efficient stacking
@@ -80,6 +83,270 @@ where
     same_storage_fast_stack(&mats_csc_views)
 }
 
+/// Stack matrices sharing the same storage and the same outer dimension
+/// along their inner dimension, ie horizontally for CSR matrices and
+/// vertically for CSC matrices.
+fn same_storage_inner_stack<N, I, Iptr>(
+    mats: &[CsMatViewI<N, I, Iptr>],
+) -> CsMatI<N, I, Iptr>
+where
+    N: Clone,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    let storage = mats[0].storage();
+    let outer_dim = mats[0].outer_dims();
+    debug_assert!(mats.iter().all(|m| m.storage() == storage));
+    debug_assert!(mats.iter().all(|m| m.outer_dims() == outer_dim));
+    let inner_dim = mats.iter().map(CsMatBase::inner_dims).sum::<usize>();
+    let nnz = mats.iter().map(CsMatBase::nnz).sum::<usize>();
+    let mut indptr = Vec::with_capacity(outer_dim + 1);
+    let mut indices = Vec::with_capacity(nnz);
+    let mut data = Vec::with_capacity(nnz);
+    indptr.push(Iptr::zero());
+    for outer in 0..outer_dim {
+        let mut inner_offset = 0;
+        for mat in mats {
+            let vec = mat.outer_view(outer).unwrap();
+            let offset = I::from_usize(inner_offset);
+            indices.extend(vec.indices().iter().map(|&i| i + offset));
+            data.extend_from_slice(vec.data());
+            inner_offset += mat.inner_dims();
+        }
+        indptr.push(Iptr::from_usize(indices.len()));
+    }
+    let shape = match storage {
+        CompressedStorage::CSR => (outer_dim, inner_dim),
+        CompressedStorage::CSC => (inner_dim, outer_dim),
+    };
+    CsMatI::new_trusted(storage, shape, indptr, indices, data)
+}
+
+/// Concatenate matrices along the given axis: `Axis(0)` stacks them
+/// vertically, and `Axis(1)` stacks them horizontally.
+///
+/// When all matrices share the same storage, it is preserved in the
+/// result. Otherwise, the matrices are converted to the storage for which
+/// stacking is the cheapest, ie CSR for vertical stacking and CSC for
+/// horizontal stacking.
+///
+/// Returns an error if `mats` is empty, if `axis` is neither `Axis(0)` nor
+/// `Axis(1)`, or if the matrices do not have the same dimension along the
+/// other axis.
+///
+/// # Example
+///
+/// ```rust
+/// use ndarray::Axis;
+/// use sprs::{concatenate, CsMat};
+/// let a = CsMat::<f64>::eye(2);
+/// let b = CsMat::<f64>::eye_csc(2);
+/// let c = concatenate(Axis(1), &[a.view(), a.view()]).unwrap();
+/// assert_eq!(c.shape(), (2, 4));
+/// assert!(c.is_csr());
+/// let d = concatenate(Axis(0), &[a.view(), b.view()]).unwrap();
+/// assert_eq!(d.shape(), (4, 2));
+/// assert!(concatenate(Axis(0), &[a.view(), c.view()]).is_err());
+/// ```
+pub fn concatenate<'a, N, I, Iptr, MatArray>(
+    axis: Axis,
+    mats: &MatArray,
+) -> Result<CsMatI<N, I, Iptr>, StructureError>
+where
+    N: 'a + Clone + Default,
+    I: 'a + SpIndex,
+    Iptr: 'a + SpIndex,
+    MatArray: AsRef<[CsMatViewI<'a, N, I, Iptr>]>,
+{
+    let mats = mats.as_ref();
+    let first = mats
+        .first()
+        .ok_or(StructureError::SizeMismatch("Empty stacking list"))?;
+    let fast_storage =
+        match axis {
+            Axis(0) => CompressedStorage::CSR,
+            Axis(1) => CompressedStorage::CSC,
+            _ => return Err(StructureError::OutOfRange(
+                "Sparse matrices can only be concatenated along axis 0 or 1",
+            )),
+        };
+    let other_dim = |m: &CsMatViewI<N, I, Iptr>| match fast_storage {
+        CompressedStorage::CSR => m.cols(),
+        CompressedStorage::CSC => m.rows(),
+    };
+    if mats.iter().any(|m| other_dim(m) != other_dim(first)) {
+        return Err(StructureError::SizeMismatch(
+            "Dimension mismatch along the non-concatenation axis",
+        ));
+    }
+    let storage = first.storage();
+    if mats.iter().all(|m| m.storage() == storage) {
+        if storage == fast_storage {
+            return Ok(same_storage_fast_stack(&mats));
+        }
+        return Ok(same_storage_inner_stack(mats));
+    }
+    let converted: Vec<_> = mats
+        .iter()
+        .map(|m| match fast_storage {
+            CompressedStorage::CSR => m.to_csr(),
+            CompressedStorage::CSC => m.to_csc(),
+        })
+        .collect();
+    let views: Vec<_> = converted.iter().map(CsMatBase::view).collect();
+    Ok(same_storage_fast_stack(&views))
+}
+
+/// Construct a CSR matrix whose rows are the given sparse vectors.
+///
+/// Returns an error if `vecs` is empty, or if the vectors do not all have
+/// the same dimension.
+///
+/// # Example
+///
+/// ```rust
+/// use sprs::{vstack_csvec, CsMat, CsVec};
+/// let a = CsVec::new(3, vec![0, 2], vec![1., 2.]);
+/// let b = CsVec::new(3, vec![1], vec![3.]);
+/// let mat: CsMat<f64> = vstack_csvec(&[a.view(), b.view()]).unwrap();
+/// assert!(mat.is_csr());
+/// assert_eq!(mat.to_dense(), ndarray::arr2(&[[1., 0., 2.], [0., 3., 0.]]));
+/// ```
+pub fn vstack_csvec<'a, N, I, Iptr, VecArray>(
+    vecs: &VecArray,
+) -> Result<CsMatI<N, I, Iptr>, StructureError>
+where
+    N: 'a + Clone,
+    I: 'a + SpIndex,
+    Iptr: SpIndex,
+    VecArray: AsRef<[CsVecViewI<'a, N, I>]>,
+{
+    stack_csvec(CompressedStorage::CSR, vecs.as_ref())
+}
+
+/// Construct a CSC matrix whose columns are the given sparse vectors.
+///
+/// Returns an error if `vecs` is empty, or if the vectors do not all have
+/// the same dimension.
+///
+/// # Example
+///
+/// ```rust
+/// use sprs::{hstack_csvec, CsMat, CsVec};
+/// let a = CsVec::new(3, vec![0, 2], vec![1., 2.]);
+/// let b = CsVec::new(3, vec![1], vec![3.]);
+/// let mat: CsMat<f64> = hstack_csvec(&[a.view(), b.view()]).unwrap();
+/// assert!(mat.is_csc());
+/// assert_eq!(mat.to_dense(), ndarray::arr2(&[[1., 0.], [0., 3.], [2., 0.]]));
+/// ```
+pub fn hstack_csvec<'a, N, I, Iptr, VecArray>(
+    vecs: &VecArray,
+) -> Result<CsMatI<N, I, Iptr>, StructureError>
+where
+    N: 'a + Clone,
+    I: 'a + SpIndex,
+    Iptr: SpIndex,
+    VecArray: AsRef<[CsVecViewI<'a, N, I>]>,
+{
+    stack_csvec(CompressedStorage::CSC, vecs.as_ref())
+}
+
+/// Use the given vectors as the outer dimensions of a new matrix
+fn stack_csvec<N, I, Iptr>(
+    storage: CompressedStorage,
+    vecs: &[CsVecViewI<N, I>],
+) -> Result<CsMatI<N, I, Iptr>, StructureError>
+where
+    N: Clone,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    let dim = vecs
+        .first()
+        .ok_or(StructureError::SizeMismatch("Empty stacking list"))?
+        .dim();
+    if vecs.iter().any(|v| v.dim() != dim) {
+        return Err(StructureError::SizeMismatch(
+            "Sparse vectors should have the same dimension",
+        ));
+    }
+    let nnz = vecs.iter().map(|v| v.nnz()).sum::<usize>();
+    let mut indptr = Vec::with_capacity(vecs.len() + 1);
+    let mut indices = Vec::with_capacity(nnz);
+    let mut data = Vec::with_capacity(nnz);
+    indptr.push(Iptr::zero());
+    for v in vecs {
+        indices.extend_from_slice(v.indices());
+        data.extend_from_slice(v.data());
+        indptr.push(Iptr::from_usize(indices.len()));
+    }
+    let shape = match storage {
+        CompressedStorage::CSR => (vecs.len(), dim),
+        CompressedStorage::CSC => (dim, vecs.len()),
+    };
+    Ok(CsMatI::new_trusted(storage, shape, indptr, indices, data))
+}
+
+/// Construct a block diagonal matrix, with the given matrices as diagonal
+/// blocks and zero off-diagonal blocks.
+///
+/// The result is in CSR storage, and has a shape of
+/// `(sum of rows, sum of cols)`. An empty list of matrices gives a `(0, 0)`
+/// matrix.
+///
+/// # Example
+///
+/// ```rust
+/// use sprs::{block_diag, CsMat};
+/// let a = CsMat::<f64>::eye(2);
+/// let b = CsMat::new((1, 3), vec![0, 2], vec![0, 2], vec![2., 3.]);
+/// let c = block_diag(&[a.view(), b.view()]);
+/// assert_eq!(c.shape(), (3, 5));
+/// assert_eq!(c.get(2, 4), Some(&3.));
+/// ```
+pub fn block_diag<'a, N, I, Iptr, MatArray>(
+    mats: &MatArray,
+) -> CsMatI<N, I, Iptr>
+where
+    N: 'a + Clone + Default,
+    I: 'a + SpIndex,
+    Iptr: 'a + SpIndex,
+    MatArray: AsRef<[CsMatViewI<'a, N, I, Iptr>]>,
+{
+    let mats = mats.as_ref();
+    let nrows = mats.iter().map(CsMatBase::rows).sum::<usize>();
+    let ncols = mats.iter().map(CsMatBase::cols).sum::<usize>();
+    let nnz = mats.iter().map(CsMatBase::nnz).sum::<usize>();
+    let mut indptr = Vec::with_capacity(nrows + 1);
+    let mut indices = Vec::with_capacity(nnz);
+    let mut data = Vec::with_capacity(nnz);
+    indptr.push(Iptr::zero());
+    let mut col_offset = 0;
+    for mat in mats {
+        let converted;
+        let mat = if mat.is_csr() {
+            mat.view()
+        } else {
+            converted = mat.to_csr();
+            converted.view()
+        };
+        let offset = I::from_usize(col_offset);
+        for row in mat.outer_iterator() {
+            indices.extend(row.indices().iter().map(|&j| j + offset));
+            data.extend_from_slice(row.data());
+            indptr.push(Iptr::from_usize(indices.len()));
+        }
+        col_offset += mat.cols();
+    }
+    CsMatI::new_trusted(
+        CompressedStorage::CSR,
+        (nrows, ncols),
+        indptr,
+        indices,
+        data,
+    )
+}
+
 /// Specify a sparse matrix by constructing it from blocks of other matrices
 ///
 /// # Examples
@@ -88,12 +355,22 @@ where
 /// let a = CsMat::<f64>::eye(3);
 /// let b = CsMat::<f64>::eye(4);
 /// let c = sprs::bmat(&[[Some(a.view()), None],
-///                      [None, Some(b.view())]]);
+///                      [None, Some(b.view())]]).unwrap();
 /// assert_eq!(c.rows(), 7);
 /// ```
+///
+/// The result is always in CSR storage. The shape of `None` blocks is
+/// inferred from the other blocks in their block row and block column.
+///
+/// Returns an error
+///
+/// - if `mats` is empty or is not a rectangular grid of blocks
+/// - if a block row or a block column only contains `None` blocks
+/// - if the blocks of a block row don't have the same number of rows
+/// - if the blocks of a block column don't have the same number of columns
 pub fn bmat<'a, N, I, Iptr, OuterArray, InnerArray>(
     mats: &OuterArray,
-) -> CsMatI<N, I, Iptr>
+) -> Result<CsMatI<N, I, Iptr>, StructureError>
 where
     N: 'a + Clone + Default,
     I: 'a + SpIndex,
@@ -103,44 +380,53 @@ where
 {
     let mats = mats.as_ref();
     let super_rows = mats.len();
-    if super_rows == 0 {
-        panic!("Empty stacking list");
-    }
-    let super_cols = mats[0].as_ref().len();
+    let super_cols = mats.first().map_or(0, |row| row.as_ref().len());
     if super_cols == 0 {
-        panic!("Empty stacking list");
+        return Err(StructureError::SizeMismatch("Empty stacking list"));
     }
 
     // check input has matrix shape
     if !mats.iter().all(|x| x.as_ref().len() == super_cols) {
-        panic!("Dimension mismatch");
+        return Err(StructureError::SizeMismatch(
+            "Blocks do not form a rectangular grid",
+        ));
     }
 
-    if mats.iter().any(|x| x.as_ref().iter().all(Option::is_none)) {
-        panic!("Empty bmat row");
-    }
-    if (0..super_cols).any(|j| mats.iter().all(|x| x.as_ref()[j].is_none())) {
-        panic!("Empty bmat col");
-    }
-
-    // find out the shapes of the None elements
-    let rows_per_row: Vec<_> = mats
+    // find out the shapes of the None elements, checking that the blocks
+    // in a block row have the same height and the blocks in a block column
+    // have the same width
+    let rows_per_row = mats
         .iter()
         .map(|row| {
-            row.as_ref().iter().fold(0, |nrows, mopt| {
-                mopt.as_ref().map_or(nrows, |m| cmp::max(nrows, m.rows()))
-            })
+            let mut heights = row.as_ref().iter().flatten().map(|m| m.rows());
+            let nrows = heights
+                .next()
+                .ok_or(StructureError::SizeMismatch("Empty bmat row"))?;
+            if heights.any(|h| h != nrows) {
+                return Err(StructureError::SizeMismatch(
+                    "Block height mismatch in bmat row",
+                ));
+            }
+            Ok(nrows)
         })
-        .collect();
-    let cols_per_col: Vec<_> = (0..super_cols)
+        .collect::<Result<Vec<_>, _>>()?;
+    let cols_per_col = (0..super_cols)
         .map(|j| {
-            mats.iter().fold(0, |ncols, row| {
-                row.as_ref()[j]
-                    .as_ref()
-                    .map_or(ncols, |m| cmp::max(ncols, m.cols()))
-            })
+            let mut widths = mats
+                .iter()
+                .filter_map(|row| row.as_ref()[j].as_ref())
+                .map(|m| m.cols());
+            let ncols = widths
+                .next()
+                .ok_or(StructureError::SizeMismatch("Empty bmat col"))?;
+            if widths.any(|w| w != ncols) {
+                return Err(StructureError::SizeMismatch(
+                    "Block width mismatch in bmat col",
+                ));
+            }
+            Ok(ncols)
         })
-        .collect();
+        .collect::<Result<Vec<_>, _>>()?;
     let mut to_vstack = Vec::new();
     to_vstack.reserve(super_rows);
     for (i, row) in mats.iter().enumerate() {
@@ -158,11 +444,98 @@ where
         to_vstack.push(stacked);
     }
     let borrows: Vec<_> = to_vstack.iter().map(CsMatBase::view).collect();
-    vstack(&borrows)
+    Ok(vstack(&borrows))
+}
+
+/// Construct a CSR matrix of the given shape from its diagonals, as
+/// `spdiags` does in MATLAB or scipy.
+///
+/// `diagonals[k]` holds the values of the diagonal of offset `offsets[k]`,
+/// where a positive offset designates a diagonal above the main diagonal,
+/// and a negative offset one below it. Following the `spdiags` convention,
+/// the diagonals are indexed by column: the entry at `(j - offsets[k], j)`
+/// is `diagonals[k][j]`. The values which would fall outside of the matrix
+/// are ignored, and so are the positions past the end of a diagonal.
+///
+/// Entries on diagonals listed several times are summed. Zero values are
+/// not stored.
+///
+/// # Panics
+///
+/// If `diagonals` and `offsets` do not have the same length.
+///
+/// # Example
+///
+/// ```rust
+/// use sprs::{spdiags, CsMat};
+/// // 1D Laplacian
+/// let lap: CsMat<f64> = spdiags(&[&[-1.; 4], &[2.; 4], &[-1.; 4]], &[-1, 0, 1], (4, 4));
+/// assert_eq!(
+///     lap.to_dense(),
+///     ndarray::arr2(&[
+///         [2., -1., 0., 0.],
+///         [-1., 2., -1., 0.],
+///         [0., -1., 2., -1.],
+///         [0., 0., -1., 2.],
+///     ])
+/// );
+/// ```
+pub fn spdiags<N, I, Iptr>(
+    diagonals: &[&[N]],
+    offsets: &[isize],
+    shape: (usize, usize),
+) -> CsMatI<N, I, Iptr>
+where
+    N: Num + Clone,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    assert_eq!(diagonals.len(), offsets.len(), "Dimension mismatch");
+    let (rows, cols) = shape;
+    // Visiting the diagonals by increasing offset gives sorted columns
+    let mut order: Vec<usize> = (0..offsets.len()).collect();
+    order.sort_by_key(|&k| offsets[k]);
+    let mut indptr = Vec::with_capacity(rows + 1);
+    let mut indices: Vec<I> = Vec::new();
+    let mut data: Vec<N> = Vec::new();
+    indptr.push(Iptr::zero());
+    for row in 0..rows {
+        let row_start = indices.len();
+        for &k in &order {
+            let col = row as isize + offsets[k];
+            if col < 0 || col as usize >= cols {
+                continue;
+            }
+            let col = col as usize;
+            let val = match diagonals[k].get(col) {
+                Some(val) => val.clone(),
+                None => continue,
+            };
+            let col = I::from_usize(col);
+            if indices.len() > row_start && indices.last() == Some(&col) {
+                let last = data.last_mut().unwrap();
+                *last = last.clone() + val;
+            } else {
+                indices.push(col);
+                data.push(val);
+            }
+        }
+        indptr.push(Iptr::from_usize(indices.len()));
+    }
+    let mut mat = CsMatI::new_trusted(
+        CompressedStorage::CSR,
+        shape,
+        indptr,
+        indices,
+        data,
+    );
+    mat.prune();
+    mat
 }
 
 #[cfg(test)]
 mod test {
+    use crate::errors::StructureError;
     use crate::sparse::CsMat;
     use crate::test_data::{mat1, mat2, mat3, mat4};
 
@@ -234,40 +607,166 @@ mod test {
     }
 
     #[test]
-    #[should_panic]
+    fn concatenate() {
+        use super::concatenate;
+        use ndarray::Axis;
+        let a = mat1();
+        let b = mat2();
+        let expected = mat1_vstack_mat2();
+        for &(a, b) in &[
+            (a.view(), b.view()),
+            (a.view(), b.to_csc().view()),
+            (a.to_csc().view(), b.view()),
+        ] {
+            let res = concatenate(Axis(0), &[a, b]).unwrap();
+            assert!(res.is_csr());
+            assert_eq!(res, expected);
+        }
+        let (a_csc, b_csc) = (a.to_csc(), b.to_csc());
+        let res = concatenate(Axis(0), &[a_csc.view(), b_csc.view()]).unwrap();
+        assert!(res.is_csc());
+        assert_eq!(res, expected.to_csc());
+
+        let (at, bt) = (a.transpose_view(), b.transpose_view());
+        let res = concatenate(Axis(1), &[at, bt]).unwrap();
+        assert!(res.is_csc());
+        assert_eq!(res, expected.transpose_view().to_owned());
+        let (at, bt) = (a_csc.transpose_view(), b_csc.transpose_view());
+        let res = concatenate(Axis(1), &[at, bt]).unwrap();
+        assert!(res.is_csr());
+        assert_eq!(res, expected.transpose_view().to_csr());
+        let res = concatenate(Axis(1), &[at, b.transpose_view()]).unwrap();
+        assert!(res.is_csc());
+        assert_eq!(res, expected.transpose_view().to_owned());
+
+        let res = concatenate(Axis(1), &[a.view()]).unwrap();
+        assert_eq!(res, a);
+    }
+
+    #[test]
+    fn concatenate_errors() {
+        use super::concatenate;
+        use crate::errors::{StructureError, StructureErrorKind};
+        use ndarray::Axis;
+        let a = mat1();
+        let c = mat3();
+        let kind =
+            |res: Result<CsMat<f64>, StructureError>| res.unwrap_err().kind();
+        let res = concatenate(Axis(0), &[a.view(), c.view()]);
+        assert_eq!(kind(res), StructureErrorKind::SizeMismatch);
+        let res = concatenate(Axis(1), &[a.view(), c.transpose_view()]);
+        assert_eq!(kind(res), StructureErrorKind::SizeMismatch);
+        let res = concatenate(Axis(2), &[a.view()]);
+        assert_eq!(kind(res), StructureErrorKind::OutOfRange);
+        let empty: &[crate::CsMatView<f64>] = &[];
+        assert_eq!(
+            kind(concatenate(Axis(0), &empty)),
+            StructureErrorKind::SizeMismatch
+        );
+    }
+
+    #[test]
+    fn stack_csvec() {
+        use super::{hstack_csvec, vstack_csvec};
+        let a = mat1();
+        let rows: Vec<_> = a.outer_iterator().collect();
+        let res: CsMat<f64> = vstack_csvec(&rows).unwrap();
+        assert_eq!(res, a);
+        let res: CsMat<f64> = hstack_csvec(&rows).unwrap();
+        assert!(res.is_csc());
+        assert_eq!(res, a.transpose_view().to_owned());
+        assert_eq!(res.to_csr(), a.transpose_view().to_csr());
+    }
+
+    #[test]
+    fn stack_csvec_errors() {
+        use super::{hstack_csvec, vstack_csvec};
+        use crate::errors::StructureErrorKind;
+        use crate::sparse::CsVec;
+        let a = CsVec::new(3, vec![0], vec![1.]);
+        let b = CsVec::new(4, vec![0], vec![1.]);
+        let res: Result<CsMat<f64>, _> = vstack_csvec(&[a.view(), b.view()]);
+        assert_eq!(res.unwrap_err().kind(), StructureErrorKind::SizeMismatch);
+        let empty: &[crate::CsVecView<f64>] = &[];
+        let res: Result<CsMat<f64>, _> = hstack_csvec(&empty);
+        assert_eq!(res.unwrap_err().kind(), StructureErrorKind::SizeMismatch);
+    }
+
+    #[test]
+    fn block_diag() {
+        use super::block_diag;
+        let a = mat1();
+        let c = mat3();
+        let res = block_diag(&[a.view(), c.to_csc().view(), a.view()]);
+        assert!(res.is_csr());
+        assert_eq!(res.shape(), (15, 14));
+        let expected = super::bmat(&[
+            [Some(a.view()), None, None],
+            [None, Some(c.view()), None],
+            [None, None, Some(a.view())],
+        ])
+        .unwrap();
+        assert_eq!(res, expected);
+
+        let empty = CsMat::<f64>::zero((2, 0));
+        let res = block_diag(&[empty.view(), c.view()]);
+        assert_eq!(res.shape(), (7, 4));
+        assert_eq!(res.slice_outer(2..7), c.view());
+
+        let res: CsMat<f64> = block_diag(&[]);
+        assert_eq!(res.shape(), (0, 0));
+    }
+
+    #[test]
     fn bmat_fail_shapes() {
-        let _: CsMat<f64> = super::bmat(&vec![vec![None, None], vec![None]]);
+        let res: Result<CsMat<f64>, _> =
+            super::bmat(&vec![vec![None, None], vec![None]]);
+        assert_eq!(
+            res.unwrap_err(),
+            StructureError::SizeMismatch(
+                "Blocks do not form a rectangular grid"
+            )
+        );
     }
 
     #[test]
-    #[should_panic]
     fn bmat_fail_empty_stacking_list() {
-        let _: CsMat<f64> = super::bmat(&[[]]);
+        let res: Result<CsMat<f64>, _> = super::bmat(&[[]]);
+        assert_eq!(
+            res.unwrap_err(),
+            StructureError::SizeMismatch("Empty stacking list")
+        );
     }
 
     #[test]
-    #[should_panic]
     fn bmat_fail_empty_bmat_row() {
         let a = mat1();
         let c = mat3();
-        let _: CsMat<f64> =
+        let res: Result<CsMat<f64>, _> =
             super::bmat(&[[None, None], [Some(a.view()), Some(c.view())]]);
+        assert_eq!(
+            res.unwrap_err(),
+            StructureError::SizeMismatch("Empty bmat row")
+        );
     }
 
     #[test]
-    #[should_panic]
     fn bmat_fail_empty_bmat_col() {
-        let a = mat1();
         let c = mat3();
-        let _: CsMat<f64> =
-            super::bmat(&[[Some(c.view()), None], [Some(a.view()), None]]);
+        let res: Result<CsMat<f64>, _> =
+            super::bmat(&[[Some(c.view()), None], [Some(c.view()), None]]);
+        assert_eq!(
+            res.unwrap_err(),
+            StructureError::SizeMismatch("Empty bmat col")
+        );
     }
 
     #[test]
     fn bmat_simple() {
         let a = CsMat::<f64>::eye(5);
         let b = CsMat::<f64>::eye(4);
-        let c = super::bmat(&[[Some(a.view()), None], [None, Some(b.view())]]);
+        let c = super::bmat(&[[Some(a.view()), None], [None, Some(b.view())]])
+            .unwrap();
         let expected = CsMat::new(
             (9, 9),
             vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
@@ -284,7 +783,8 @@ mod test {
         let c = super::bmat(&[
             [Some(a.view()), Some(b.view())],
             [Some(b.view()), None],
-        ]);
+        ])
+        .unwrap();
         let expected = CsMat::new(
             (10, 10),
             vec![0, 6, 10, 11, 14, 17, 21, 23, 23, 25, 27],
@@ -304,7 +804,8 @@ mod test {
         let f = super::bmat(&[
             [Some(d.view()), Some(a.view())],
             [None, Some(e.view())],
-        ]);
+        ])
+        .unwrap();
         let expected = CsMat::new(
             (10, 9),
             vec![0, 4, 8, 10, 12, 14, 16, 18, 21, 23, 24],
@@ -319,4 +820,90 @@ mod test {
         );
         assert_eq!(f, expected);
     }
+
+    #[test]
+    fn bmat_none_corner() {
+        let a = CsMat::<f64>::eye(3);
+        let b = CsMat::new((3, 2), vec![0, 1, 1, 2], vec![0, 1], vec![2., 3.]);
+        let b_t = b.transpose_view().to_owned();
+        let c = super::bmat(&[
+            [Some(a.view()), Some(b.view())],
+            [Some(b_t.view()), None],
+        ])
+        .unwrap();
+        assert!(c.is_csr());
+        let expected = CsMat::new(
+            (5, 5),
+            vec![0, 2, 3, 5, 6, 7],
+            vec![0, 3, 1, 2, 4, 0, 2],
+            vec![1., 2., 1., 1., 3., 2., 3.],
+        );
+        assert_eq!(c, expected);
+    }
+
+    #[test]
+    fn bmat_fail_height_mismatch() {
+        let a = CsMat::<f64>::eye(3);
+        let b = CsMat::<f64>::eye(2);
+        let res = super::bmat(&[[Some(a.view()), Some(b.view())]]);
+        assert_eq!(
+            res.unwrap_err(),
+            StructureError::SizeMismatch("Block height mismatch in bmat row")
+        );
+    }
+
+    #[test]
+    fn bmat_fail_width_mismatch() {
+        let a = CsMat::<f64>::eye(3);
+        let b = CsMat::<f64>::eye(2);
+        let res = super::bmat(&[[Some(a.view())], [Some(b.view())]]);
+        assert_eq!(
+            res.unwrap_err(),
+            StructureError::SizeMismatch("Block width mismatch in bmat col")
+        );
+    }
+
+    #[test]
+    fn spdiags() {
+        // 1D Laplacian, checked through a known product
+        let n = 5;
+        let lap: CsMat<f64> = super::spdiags(
+            &[&vec![-1.; n], &vec![2.; n], &vec![-1.; n]],
+            &[-1, 0, 1],
+            (n, n),
+        );
+        assert!(lap.is_csr());
+        assert_eq!(lap.nnz(), 3 * n - 2);
+        lap.check_compressed_structure().unwrap();
+        let x = ndarray::arr1(&[0., 1., 4., 9., 16.]);
+        assert_eq!(&lap * &x, ndarray::arr1(&[-1., -2., -2., -2., 23.]));
+
+        // scipy.sparse.spdiags's documentation example
+        let data = [1., 2., 3., 4.];
+        let mat: CsMat<f64> =
+            super::spdiags(&[&data, &data, &data], &[0, -1, 2], (4, 4));
+        let expected = ndarray::arr2(&[
+            [1., 0., 3., 0.],
+            [1., 2., 0., 4.],
+            [0., 2., 3., 0.],
+            [0., 0., 3., 4.],
+        ]);
+        assert_eq!(mat.to_dense(), expected);
+
+        // clipping to a rectangular shape, short diagonals, duplicated
+        // and out of bounds offsets
+        let mat: CsMat<i32> = super::spdiags(
+            &[&[1, 2, 3], &[1], &[5, 5, 5], &[7, 7]],
+            &[0, 1, 0, -4],
+            (2, 3),
+        );
+        let expected = ndarray::arr2(&[[6, 0, 0], [0, 7, 0]]);
+        assert_eq!(mat.to_dense(), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "Dimension mismatch")]
+    fn spdiags_fail() {
+        let _: CsMat<f64> = super::spdiags(&[&[1.]], &[0, 1], (2, 2));
+    }
 }
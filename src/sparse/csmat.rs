@@ -1,5 +1,5 @@
-use ndarray::ArrayView;
-use num_traits::{Float, Num, Signed, Zero};
+use ndarray::{ArrayView, ArrayViewMut2};
+use num_traits::{Float, Num, NumCast, Signed, Zero};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::cmp;
@@ -24,7 +24,7 @@ use ndarray::{self, Array, ArrayBase, ShapeBuilder};
 
 use crate::indexing::SpIndex;
 
-use crate::errors::StructureError;
+use crate::errors::{ArithmeticError, CastError, StructureError};
 use crate::sparse::binop;
 use crate::sparse::permutation::PermViewI;
 use crate::sparse::prelude::*;
@@ -55,6 +55,13 @@ impl CompressedStorage {
     }
 }
 
+/// The maximum of the given values, or zero if there are none
+fn max_or_zero<N: Num + PartialOrd>(values: Vec<N>) -> N {
+    values
+        .into_iter()
+        .fold(N::zero(), |max, x| if x > max { x } else { max })
+}
+
 pub fn outer_dimension(
     storage: CompressedStorage,
     rows: usize,
@@ -253,6 +260,129 @@ where
         Self::new_checked(CompressedStorage::CSC, shape, indptr, indices, data)
     }
 
+    /// Try to create a new `CSR` sparse matrix, reporting precisely the
+    /// first violation of the compressed structure invariants:
+    ///
+    /// - [`StructureError::DecreasingIndptr`] if the indptr is not
+    ///   monotonically non-decreasing,
+    /// - [`StructureError::IndptrNnzMismatch`] if the indptr's last element
+    ///   does not match the number of non-zeros,
+    /// - [`StructureError::IndexOutOfBounds`] if an inner index is not lower
+    ///   than the inner dimension,
+    /// - [`StructureError::UnsortedIndices`] if the inner indices of an
+    ///   outer slice are not sorted or contain duplicates.
+    ///
+    /// Other errors, such as length mismatches, are reported as for
+    /// [`try_new`](Self::try_new).
+    ///
+    /// See `try_new_csc_checked` for the `CSC` equivalent.
+    ///
+    /// [`StructureError::DecreasingIndptr`]: crate::errors::StructureError::DecreasingIndptr
+    /// [`StructureError::IndptrNnzMismatch`]: crate::errors::StructureError::IndptrNnzMismatch
+    /// [`StructureError::IndexOutOfBounds`]: crate::errors::StructureError::IndexOutOfBounds
+    /// [`StructureError::UnsortedIndices`]: crate::errors::StructureError::UnsortedIndices
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::errors::StructureError;
+    /// use sprs::CsMat;
+    /// let res = CsMat::try_new_checked(
+    ///     (2, 3),
+    ///     vec![0, 2, 3],
+    ///     vec![2, 1, 0],
+    ///     vec![1., 2., 3.],
+    /// );
+    /// assert_eq!(res, Err(StructureError::UnsortedIndices { outer: 0 }));
+    /// ```
+    pub fn try_new_checked(
+        shape: (usize, usize),
+        indptr: IptrStorage,
+        indices: IStorage,
+        data: DStorage,
+    ) -> Result<Self, StructureError> {
+        Self::new_checked_detailed(CSR, shape, indptr, indices, data)
+    }
+
+    /// Try to create a new `CSC` sparse matrix, reporting precisely the
+    /// first violation of the compressed structure invariants.
+    ///
+    /// See `try_new_checked` for the `CSR` equivalent.
+    pub fn try_new_csc_checked(
+        shape: (usize, usize),
+        indptr: IptrStorage,
+        indices: IStorage,
+        data: DStorage,
+    ) -> Result<Self, StructureError> {
+        Self::new_checked_detailed(CSC, shape, indptr, indices, data)
+    }
+
+    /// Create a matrix from the storage components returned by
+    /// [`into_raw_storage`](Self::into_raw_storage), along with its storage
+    /// order and shape, checking their validity as
+    /// [`try_new_checked`](Self::try_new_checked) does.
+    ///
+    /// This is the counterpart of `into_raw_storage`, which can for
+    /// instance be used to exchange matrices with other languages.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::{CsMat, CsMatI};
+    /// let mat = CsMat::<f64>::eye_csc(3);
+    /// let (storage, shape) = (mat.storage(), mat.shape());
+    /// let (indptr, indices, data) = mat.clone().into_raw_storage();
+    /// let res = CsMatI::from_raw_storage(storage, shape, indptr, indices, data);
+    /// assert_eq!(res, Ok(mat));
+    /// ```
+    pub fn from_raw_storage(
+        storage: CompressedStorage,
+        shape: (usize, usize),
+        indptr: IptrStorage,
+        indices: IStorage,
+        data: DStorage,
+    ) -> Result<Self, StructureError> {
+        Self::new_checked_detailed(storage, shape, indptr, indices, data)
+    }
+
+    /// Create a matrix from the storage components returned by
+    /// [`into_raw_storage`](Self::into_raw_storage), without checking their
+    /// validity. This is the same as [`new_unchecked`](Self::new_unchecked).
+    ///
+    /// # Safety
+    ///
+    /// See [`new_unchecked`](Self::new_unchecked).
+    pub unsafe fn from_raw_storage_unchecked(
+        storage: CompressedStorage,
+        shape: (usize, usize),
+        indptr: IptrStorage,
+        indices: IStorage,
+        data: DStorage,
+    ) -> Self {
+        Self::new_unchecked(storage, shape, indptr, indices, data)
+    }
+
+    fn new_checked_detailed(
+        storage: CompressedStorage,
+        shape: (usize, usize),
+        indptr: IptrStorage,
+        indices: IStorage,
+        data: DStorage,
+    ) -> Result<Self, StructureError> {
+        let (inner, outer) = match storage {
+            CSR => (shape.1, shape.0),
+            CSC => (shape.0, shape.1),
+        };
+        utils::check_compressed_structure_detailed(
+            inner,
+            outer,
+            indptr.as_ref(),
+            indices.as_ref(),
+        )?;
+        Self::new_checked(storage, shape, indptr, indices, data)
+            .map_err(|(_, _, _, e)| e)
+    }
+
     /// Create a `CsMat` matrix from raw data,
     /// without checking their validity
     ///
@@ -399,6 +529,28 @@ where
     {
         Self::new_from_unsorted_checked(CSC, shape, indptr, indices, data)
     }
+
+    /// Sort the inner indices of each outer slice in place, permuting the
+    /// values accordingly. Outer slices that are already sorted are left
+    /// untouched.
+    ///
+    /// Duplicated inner indices are not merged, which means
+    /// [`is_sorted`](Self::is_sorted) will still return `false` for a matrix
+    /// containing some.
+    pub fn sort_indices(&mut self)
+    where
+        N: Clone,
+    {
+        let mut buf = Vec::new();
+        for range in self.indptr.iter_outer_sz() {
+            let indices = &mut self.indices[range.clone()];
+            if utils::sorted_indices(indices) {
+                continue;
+            }
+            let data = &mut self.data[range];
+            utils::sort_indices_data_slices(indices, data, &mut buf);
+        }
+    }
 }
 
 /// # Constructor methods for owned sparse matrices
@@ -446,6 +598,116 @@ impl<N, I: SpIndex, Iptr: SpIndex> CsMatI<N, I, Iptr> {
         let data = vec![N::one(); n];
         Self::new_trusted(CSC, (n, n), indptr, indices, data)
     }
+
+    /// Square diagonal matrix with the given diagonal values, stored as a
+    /// CSR matrix.
+    ///
+    /// There is exactly one stored entry for each non-zero value of `diag`,
+    /// the zero values are not stored.
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let mat = CsMat::from_diag(&[1., 0., 3.]);
+    /// assert!(mat.is_csr());
+    /// assert_eq!(mat, CsMat::new((3, 3), vec![0, 1, 1, 2], vec![0, 2], vec![1., 3.]));
+    /// ```
+    pub fn from_diag(diag: &[N]) -> Self
+    where
+        N: Zero + Clone,
+    {
+        let n = diag.len();
+        let _ = (I::from_usize(n), Iptr::from_usize(n)); // Make sure n fits in type I & Iptr
+        let mut indptr = Vec::with_capacity(n + 1);
+        let mut indices = Vec::with_capacity(n);
+        let mut data = Vec::with_capacity(n);
+        indptr.push(Iptr::zero());
+        for (i, val) in diag.iter().enumerate() {
+            if !val.is_zero() {
+                indices.push(I::from_usize_unchecked(i));
+                data.push(val.clone());
+            }
+            indptr.push(Iptr::from_usize_unchecked(indices.len()));
+        }
+        Self::new_trusted(CSR, (n, n), indptr, indices, data)
+    }
+
+    /// Create a matrix with the requested storage and shape from an
+    /// iterator of `(row, col, value)` triplets, summing the values of
+    /// duplicated locations.
+    ///
+    /// The triplets can come in any order, they are sorted internally.
+    /// Contrary to building a [`TriMatI`](crate::TriMatI) and converting
+    /// it, the triplets are buffered only once. Duplicates are summed in
+    /// the order of the iterator, and explicit zeros are kept.
+    ///
+    /// Returns an error if a triplet is out of the bounds of `shape`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::{CsMat, CSR};
+    /// let triplets = (0..3).map(|i| (i, 2 - i, i as f64)).chain(Some((1, 1, 4.)));
+    /// let mat = CsMat::from_triplet_iter(CSR, (3, 3), triplets).unwrap();
+    /// assert_eq!(mat, CsMat::new((3, 3), vec![0, 1, 2, 3], vec![2, 1, 0], vec![0., 5., 2.]));
+    /// ```
+    pub fn from_triplet_iter<It>(
+        storage: CompressedStorage,
+        shape: Shape,
+        triplets: It,
+    ) -> Result<Self, StructureError>
+    where
+        N: Add<Output = N>,
+        It: IntoIterator<Item = (usize, usize, N)>,
+    {
+        let (rows, cols) = shape;
+        let outer_dims = match storage {
+            CSR => rows,
+            CSC => cols,
+        };
+        let _ = (I::from_usize(rows.max(cols)), Iptr::from_usize(outer_dims)); // Make sure the dimensions fit in type I & Iptr
+        let triplets = triplets.into_iter();
+        let mut entries = Vec::with_capacity(triplets.size_hint().0);
+        for (row, col, val) in triplets {
+            if row >= rows || col >= cols {
+                return Err(StructureError::OutOfRange(
+                    "Triplet out of bounds",
+                ));
+            }
+            let (outer, inner) = match storage {
+                CSR => (row, col),
+                CSC => (col, row),
+            };
+            entries.push((
+                I::from_usize_unchecked(outer),
+                I::from_usize_unchecked(inner),
+                val,
+            ));
+        }
+        // The sort is stable so that duplicates are summed in input order
+        entries.sort_by_key(|&(outer, inner, _)| (outer, inner));
+
+        let mut indptr = vec![Iptr::zero(); outer_dims + 1];
+        let mut indices = Vec::with_capacity(entries.len());
+        let mut data: Vec<N> = Vec::with_capacity(entries.len());
+        let mut last = None;
+        for (outer, inner, val) in entries {
+            if last == Some((outer, inner)) {
+                let sum = data.pop().unwrap() + val;
+                data.push(sum);
+            } else {
+                indices.push(inner);
+                data.push(val);
+                indptr[outer.index_unchecked() + 1] += Iptr::one();
+                last = Some((outer, inner));
+            }
+        }
+        for outer in 0..outer_dims {
+            let prev = indptr[outer];
+            indptr[outer + 1] += prev;
+        }
+        Ok(Self::new_trusted(storage, shape, indptr, indices, data))
+    }
+
     /// Create an empty `CsMat` for building purposes
     pub fn empty(storage: CompressedStorage, inner_size: usize) -> Self {
         let shape = match storage {
@@ -548,6 +810,50 @@ impl<N, I: SpIndex, Iptr: SpIndex> CsMatI<N, I, Iptr> {
         Self::csr_from_dense(m.reversed_axes(), epsilon).transpose_into()
     }
 
+    /// Create a matrix with the requested storage from a dense matrix,
+    /// storing its non-zero elements.
+    ///
+    /// The dense matrix is scanned directly in the storage order, no
+    /// conversion between storages being performed. To also ignore the
+    /// elements close to zero, see [`csr_from_dense`](Self::csr_from_dense)
+    /// and [`csc_from_dense`](Self::csc_from_dense).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::{CsMat, CSC};
+    /// use ndarray::arr2;
+    /// let dense = arr2(&[[1, 0, 2], [0, 0, 3]]);
+    /// let mat = CsMat::from_dense(dense.view(), CSC);
+    /// assert!(mat.is_csc());
+    /// assert_eq!(mat.indptr().raw_storage(), &[0, 1, 1, 3]);
+    /// assert_eq!(mat.to_dense(), dense);
+    /// ```
+    pub fn from_dense(m: ArrayView<N, Ix2>, storage: CompressedStorage) -> Self
+    where
+        N: Zero + Clone,
+    {
+        let shape = m.dim();
+        let outer_view = match storage {
+            CSR => m,
+            CSC => m.reversed_axes(),
+        };
+        let mut indptr = Vec::with_capacity(outer_view.nrows() + 1);
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        indptr.push(Iptr::zero());
+        for outer in outer_view.outer_iter() {
+            for (inner_ind, x) in outer.iter().enumerate() {
+                if !x.is_zero() {
+                    indices.push(I::from_usize(inner_ind));
+                    data.push(x.clone());
+                }
+            }
+            indptr.push(Iptr::from_usize(indices.len()));
+        }
+        Self::new_trusted(storage, shape, indptr, indices, data)
+    }
+
     /// Append an outer dim to an existing matrix, compressing it in the process
     pub fn append_outer(mut self, data: &[N]) -> Self
     where
@@ -588,6 +894,82 @@ impl<N, I: SpIndex, Iptr: SpIndex> CsMatI<N, I, Iptr> {
         self
     }
 
+    /// Append an outer dim to this matrix in place, provided by a sparse
+    /// vector, eg append a row to a CSR matrix.
+    ///
+    /// This is the fallible, in place counterpart of
+    /// [`append_outer_csvec`](Self::append_outer_csvec), hence its name:
+    /// `append_outer` is already taken by the method appending a dense
+    /// slice. The matrix is left untouched on failure.
+    ///
+    /// # Errors
+    ///
+    /// - [`StructureError::SizeMismatch`] if the dimension of `vec` is not
+    ///   the inner dimension of the matrix,
+    /// - [`StructureError::UnsortedIndices`] if the indices of `vec` are not
+    ///   sorted or contain duplicates,
+    /// - [`StructureError::IndexOutOfBounds`] if an index of `vec` is not
+    ///   lower than the inner dimension of the matrix.
+    ///
+    /// The last two can only happen for a vector whose structure was not
+    /// checked on construction.
+    ///
+    /// [`StructureError::SizeMismatch`]: crate::errors::StructureError::SizeMismatch
+    /// [`StructureError::UnsortedIndices`]: crate::errors::StructureError::UnsortedIndices
+    /// [`StructureError::IndexOutOfBounds`]: crate::errors::StructureError::IndexOutOfBounds
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::{CsMat, CsVec};
+    /// let mut mat = CsMat::<f64>::zero((0, 3));
+    /// mat.try_append_outer_csvec(CsVec::new(3, vec![0, 2], vec![1., 2.]).view())
+    ///     .unwrap();
+    /// mat.try_append_outer_csvec(CsVec::new(3, vec![1], vec![3.]).view())
+    ///     .unwrap();
+    /// assert_eq!(mat.shape(), (2, 3));
+    /// assert_eq!(mat.get(1, 1), Some(&3.));
+    /// let res = mat.try_append_outer_csvec(CsVec::new(2, vec![1], vec![3.]).view());
+    /// assert!(res.is_err());
+    /// ```
+    pub fn try_append_outer_csvec(
+        &mut self,
+        vec: CsVecViewI<N, I>,
+    ) -> Result<(), StructureError>
+    where
+        N: Clone,
+    {
+        if vec.dim() != self.inner_dims() {
+            return Err(StructureError::SizeMismatch(
+                "Vector dimension and inner dimension mismatch",
+            ));
+        }
+        let outer = self.outer_dims();
+        if !utils::sorted_indices(vec.indices()) {
+            return Err(StructureError::UnsortedIndices { outer });
+        }
+        // the indices are sorted, so only the last one needs a bounds check
+        if let Some(&index) = vec.indices().last() {
+            if index.index() >= self.inner_dims() {
+                return Err(StructureError::IndexOutOfBounds {
+                    outer,
+                    index: index.index(),
+                    bound: self.inner_dims(),
+                });
+            }
+        }
+        // the last indptr value includes the offset of the indptr, if any
+        let last_indptr = *self.indptr.raw_storage().last().unwrap();
+        self.indices.extend_from_slice(vec.indices());
+        self.data.extend_from_slice(vec.data());
+        match self.storage {
+            CSR => self.nrows += 1,
+            CSC => self.ncols += 1,
+        }
+        self.indptr.push(last_indptr + Iptr::from_usize(vec.nnz()));
+        Ok(())
+    }
+
     /// Insert an element in the matrix. If the element is already present,
     /// its value is overwritten.
     ///
@@ -645,6 +1027,209 @@ impl<N, I: SpIndex, Iptr: SpIndex> CsMatI<N, I, Iptr> {
         }
     }
 
+    /// Set the value of the element located at `(row, col)`, inserting it
+    /// if it is not already stored. The inner indices are kept sorted.
+    ///
+    /// If `prune_zeros` is `true` and `val` is zero, the element is instead
+    /// removed from the storage if it was present, and nothing is inserted.
+    ///
+    /// Contrary to [`insert`](Self::insert), this never changes the shape of
+    /// the matrix, and returns an error if the location is out of bounds.
+    ///
+    /// Inserting or removing an element requires shifting the storage past
+    /// its location, which is `O(nnz)` in the worst case. This is however
+    /// efficient when the elements are inserted following the storage order.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let mut mat = CsMat::new((2, 2), vec![0, 1, 2], vec![0, 1], vec![1, 2]);
+    /// mat.set_or_insert(0, 1, 3, false).unwrap();
+    /// mat.set_or_insert(1, 1, 0, true).unwrap();
+    /// assert_eq!(mat, CsMat::new((2, 2), vec![0, 2, 2], vec![0, 1], vec![1, 3]));
+    /// assert!(mat.set_or_insert(2, 0, 1, false).is_err());
+    /// ```
+    pub fn set_or_insert(
+        &mut self,
+        row: usize,
+        col: usize,
+        val: N,
+        prune_zeros: bool,
+    ) -> Result<(), StructureError>
+    where
+        N: Zero,
+    {
+        if row >= self.rows() || col >= self.cols() {
+            return Err(StructureError::OutOfRange("Location out of bounds"));
+        }
+        let outer_ind = outer_dimension(self.storage(), row, col);
+        let inner_ind =
+            I::from_usize(inner_dimension(self.storage(), row, col));
+        let remove = prune_zeros && val.is_zero();
+        let range = self.indptr.outer_inds_sz(outer_ind);
+        match self.indices[range.clone()].binary_search(&inner_ind) {
+            Ok(pos) if remove => {
+                self.indices.remove(range.start + pos);
+                self.data.remove(range.start + pos);
+                self.indptr.record_removed_element(outer_ind);
+            }
+            Ok(pos) => self.data[range.start + pos] = val,
+            Err(_) if remove => (),
+            Err(pos) => {
+                self.indices.insert(range.start + pos, inner_ind);
+                self.data.insert(range.start + pos, val);
+                self.indptr.record_new_element(outer_ind);
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove the explicitly stored zeros from this matrix.
+    ///
+    /// Returns the number of removed entries.
+    pub fn prune(&mut self) -> usize
+    where
+        N: Zero,
+    {
+        self.prune_by(|val| !val.is_zero())
+    }
+
+    /// Only keep the non-zero entries for which `keep` returns `true`,
+    /// removing the others from the storage. This can be used for instance
+    /// to remove the entries with a small magnitude.
+    ///
+    /// Returns the number of removed entries.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let mut mat = CsMat::new(
+    ///     (2, 2),
+    ///     vec![0, 2, 3],
+    ///     vec![0, 1, 1],
+    ///     vec![1., 1e-12, 2.],
+    /// );
+    /// let removed = mat.prune_by(|&val: &f64| val.abs() > 1e-10);
+    /// assert_eq!(removed, 1);
+    /// assert_eq!(mat, CsMat::new((2, 2), vec![0, 1, 2], vec![0, 1], vec![1., 2.]));
+    /// ```
+    pub fn prune_by<F>(&mut self, mut keep: F) -> usize
+    where
+        F: FnMut(&N) -> bool,
+    {
+        let old_nnz = self.nnz();
+        let indptr = self.indptr.raw_storage_mut();
+        let offset = indptr[0].index();
+        let mut start = 0;
+        let mut nnz = 0;
+        indptr[0] = Iptr::zero();
+        for ip in indptr[1..].iter_mut() {
+            let end = ip.index() - offset;
+            for k in start..end {
+                if keep(&self.data[k]) {
+                    self.indices.swap(nnz, k);
+                    self.data.swap(nnz, k);
+                    nnz += 1;
+                }
+            }
+            start = end;
+            *ip = Iptr::from_usize(nnz);
+        }
+        self.indices.truncate(nnz);
+        self.data.truncate(nnz);
+        old_nnz - nnz
+    }
+
+    /// Remove the entries whose magnitude is strictly below `t`, rebuilding
+    /// the structure of the matrix.
+    ///
+    /// Returns the number of removed entries.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let mut mat = CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 1], vec![1., -0.1, 0.5]);
+    /// assert_eq!(mat.threshold(0.5), 1);
+    /// assert_eq!(mat, CsMat::new((2, 2), vec![0, 1, 2], vec![0, 1], vec![1., 0.5]));
+    /// ```
+    pub fn threshold(&mut self, t: N) -> usize
+    where
+        N: Signed + PartialOrd,
+    {
+        self.prune_by(|val| val.abs() >= t)
+    }
+
+    /// Bring this matrix to its canonical form: the inner indices of each
+    /// outer slice are sorted, duplicated entries are summed into a single
+    /// one, and explicitly stored zeros (including those resulting from
+    /// the summation of duplicates) are removed.
+    ///
+    /// This is mostly useful for matrices whose structure was not checked on
+    /// construction, such as those created by
+    /// [`new_unchecked`](CsMatBase::new_unchecked), or which may hold
+    /// explicit zeros.
+    ///
+    /// The derived `PartialEq` implementation compares the raw storage of
+    /// the matrices. Once canonicalized, two matrices with the same storage
+    /// order thus compare equal if and only if they represent the same
+    /// mathematical matrix.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// use sprs::CompressedStorage::CSR;
+    /// // Unsorted indices, duplicates and cancelling entries
+    /// let mut mat = unsafe {
+    ///     CsMat::new_unchecked(
+    ///         CSR,
+    ///         (2, 3),
+    ///         vec![0, 3, 5],
+    ///         vec![2, 0, 2, 1, 1],
+    ///         vec![1., 2., 3., 1., -1.],
+    ///     )
+    /// };
+    /// mat.canonicalize();
+    /// let expected = CsMat::new((2, 3), vec![0, 2, 2], vec![0, 2], vec![2., 4.]);
+    /// assert_eq!(mat, expected);
+    /// ```
+    pub fn canonicalize(&mut self)
+    where
+        N: Zero + Clone,
+    {
+        self.sort_indices();
+        let indptr = self.indptr.raw_storage_mut();
+        let offset = indptr[0].index();
+        let mut start = 0;
+        let mut nnz = 0;
+        indptr[0] = Iptr::zero();
+        for ip in indptr[1..].iter_mut() {
+            let end = ip.index() - offset;
+            let outer_start = nnz;
+            for k in start..end {
+                if nnz > outer_start && self.indices[nnz - 1] == self.indices[k]
+                {
+                    let val = std::mem::replace(&mut self.data[k], N::zero());
+                    let acc =
+                        std::mem::replace(&mut self.data[nnz - 1], N::zero());
+                    self.data[nnz - 1] = acc + val;
+                } else {
+                    self.indices.swap(nnz, k);
+                    self.data.swap(nnz, k);
+                    nnz += 1;
+                }
+            }
+            start = end;
+            *ip = Iptr::from_usize(nnz);
+        }
+        self.indices.truncate(nnz);
+        self.data.truncate(nnz);
+        self.prune();
+    }
+
     fn set_outer_dims(&mut self, outer_dims: usize) {
         match self.storage() {
             CSR => self.nrows = outer_dims,
@@ -660,6 +1245,16 @@ impl<N, I: SpIndex, Iptr: SpIndex> CsMatI<N, I, Iptr> {
     }
 }
 
+/// Flag the listed positions among `len` ones
+fn removal_mask(len: usize, indices: &[usize]) -> Vec<bool> {
+    let mut removed = vec![false; len];
+    for &ind in indices {
+        assert!(ind < len, "Out of bounds index");
+        removed[ind] = true;
+    }
+    removed
+}
+
 /// # Constructor methods for sparse matrix views
 ///
 /// These constructors can be used to create views over non-matrix data
@@ -778,7 +1373,8 @@ where
     }
 
     /// Access the element located at row i and column j.
-    /// Will return None if there is no non-zero element at this location.
+    /// Will return None if there is no non-zero element at this location,
+    /// including when the location is out of bounds.
     ///
     /// This access is logarithmic in the number of non-zeros
     /// in the corresponding outer slice. It is therefore advisable not to rely
@@ -1011,24 +1607,262 @@ where
         }
     }
 
-    /// Return a view into the current matrix
-    pub fn view(&self) -> CsMatViewI<N, I, Iptr> {
-        CsMatViewI {
+    /// Clone the matrix with another integer type for both the indptr and
+    /// the indices, eg to pass it to a library expecting `i32` indices.
+    /// The storage and the data are preserved.
+    ///
+    /// Contrary to [`to_other_types`](Self::to_other_types), this does not
+    /// panic but returns an error if a value of the indptr or of the
+    /// indices cannot be represented by the requested integer type.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::{CsMat, CsMatI};
+    /// let mat: CsMat<f64> = CsMat::eye(3);
+    /// let small: CsMatI<f64, i32> = mat.to_index_type().unwrap();
+    /// assert_eq!(small.indptr().raw_storage(), &[0, 1, 2, 3]);
+    /// let big: CsMat<f64> = CsMat::eye(40_000);
+    /// assert!(big.to_index_type::<i16>().is_err());
+    /// ```
+    pub fn to_index_type<I2>(&self) -> Result<CsMatI<N, I2, I2>, StructureError>
+    where
+        N: Clone,
+        I2: SpIndex,
+    {
+        let overflow =
+            || StructureError::OutOfRange("Index does not fit the index type");
+        let indptr = self
+            .proper_indptr()
+            .iter()
+            .map(|i| I2::try_from_usize(i.index()))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(overflow)?;
+        let indices = self
+            .indices
+            .iter()
+            .map(|i| I2::try_from_usize(i.index()))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(overflow)?;
+        Ok(CsMatI {
             storage: self.storage,
             nrows: self.nrows,
             ncols: self.ncols,
-            indptr: crate::IndPtrView::new_trusted(self.indptr.raw_storage()),
-            indices: &self.indices[..],
-            data: &self.data[..],
-        }
+            indptr: crate::IndPtr::new_trusted(indptr),
+            indices,
+            data: self.data.to_vec(),
+        })
     }
 
-    pub fn structure_view(&self) -> CsStructureViewI<I, Iptr> {
-        // Safety: std::slice::from_raw_parts requires its passed
-        // pointer to be valid for the whole length of the slice. We have a
-        // zero-sized type, so the length is zero, and since we cast
-        // a non-null pointer, the pointer is valid as all pointers to zero-sized
-        // types are valid if they are not null.
+    /// Build a new matrix made of the outer dimensions (eg the rows for a
+    /// CSR matrix) listed in `indices`, in the given order. Contrary to a
+    /// permutation, indices can be repeated or omitted, which is useful eg
+    /// to sample a minibatch of rows.
+    ///
+    /// The outer dimension of the result is `indices.len()`, its inner
+    /// dimension is the inner dimension of this matrix.
+    ///
+    /// # Panics
+    ///
+    /// If an index is out of bounds.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// use ndarray::arr2;
+    /// let mat = CsMat::csr_from_dense(
+    ///     arr2(&[[1, 0, 2], [0, 3, 0], [4, 0, 5]]).view(), 0
+    /// );
+    /// let res = mat.select_outer(&[2, 0, 2]);
+    /// assert_eq!(res.to_dense(), arr2(&[[4, 0, 5], [1, 0, 2], [4, 0, 5]]));
+    /// let res = mat.select_inner(&[1, 1]);
+    /// assert_eq!(res.to_dense(), arr2(&[[0, 0], [3, 3], [0, 0]]));
+    /// ```
+    pub fn select_outer(&self, indices: &[usize]) -> CsMatI<N, I, Iptr>
+    where
+        N: Clone,
+    {
+        let mut indptr = Vec::with_capacity(indices.len() + 1);
+        let mut new_indices = Vec::new();
+        let mut data = Vec::new();
+        indptr.push(Iptr::zero());
+        for &outer in indices {
+            assert!(outer < self.outer_dims(), "Out of bounds index");
+            let range = self.indptr.outer_inds_sz(outer);
+            new_indices.extend_from_slice(&self.indices[range.clone()]);
+            data.extend_from_slice(&self.data[range]);
+            indptr.push(Iptr::from_usize(new_indices.len()));
+        }
+        let shape = match self.storage {
+            CSR => (indices.len(), self.ncols),
+            CSC => (self.nrows, indices.len()),
+        };
+        CsMatI::new_trusted(self.storage, shape, indptr, new_indices, data)
+    }
+
+    /// Build a new matrix made of the inner dimensions (eg the columns for
+    /// a CSR matrix) listed in `indices`, in the given order. Indices can be
+    /// repeated or omitted.
+    ///
+    /// The inner dimension of the result is `indices.len()`, its outer
+    /// dimension is the outer dimension of this matrix.
+    ///
+    /// # Panics
+    ///
+    /// If an index is out of bounds.
+    pub fn select_inner(&self, indices: &[usize]) -> CsMatI<N, I, Iptr>
+    where
+        N: Clone,
+    {
+        // new positions of each inner dimension, in increasing order
+        let mut new_pos = vec![Vec::new(); self.inner_dims()];
+        for (pos, &inner) in indices.iter().enumerate() {
+            assert!(inner < self.inner_dims(), "Out of bounds index");
+            new_pos[inner].push(pos);
+        }
+        let mut indptr = Vec::with_capacity(self.outer_dims() + 1);
+        let mut new_indices = Vec::new();
+        let mut data = Vec::new();
+        let mut outer_entries = Vec::new();
+        indptr.push(Iptr::zero());
+        for range in self.indptr.iter_outer_sz() {
+            outer_entries.clear();
+            for (inner, val) in
+                self.indices[range.clone()].iter().zip(&self.data[range])
+            {
+                for &pos in &new_pos[inner.index_unchecked()] {
+                    outer_entries.push((pos, val));
+                }
+            }
+            outer_entries.sort_unstable_by_key(|&(pos, _)| pos);
+            for &(pos, val) in &outer_entries {
+                new_indices.push(I::from_usize(pos));
+                data.push(val.clone());
+            }
+            indptr.push(Iptr::from_usize(new_indices.len()));
+        }
+        let shape = match self.storage {
+            CSR => (self.nrows, indices.len()),
+            CSC => (indices.len(), self.ncols),
+        };
+        CsMatI::new_trusted(self.storage, shape, indptr, new_indices, data)
+    }
+
+    /// Build a new matrix without the outer dimensions (eg the rows for a
+    /// CSR matrix) listed in `indices`. The remaining outer dimensions are
+    /// renumbered contiguously, preserving their order.
+    ///
+    /// `indices` does not need to be sorted, and can contain duplicates.
+    ///
+    /// # Panics
+    ///
+    /// If an index is out of bounds.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// use ndarray::arr2;
+    /// let mat = CsMat::csr_from_dense(
+    ///     arr2(&[[1, 0, 2], [0, 3, 0], [4, 0, 5]]).view(), 0
+    /// );
+    /// let res = mat.without_outer(&[1]);
+    /// assert_eq!(res.to_dense(), arr2(&[[1, 0, 2], [4, 0, 5]]));
+    /// let res = mat.without_inner(&[2, 0, 2]);
+    /// assert_eq!(res.to_dense(), arr2(&[[0], [3], [0]]));
+    /// ```
+    pub fn without_outer(&self, indices: &[usize]) -> CsMatI<N, I, Iptr>
+    where
+        N: Clone,
+    {
+        let removed = removal_mask(self.outer_dims(), indices);
+        let mut indptr = Vec::with_capacity(self.outer_dims() + 1);
+        let mut new_indices = Vec::with_capacity(self.nnz());
+        let mut data = Vec::with_capacity(self.nnz());
+        indptr.push(Iptr::zero());
+        for (range, _) in self
+            .indptr
+            .iter_outer_sz()
+            .zip(&removed)
+            .filter(|&(_, &removed)| !removed)
+        {
+            new_indices.extend_from_slice(&self.indices[range.clone()]);
+            data.extend_from_slice(&self.data[range]);
+            indptr.push(Iptr::from_usize(new_indices.len()));
+        }
+        let outer_dims = indptr.len() - 1;
+        let shape = match self.storage {
+            CSR => (outer_dims, self.ncols),
+            CSC => (self.nrows, outer_dims),
+        };
+        CsMatI::new_trusted(self.storage, shape, indptr, new_indices, data)
+    }
+
+    /// Build a new matrix without the inner dimensions (eg the columns for
+    /// a CSR matrix) listed in `indices`. The remaining inner dimensions
+    /// are renumbered contiguously, preserving their order.
+    ///
+    /// `indices` does not need to be sorted, and can contain duplicates.
+    ///
+    /// # Panics
+    ///
+    /// If an index is out of bounds.
+    pub fn without_inner(&self, indices: &[usize]) -> CsMatI<N, I, Iptr>
+    where
+        N: Clone,
+    {
+        let removed = removal_mask(self.inner_dims(), indices);
+        // new position of each kept inner dimension
+        let mut new_pos = Vec::with_capacity(removed.len());
+        let mut nb_kept = 0;
+        for &removed in &removed {
+            new_pos.push(nb_kept);
+            if !removed {
+                nb_kept += 1;
+            }
+        }
+        let mut indptr = Vec::with_capacity(self.outer_dims() + 1);
+        let mut new_indices = Vec::with_capacity(self.nnz());
+        let mut data = Vec::with_capacity(self.nnz());
+        indptr.push(Iptr::zero());
+        for range in self.indptr.iter_outer_sz() {
+            for (inner, val) in
+                self.indices[range.clone()].iter().zip(&self.data[range])
+            {
+                let inner = inner.index_unchecked();
+                if !removed[inner] {
+                    new_indices.push(I::from_usize(new_pos[inner]));
+                    data.push(val.clone());
+                }
+            }
+            indptr.push(Iptr::from_usize(new_indices.len()));
+        }
+        let shape = match self.storage {
+            CSR => (self.nrows, nb_kept),
+            CSC => (nb_kept, self.ncols),
+        };
+        CsMatI::new_trusted(self.storage, shape, indptr, new_indices, data)
+    }
+
+    /// Return a view into the current matrix
+    pub fn view(&self) -> CsMatViewI<N, I, Iptr> {
+        CsMatViewI {
+            storage: self.storage,
+            nrows: self.nrows,
+            ncols: self.ncols,
+            indptr: crate::IndPtrView::new_trusted(self.indptr.raw_storage()),
+            indices: &self.indices[..],
+            data: &self.data[..],
+        }
+    }
+
+    pub fn structure_view(&self) -> CsStructureViewI<I, Iptr> {
+        // Safety: std::slice::from_raw_parts requires its passed
+        // pointer to be valid for the whole length of the slice. We have a
+        // zero-sized type, so the length is zero, and since we cast
+        // a non-null pointer, the pointer is valid as all pointers to zero-sized
+        // types are valid if they are not null.
         let zst_data = unsafe {
             std::slice::from_raw_parts(
                 self.data.as_ptr() as *const (),
@@ -1045,135 +1879,1081 @@ where
         }
     }
 
-    pub fn to_dense(&self) -> Array<N, Ix2>
+    /// Return the sparsity pattern of this matrix, as a boolean matrix
+    /// with the same structure and all its stored values set to `true`.
+    /// Explicitly stored zeros are part of the pattern.
+    ///
+    /// Contrary to [`structure_view`](Self::structure_view), this allocates,
+    /// but the result is a regular matrix which can for instance be
+    /// compared, transposed or converted to another storage.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let a = CsMat::new((2, 2), vec![0, 1, 2], vec![1, 0], vec![1.5, -2.]);
+    /// let b = a.map(|x| 2. * x);
+    /// assert_eq!(a.pattern(), b.pattern());
+    /// assert_eq!(a.pattern().data(), &[true, true]);
+    /// ```
+    pub fn pattern(&self) -> CsMatI<bool, I, Iptr> {
+        self.map(|_| true)
+    }
+
+    pub fn to_dense(&self) -> Array<N, Ix2>
+    where
+        N: Clone + Zero,
+    {
+        let mut res = Array::zeros((self.rows(), self.cols()));
+        assign_to_dense(res.view_mut(), self.view());
+        res
+    }
+
+    /// Write this matrix into the dense array `out`, which allows reusing
+    /// its allocation. The entries of `out` that are not stored in this
+    /// matrix are set to zero.
+    ///
+    /// The stored entries are scattered following the storage order of
+    /// this matrix, and the buffer is zeroed following its own memory
+    /// layout. The writes are thus contiguous when the layout of `out`
+    /// matches the storage of this matrix, ie a row-major array for a CSR
+    /// matrix and a column-major array for a CSC matrix.
+    ///
+    /// # Panics
+    ///
+    /// If the shape of `out` is not `(self.rows(), self.cols())`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// use ndarray::Array2;
+    /// let mut buffer = Array2::from_elem((3, 3), 7.);
+    /// let eye = CsMat::<f64>::eye(3);
+    /// eye.to_dense_into(buffer.view_mut());
+    /// assert_eq!(buffer, Array2::eye(3));
+    /// ```
+    pub fn to_dense_into(&self, mut out: ArrayViewMut2<N>)
+    where
+        N: Clone + Zero,
+    {
+        assert_eq!(out.dim(), self.shape(), "Dimension mismatch");
+        out.fill(N::zero());
+        assign_to_dense(out, self.view());
+    }
+
+    /// Expand the outer dimension `i` (eg the row `i` for a CSR matrix)
+    /// into a dense vector of length `inner_dims()`, with zeros where no
+    /// element is stored.
+    ///
+    /// # Panics
+    ///
+    /// If `i` is out of bounds.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// use ndarray::arr1;
+    /// let mat = CsMat::new((2, 3), vec![0, 2, 3], vec![0, 2, 1], vec![1, 2, 3]);
+    /// assert_eq!(mat.outer_to_dense(0), arr1(&[1, 0, 2]));
+    /// assert_eq!(mat.inner_to_dense(2), arr1(&[2, 0]));
+    /// ```
+    pub fn outer_to_dense(&self, i: usize) -> Array<N, Ix1>
+    where
+        N: Clone + Zero,
+    {
+        self.outer_view(i).expect("Out of bounds index").to_dense()
+    }
+
+    /// Expand the inner dimension `j` (eg the column `j` for a CSR matrix)
+    /// into a dense vector of length `outer_dims()`, with zeros where no
+    /// element is stored.
+    ///
+    /// This requires a lookup in each outer dimension, and is thus more
+    /// costly than [`outer_to_dense`](Self::outer_to_dense).
+    ///
+    /// # Panics
+    ///
+    /// If `j` is out of bounds.
+    pub fn inner_to_dense(&self, j: usize) -> Array<N, Ix1>
+    where
+        N: Clone + Zero,
+    {
+        assert!(j < self.inner_dims(), "Out of bounds index");
+        Array::from_iter((0..self.outer_dims()).map(|i| {
+            self.get_outer_inner(i, j).cloned().unwrap_or_else(N::zero)
+        }))
+    }
+
+    /// Return an outer iterator for the matrix
+    ///
+    /// This can be used for iterating over the rows (resp. cols) of
+    /// a CSR (resp. CSC) matrix.
+    ///
+    /// ```rust
+    /// use sprs::{CsMat};
+    /// let eye = CsMat::eye(5);
+    /// for (row_ind, row_vec) in eye.outer_iterator().enumerate() {
+    ///     let (col_ind, &val): (_, &f64) = row_vec.iter().next().unwrap();
+    ///     assert_eq!(row_ind, col_ind);
+    ///     assert_eq!(val, 1.);
+    /// }
+    /// ```
+    pub fn outer_iterator(
+        &self,
+    ) -> impl std::iter::DoubleEndedIterator<Item = CsVecViewI<N, I>>
+           + std::iter::ExactSizeIterator<Item = CsVecViewI<N, I>>
+           + '_ {
+        self.indptr.iter_outer_sz().map(move |range| {
+            CsVecViewI::new_trusted(
+                self.inner_dims(),
+                // TODO: unsafe slice indexing
+                &self.indices[range.clone()],
+                &self.data[range],
+            )
+        })
+    }
+
+    /// Return a parallel iterator over the outer dimension of the matrix,
+    /// yielding the index of each outer slice along with a view of it, as
+    /// `outer_iterator().enumerate()` would.
+    ///
+    /// This is only available with the `multi_thread` feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rayon::prelude::*;
+    /// use sprs::CsMat;
+    /// let mat = CsMat::new((2, 3), vec![0, 2, 3], vec![0, 2, 1], vec![1., 2., 3.]);
+    /// let row_sums: Vec<f64> = mat
+    ///     .par_outer_iterator()
+    ///     .map(|(_, row)| row.data().iter().sum())
+    ///     .collect();
+    /// assert_eq!(row_sums, vec![3., 3.]);
+    /// ```
+    #[cfg(feature = "multi_thread")]
+    pub fn par_outer_iterator(
+        &self,
+    ) -> impl rayon::iter::IndexedParallelIterator<
+        Item = (usize, CsVecViewI<'_, N, I>),
+    > + '_
+    where
+        N: Sync,
+        IptrStorage: Sync,
+        IndStorage: Sync,
+        DataStorage: Sync,
+    {
+        use rayon::prelude::*;
+        (0..self.outer_dims()).into_par_iter().map(move |i| {
+            let range = self.indptr.outer_inds_sz(i);
+            let vec = CsVecViewI::new_trusted(
+                self.inner_dims(),
+                &self.indices[range.clone()],
+                &self.data[range],
+            );
+            (i, vec)
+        })
+    }
+
+    /// Return an outer iterator over P*A*P^T, where it is necessary to use
+    /// `CsVec::iter_perm(perm.inv())` to iterate over the inner dimension.
+    /// Unstable, this is a convenience function for the crate `sprs-ldl`
+    /// for now.
+    #[doc(hidden)]
+    pub fn outer_iterator_papt<'a, 'perm: 'a>(
+        &'a self,
+        perm: PermViewI<'perm, I>,
+    ) -> impl std::iter::DoubleEndedIterator<Item = (usize, CsVecViewI<N, I>)>
+           + std::iter::ExactSizeIterator<Item = (usize, CsVecViewI<N, I>)>
+           + '_ {
+        (0..self.outer_dims()).into_iter().map(move |outer_ind| {
+            let outer_ind_perm = perm.at(outer_ind);
+            let range = self.indptr.outer_inds_sz(outer_ind_perm);
+            let indices = &self.indices[range.clone()];
+            let data = &self.data[range];
+            // CsMat invariants imply CsVec invariants
+            let vec = CsVecBase::new_trusted(self.inner_dims(), indices, data);
+            (outer_ind_perm, vec)
+        })
+    }
+
+    /// Check whether the inner indices of each outer slice are sorted in
+    /// increasing order, without duplicates.
+    ///
+    /// This is always the case for matrices built through the checked
+    /// constructors, but may not hold for matrices created using
+    /// [`new_unchecked`](Self::new_unchecked).
+    pub fn is_sorted(&self) -> bool {
+        self.indptr
+            .iter_outer_sz()
+            .all(|range| utils::sorted_indices(&self.indices[range]))
+    }
+
+    /// Check whether the stored pattern of this matrix is lower triangular,
+    /// ie whether no entry is stored above the diagonal. Explicitly stored
+    /// zeros are considered part of the pattern.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let mat = CsMat::new((2, 2), vec![0, 1, 3], vec![0, 0, 1], vec![1, 2, 3]);
+    /// assert!(mat.is_lower_triangular());
+    /// assert!(!mat.is_upper_triangular());
+    /// assert!(mat.transpose_view().is_upper_triangular());
+    /// ```
+    pub fn is_lower_triangular(&self) -> bool {
+        match self.storage {
+            CSR => self.all_outer_inner(|outer, inner| inner <= outer),
+            CSC => self.all_outer_inner(|outer, inner| inner >= outer),
+        }
+    }
+
+    /// Check whether the stored pattern of this matrix is upper triangular,
+    /// ie whether no entry is stored below the diagonal. Explicitly stored
+    /// zeros are considered part of the pattern.
+    pub fn is_upper_triangular(&self) -> bool {
+        match self.storage {
+            CSR => self.all_outer_inner(|outer, inner| inner >= outer),
+            CSC => self.all_outer_inner(|outer, inner| inner <= outer),
+        }
+    }
+
+    /// Compute the lower and upper bandwidths of this matrix, ie the
+    /// maximum of `row - col` and of `col - row` over the stored entries.
+    /// A diagonal matrix thus has bandwidths `(0, 0)`, and a matrix without
+    /// entries below (resp. above) the diagonal has a lower (resp. upper)
+    /// bandwidth of `0`, which also holds for rectangular matrices.
+    ///
+    /// Explicitly stored zeros are considered part of the pattern.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// use ndarray::arr2;
+    /// let mat = CsMat::csr_from_dense(
+    ///     arr2(&[[1, 0, 0, 2], [3, 4, 0, 0], [0, 0, 5, 0]]).view(), 0
+    /// );
+    /// assert_eq!(mat.bandwidth(), (1, 3));
+    /// assert_eq!(CsMat::<f64>::eye(3).bandwidth(), (0, 0));
+    /// ```
+    pub fn bandwidth(&self) -> (usize, usize) {
+        let (mut lower, mut upper) = (0, 0);
+        for (outer, range) in self.indptr.iter_outer_sz().enumerate() {
+            for inner in &self.indices[range] {
+                let inner = inner.index_unchecked();
+                let (row, col) = match self.storage {
+                    CSR => (outer, inner),
+                    CSC => (inner, outer),
+                };
+                if row > col {
+                    lower = lower.max(row - col);
+                } else {
+                    upper = upper.max(col - row);
+                }
+            }
+        }
+        (lower, upper)
+    }
+
+    /// Extract the upper triangular part of this matrix, ie the entries on
+    /// or above the `k`-th diagonal, as `numpy.triu` does. `k = 0` is the
+    /// main diagonal, `k > 0` is above it and `k < 0` is below it.
+    ///
+    /// The result has the storage of `self`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// use ndarray::arr2;
+    /// let mat = CsMat::csr_from_dense(
+    ///     arr2(&[[1, 2, 3], [4, 5, 6], [7, 8, 9]]).view(), 0
+    /// );
+    /// assert_eq!(mat.triu(1).to_dense(), arr2(&[[0, 2, 3], [0, 0, 6], [0, 0, 0]]));
+    /// assert_eq!(mat.tril(0).to_dense(), arr2(&[[1, 0, 0], [4, 5, 0], [7, 8, 9]]));
+    /// assert_eq!(mat.tril(-2).to_dense(), arr2(&[[0, 0, 0], [0, 0, 0], [7, 0, 0]]));
+    /// ```
+    pub fn triu(&self, k: isize) -> CsMatI<N, I, Iptr>
+    where
+        N: Clone,
+    {
+        self.filter_by_diag_offset(|offset| offset >= k)
+    }
+
+    /// Extract the lower triangular part of this matrix, ie the entries on
+    /// or below the `k`-th diagonal, as `numpy.tril` does. `k = 0` is the
+    /// main diagonal, `k > 0` is above it and `k < 0` is below it.
+    ///
+    /// The result has the storage of `self`.
+    pub fn tril(&self, k: isize) -> CsMatI<N, I, Iptr>
+    where
+        N: Clone,
+    {
+        self.filter_by_diag_offset(|offset| offset <= k)
+    }
+
+    /// Only keep the entries for which `keep(col - row)` is `true`
+    fn filter_by_diag_offset<F>(&self, keep: F) -> CsMatI<N, I, Iptr>
+    where
+        N: Clone,
+        F: Fn(isize) -> bool,
+    {
+        let mut indptr = Vec::with_capacity(self.outer_dims() + 1);
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        indptr.push(Iptr::zero());
+        for (outer, range) in self.indptr.iter_outer_sz().enumerate() {
+            for (&inner, val) in
+                self.indices[range.clone()].iter().zip(&self.data[range])
+            {
+                let offset = inner.index() as isize - outer as isize;
+                let offset = match self.storage {
+                    CSR => offset,
+                    CSC => -offset,
+                };
+                if keep(offset) {
+                    indices.push(inner);
+                    data.push(val.clone());
+                }
+            }
+            indptr.push(Iptr::from_usize(indices.len()));
+        }
+        CsMatI::new_trusted(self.storage, self.shape(), indptr, indices, data)
+    }
+
+    /// Check whether this matrix is equal to its transpose, both in pattern
+    /// and in values. Explicitly stored zeros are considered part of the
+    /// pattern. Rectangular matrices are never symmetric.
+    ///
+    /// This looks up the transposed position of each non-zero, and does
+    /// not need to compute the transpose of the matrix.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let mat = CsMat::new((2, 2), vec![0, 2, 4], vec![0, 1, 0, 1], vec![1, 2, 2, 3]);
+    /// assert!(mat.is_symmetric());
+    /// let mat = CsMat::new((2, 2), vec![0, 2, 4], vec![0, 1, 0, 1], vec![1, 2, 4, 3]);
+    /// assert!(!mat.is_symmetric());
+    /// assert!(mat.is_structurally_symmetric());
+    /// ```
+    pub fn is_symmetric(&self) -> bool
+    where
+        N: PartialEq,
+    {
+        self.all_mirrored(|val, mirror| val == mirror)
+    }
+
+    /// Check whether the pattern of this matrix is equal to the pattern
+    /// of its transpose, regardless of the values. Rectangular matrices are
+    /// never structurally symmetric.
+    pub fn is_structurally_symmetric(&self) -> bool {
+        self.all_mirrored(|_, _| true)
+    }
+
+    /// Check that each stored entry has a stored mirror entry with respect
+    /// to the diagonal, and that `pred(val, mirror)` holds for each of them
+    fn all_mirrored<F>(&self, pred: F) -> bool
+    where
+        F: Fn(&N, &N) -> bool,
+    {
+        if self.rows() != self.cols() {
+            return false;
+        }
+        self.indptr
+            .iter_outer_sz()
+            .enumerate()
+            .all(|(outer, range)| {
+                self.indices[range.clone()]
+                    .iter()
+                    .zip(&self.data[range])
+                    .all(|(inner, val)| {
+                        let inner = inner.index_unchecked();
+                        if inner == outer {
+                            return true;
+                        }
+                        match self.get_outer_inner(inner, outer) {
+                            Some(mirror) => pred(val, mirror),
+                            None => false,
+                        }
+                    })
+            })
+    }
+
+    /// Check that `pred(outer, inner)` holds for each stored entry,
+    /// stopping at the first violation
+    fn all_outer_inner<F>(&self, pred: F) -> bool
+    where
+        F: Fn(usize, usize) -> bool,
+    {
+        self.indptr
+            .iter_outer_sz()
+            .enumerate()
+            .all(|(outer, range)| {
+                self.indices[range]
+                    .iter()
+                    .all(|inner| pred(outer, inner.index_unchecked()))
+            })
+    }
+
+    /// Get the max number of nnz for each outer dim
+    pub fn max_outer_nnz(&self) -> usize {
+        self.outer_iterator()
+            .map(|outer| outer.indices().len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Get the number of non-zeros of each outer dimension (eg of each row
+    /// for a CSR matrix). This is computed from the index pointer.
+    pub fn nnz_per_outer(&self) -> Vec<usize> {
+        self.indptr
+            .iter_outer_sz()
+            .map(|range| range.len())
+            .collect()
+    }
+
+    /// Get the number of non-zeros of each inner dimension (eg of each
+    /// column for a CSR matrix). This requires a pass over the indices.
+    pub fn nnz_per_inner(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.inner_dims()];
+        for ind in self.indices.iter() {
+            counts[ind.index()] += 1;
+        }
+        counts
+    }
+
+    /// Get the number of non-zeros of each row, whatever the storage
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let mat = CsMat::new((2, 3), vec![0, 3, 4], vec![0, 1, 2, 1], vec![1; 4]);
+    /// assert_eq!(mat.nnz_per_row(), vec![3, 1]);
+    /// assert_eq!(mat.nnz_per_col(), vec![1, 2, 1]);
+    /// assert_eq!(mat.to_csc().nnz_per_row(), vec![3, 1]);
+    /// ```
+    pub fn nnz_per_row(&self) -> Vec<usize> {
+        match self.storage {
+            CSR => self.nnz_per_outer(),
+            CSC => self.nnz_per_inner(),
+        }
+    }
+
+    /// Get the number of non-zeros of each column, whatever the storage
+    pub fn nnz_per_col(&self) -> Vec<usize> {
+        match self.storage {
+            CSR => self.nnz_per_inner(),
+            CSC => self.nnz_per_outer(),
+        }
+    }
+
+    /// Get the degrees of each vertex on a symmetric matrix
+    ///
+    /// The nonzero pattern of a symmetric matrix can be interpreted as
+    /// an undirected graph. In such a graph, a vertex i is connected to another
+    /// vertex j if there is a corresponding nonzero entry in the matrix at
+    /// location (i, j).
+    ///
+    /// This function returns a vector containing the degree of each vertex,
+    /// that is to say the number of neighbor of each vertex. We do not
+    /// count diagonal entries as a neighbor.
+    pub fn degrees(&self) -> Vec<usize> {
+        self.outer_iterator()
+            .enumerate()
+            .map(|(outer_dim, outer)| {
+                outer
+                    .indices()
+                    .iter()
+                    .filter(|ind| ind.index() != outer_dim)
+                    .count()
+            })
+            .collect()
+    }
+
+    /// Get a view into the i-th outer dimension (eg i-th row for a CSR matrix)
+    pub fn outer_view(&self, i: usize) -> Option<CsVecViewI<N, I>> {
+        if i >= self.outer_dims() {
+            return None;
+        }
+        let range = self.indptr.outer_inds_sz(i);
+        // CsMat invariants imply CsVec invariants
+        Some(CsVecViewI::new_trusted(
+            self.inner_dims(),
+            // TODO: unsafe slice indexing
+            &self.indices[range.clone()],
+            &self.data[range],
+        ))
+    }
+
+    /// Get the diagonal of a sparse matrix
+    pub fn diag(&self) -> CsVecI<N, I>
+    where
+        N: Clone,
+    {
+        let shape = self.shape();
+        let smallest_dim: usize = cmp::min(shape.0, shape.1);
+        // Assuming most matrices have dense diagonals, it seems prudent
+        // to allocate a bit of memory up front
+        let heuristic = smallest_dim / 2;
+        let mut index_vec = Vec::with_capacity(heuristic);
+        let mut data_vec = Vec::with_capacity(heuristic);
+
+        for i in 0..smallest_dim {
+            let optional_index = self.nnz_index(i, i);
+            if let Some(idx) = optional_index {
+                data_vec.push(self[idx].clone());
+                index_vec.push(I::from_usize(i));
+            }
+        }
+        data_vec.shrink_to_fit();
+        index_vec.shrink_to_fit();
+        CsVecI::new_trusted(smallest_dim, index_vec, data_vec)
+    }
+
+    /// Get the `k`-th diagonal of a sparse matrix
+    ///
+    /// A positive `k` selects a diagonal above the main diagonal, ie the
+    /// entries `(i, i + k)`, while a negative `k` selects a diagonal below
+    /// the main diagonal, ie the entries `(i - k, i)`. `diag_offset(0)` is
+    /// equivalent to `diag()`.
+    ///
+    /// The dimension of the returned vector is the number of positions on
+    /// the requested diagonal, which is zero if `k` is out of the matrix.
+    pub fn diag_offset(&self, k: isize) -> CsVecI<N, I>
+    where
+        N: Clone,
+    {
+        let (row_offset, col_offset) = if k >= 0 {
+            (0, k as usize)
+        } else {
+            (k.wrapping_neg() as usize, 0)
+        };
+        let diag_len = cmp::min(
+            self.rows().saturating_sub(row_offset),
+            self.cols().saturating_sub(col_offset),
+        );
+        let mut index_vec = Vec::new();
+        let mut data_vec = Vec::new();
+        for i in 0..diag_len {
+            if let Some(val) = self.get(i + row_offset, i + col_offset) {
+                data_vec.push(val.clone());
+                index_vec.push(I::from_usize(i));
+            }
+        }
+        CsVecI::new_trusted(diag_len, index_vec, data_vec)
+    }
+
+    /// Split a sparse matrix into its diagonal and its strictly
+    /// off-diagonal part, in a single pass over the non-zeros.
+    ///
+    /// The diagonal is returned as a sparse vector of dimension
+    /// `min(rows, cols)`, as [`diag`](Self::diag) would, and the
+    /// off-diagonal part has the same shape and storage as `self`. This is
+    /// the decomposition used by Jacobi or Gauss-Seidel like iterations.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::{CsMat, CsVec};
+    /// let mat = CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 1], vec![1, 2, 3]);
+    /// let (diag, off_diag) = mat.split_diag();
+    /// assert_eq!(diag, CsVec::new(2, vec![0, 1], vec![1, 3]));
+    /// assert_eq!(off_diag, CsMat::new((2, 2), vec![0, 1, 1], vec![1], vec![2]));
+    /// ```
+    pub fn split_diag(&self) -> (CsVecI<N, I>, CsMatI<N, I, Iptr>)
+    where
+        N: Clone,
+    {
+        let mut diag_inds = Vec::new();
+        let mut diag_data = Vec::new();
+        let mut indptr = Vec::with_capacity(self.outer_dims() + 1);
+        let mut indices = Vec::with_capacity(self.nnz());
+        let mut data = Vec::with_capacity(self.nnz());
+        indptr.push(Iptr::zero());
+        for (outer, range) in self.indptr.iter_outer_sz().enumerate() {
+            for (&inner, val) in
+                self.indices[range.clone()].iter().zip(&self.data[range])
+            {
+                if inner.index() == outer {
+                    diag_inds.push(inner);
+                    diag_data.push(val.clone());
+                } else {
+                    indices.push(inner);
+                    data.push(val.clone());
+                }
+            }
+            indptr.push(Iptr::from_usize(indices.len()));
+        }
+        let diag_len = cmp::min(self.rows(), self.cols());
+        let diag = CsVecI::new_trusted(diag_len, diag_inds, diag_data);
+        let off_diag = CsMatI::new_trusted(
+            self.storage,
+            self.shape(),
+            indptr,
+            indices,
+            data,
+        );
+        (diag, off_diag)
+    }
+
+    /// Sum of the entries on the diagonal of a sparse matrix
+    ///
+    /// For rectangular matrices, this sums over the `min(rows, cols)`
+    /// diagonal positions. Returns zero if there is no diagonal entry.
+    pub fn trace(&self) -> N
+    where
+        N: Num + Clone,
+    {
+        self.diag_iter()
+            .flatten()
+            .fold(N::zero(), |acc, val| acc + val.clone())
+    }
+
+    /// Compute the `n`-th power of this square matrix, using
+    /// exponentiation by squaring, ie about `log2(n)` sparse products.
+    /// `A^0` is the identity matrix. The result has the storage of `self`.
+    ///
+    /// Note that powers of a sparse matrix tend to fill in quickly: `A^n`
+    /// has a non-zero at `(i, j)` as soon as there is a path of length `n`
+    /// from `i` to `j` in the graph of `A`, so large exponents can give
+    /// almost dense results.
+    ///
+    /// # Panics
+    ///
+    /// If the matrix is not square.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// // the adjacency matrix of a directed path 0 -> 1 -> 2
+    /// let a = CsMat::new((3, 3), vec![0, 1, 2, 2], vec![1, 2], vec![1, 1]);
+    /// let a2 = a.pow(2);
+    /// assert_eq!(a2, CsMat::new((3, 3), vec![0, 1, 1, 1], vec![2], vec![1]));
+    /// assert_eq!(a.pow(3).nnz(), 0);
+    /// assert_eq!(a.pow(0), CsMat::eye(3));
+    /// ```
+    pub fn pow(&self, n: u32) -> CsMatI<N, I, Iptr>
+    where
+        N: Num + Clone + crate::MulAcc + Default + Send + Sync,
+    {
+        assert_eq!(self.rows(), self.cols(), "Non square matrix");
+        if n == 0 {
+            return match self.storage {
+                CSR => CsMatI::eye(self.rows()),
+                CSC => CsMatI::eye_csc(self.rows()),
+            };
+        }
+        let mut n = n;
+        let mut square = self.to_owned();
+        let mut res: Option<CsMatI<N, I, Iptr>> = None;
+        loop {
+            if n & 1 == 1 {
+                res = Some(match res {
+                    Some(res) => &res * &square,
+                    None => square.clone(),
+                });
+            }
+            n >>= 1;
+            if n == 0 {
+                break;
+            }
+            square = &square * &square;
+        }
+        res.unwrap()
+    }
+
+    /// Compute the sparse matrix product `self * rhs`, with the result in
+    /// the requested storage.
+    ///
+    /// The product routine natively produces a CSR result from CSR operands.
+    /// A CSC result is obtained natively as the transpose of the CSR product
+    /// `rhs^T * self^T`, whose operands are CSR views of CSC matrices. The
+    /// operands are only converted when their storage does not match
+    /// `out_storage`, so this avoids converting the result when the next
+    /// operation expects a given storage.
+    ///
+    /// # Panics
+    ///
+    /// If the number of columns of `self` does not match the number of rows
+    /// of `rhs`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::{CsMat, CompressedStorage::CSC};
+    /// let a = CsMat::new_csc((2, 2), vec![0, 1, 2], vec![1, 0], vec![1., 2.]);
+    /// let b = CsMat::new_csc((2, 2), vec![0, 2, 2], vec![0, 1], vec![3., 4.]);
+    /// let c = a.mul_choose_storage(&b.view(), CSC);
+    /// assert!(c.is_csc());
+    /// assert_eq!(c, &a * &b);
+    /// ```
+    pub fn mul_choose_storage(
+        &self,
+        rhs: &CsMatViewI<N, I, Iptr>,
+        out_storage: CompressedStorage,
+    ) -> CsMatI<N, I, Iptr>
+    where
+        N: Clone + crate::MulAcc + Zero + Default + Send + Sync,
+    {
+        assert_eq!(self.cols(), rhs.rows(), "Dimension mismatch");
+        let (lhs_tmp, rhs_tmp);
+        let lhs = if self.storage == out_storage {
+            self.view()
+        } else {
+            lhs_tmp = self.to_other_storage();
+            lhs_tmp.view()
+        };
+        let rhs = if rhs.storage() == out_storage {
+            rhs.view()
+        } else {
+            rhs_tmp = rhs.to_other_storage();
+            rhs_tmp.view()
+        };
+        match out_storage {
+            CSR => smmp::mul_csr_csr(lhs, rhs),
+            CSC => {
+                smmp::mul_csr_csr(rhs.transpose_view(), lhs.transpose_view())
+                    .transpose_into()
+            }
+        }
+    }
+
+    /// Compute the sparse product of this matrix with `rhs`, detecting
+    /// overflows in the accumulation of the values. This is intended for
+    /// integer scalars, where the product operator would silently wrap
+    /// around (or panic in debug builds). The result has the storage of
+    /// `self`.
+    ///
+    /// The computation is sequential, and is slower than the product
+    /// operator, which should be preferred for floating point scalars.
+    ///
+    /// # Errors
+    ///
+    /// `ArithmeticError::Overflow` pointing at the location of the first
+    /// value of the product whose computation overflowed.
+    ///
+    /// # Panics
+    ///
+    /// If the number of columns of `self` does not match the number of
+    /// rows of `rhs`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// use sprs::errors::ArithmeticError;
+    /// let a = CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 1], vec![1i8, 2, 16]);
+    /// let c = a.checked_mul(&CsMat::eye(2).view()).unwrap();
+    /// assert_eq!(c, a);
+    /// let res = a.checked_mul(&a.view());
+    /// assert_eq!(res, Err(ArithmeticError::Overflow { row: 1, col: 1 }));
+    /// ```
+    pub fn checked_mul(
+        &self,
+        rhs: &CsMatViewI<N, I, Iptr>,
+    ) -> Result<CsMatI<N, I, Iptr>, ArithmeticError>
+    where
+        N: Clone + crate::CheckedMulAcc + Zero + Default,
+    {
+        assert_eq!(self.cols(), rhs.rows(), "Dimension mismatch");
+        let rhs_tmp;
+        let lhs = self.view();
+        let rhs = if rhs.storage() == self.storage {
+            rhs.view()
+        } else {
+            rhs_tmp = rhs.to_other_storage();
+            rhs_tmp.view()
+        };
+        match self.storage {
+            CSR => smmp::checked_mul_csr_csr(lhs, rhs),
+            CSC => smmp::checked_mul_csr_csr(
+                rhs.transpose_view(),
+                lhs.transpose_view(),
+            )
+            .map(CsMatBase::transpose_into)
+            .map_err(|e| match e {
+                ArithmeticError::Overflow { row, col } => {
+                    ArithmeticError::Overflow { row: col, col: row }
+                }
+            }),
+        }
+    }
+
+    /// Compute the product `A^T x` of the transpose of this matrix with the
+    /// dense vector `x`, without materializing the transpose nor converting
+    /// the storage. For a CSR matrix, this is a single pass over the stored
+    /// entries accumulating `y[col] += a[row, col] * x[row]`.
+    ///
+    /// # Panics
+    ///
+    /// If the length of `x` does not match the number of rows.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// use ndarray::arr1;
+    /// let a = CsMat::new((2, 3), vec![0, 2, 3], vec![0, 2, 1], vec![1., 2., 3.]);
+    /// let x = arr1(&[1., -1.]);
+    /// assert_eq!(a.trans_mul_vec(x.view()), arr1(&[1., -3., 2.]));
+    /// assert_eq!(a.trans_mul_vec(x.view()), &a.transpose_view() * &x);
+    /// ```
+    pub fn trans_mul_vec(&self, x: ndarray::ArrayView1<N>) -> Array<N, Ix1>
     where
-        N: Clone + Zero,
+        N: Clone + crate::MulAcc + Zero,
     {
-        let mut res = Array::zeros((self.rows(), self.cols()));
-        assign_to_dense(res.view_mut(), self.view());
+        assert_eq!(x.len(), self.rows(), "Dimension mismatch");
+        let mut res = Array::zeros(self.cols());
+        let transposed = self.transpose_view();
+        match transposed.storage() {
+            CSR => prod::mul_acc_mat_vec_csr(transposed, x, res.view_mut()),
+            CSC => prod::mul_acc_mat_vec_csc(transposed, x, res.view_mut()),
+        }
         res
     }
 
-    /// Return an outer iterator for the matrix
-    ///
-    /// This can be used for iterating over the rows (resp. cols) of
-    /// a CSR (resp. CSC) matrix.
+    /// Compute the sum of the entries of each row of the matrix
     ///
-    /// ```rust
-    /// use sprs::{CsMat};
-    /// let eye = CsMat::eye(5);
-    /// for (row_ind, row_vec) in eye.outer_iterator().enumerate() {
-    ///     let (col_ind, &val): (_, &f64) = row_vec.iter().next().unwrap();
-    ///     assert_eq!(row_ind, col_ind);
-    ///     assert_eq!(val, 1.);
-    /// }
-    /// ```
-    pub fn outer_iterator(
-        &self,
-    ) -> impl std::iter::DoubleEndedIterator<Item = CsVecViewI<N, I>>
-           + std::iter::ExactSizeIterator<Item = CsVecViewI<N, I>>
-           + '_ {
-        self.indptr.iter_outer_sz().map(move |range| {
-            CsVecViewI::new_trusted(
-                self.inner_dims(),
-                // TODO: unsafe slice indexing
-                &self.indices[range.clone()],
-                &self.data[range],
-            )
-        })
+    /// Empty rows have a sum of zero.
+    pub fn row_sums(&self) -> Array<N, Ix1>
+    where
+        N: Num + Clone,
+    {
+        let sums = match self.storage() {
+            CSR => self.outer_sums_by(N::clone),
+            CSC => self.inner_sums_by(N::clone),
+        };
+        Array::from(sums)
     }
 
-    /// Return an outer iterator over P*A*P^T, where it is necessary to use
-    /// `CsVec::iter_perm(perm.inv())` to iterate over the inner dimension.
-    /// Unstable, this is a convenience function for the crate `sprs-ldl`
-    /// for now.
-    #[doc(hidden)]
-    pub fn outer_iterator_papt<'a, 'perm: 'a>(
-        &'a self,
-        perm: PermViewI<'perm, I>,
-    ) -> impl std::iter::DoubleEndedIterator<Item = (usize, CsVecViewI<N, I>)>
-           + std::iter::ExactSizeIterator<Item = (usize, CsVecViewI<N, I>)>
-           + '_ {
-        (0..self.outer_dims()).into_iter().map(move |outer_ind| {
-            let outer_ind_perm = perm.at(outer_ind);
-            let range = self.indptr.outer_inds_sz(outer_ind_perm);
-            let indices = &self.indices[range.clone()];
-            let data = &self.data[range];
-            // CsMat invariants imply CsVec invariants
-            let vec = CsVecBase::new_trusted(self.inner_dims(), indices, data);
-            (outer_ind_perm, vec)
-        })
+    /// Compute the sum of the entries of each column of the matrix
+    ///
+    /// Empty columns have a sum of zero.
+    pub fn col_sums(&self) -> Array<N, Ix1>
+    where
+        N: Num + Clone,
+    {
+        let sums = match self.storage() {
+            CSR => self.inner_sums_by(N::clone),
+            CSC => self.outer_sums_by(N::clone),
+        };
+        Array::from(sums)
     }
 
-    /// Get the max number of nnz for each outer dim
-    pub fn max_outer_nnz(&self) -> usize {
-        self.outer_iterator()
-            .map(|outer| outer.indices().len())
-            .max()
-            .unwrap_or(0)
+    /// Compute the mean of each outer slice of the matrix, ie of each row
+    /// for a CSR matrix and of each column for a CSC matrix.
+    ///
+    /// The implicit zeros are accounted for: the mean of an outer slice is
+    /// the sum of its entries divided by the inner dimension, and not by
+    /// its number of non-zeros. The means are `NaN` if the inner dimension
+    /// is zero.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let mat = CsMat::new((2, 4), vec![0, 2, 2], vec![0, 3], vec![2., 6.]);
+    /// assert_eq!(mat.mean_outer(), ndarray::arr1(&[2., 0.]));
+    /// ```
+    pub fn mean_outer(&self) -> Array<N, Ix1>
+    where
+        N: Float,
+    {
+        let inner_dims = N::from(self.inner_dims()).unwrap();
+        self.outer_sums_by(|&x| x)
+            .into_iter()
+            .map(|sum| sum / inner_dims)
+            .collect()
     }
 
-    /// Get the degrees of each vertex on a symmetric matrix
+    /// Compute the population variance of each outer slice of the matrix,
+    /// ie of each row for a CSR matrix and of each column for a CSC matrix.
     ///
-    /// The nonzero pattern of a symmetric matrix can be interpreted as
-    /// an undirected graph. In such a graph, a vertex i is connected to another
-    /// vertex j if there is a corresponding nonzero entry in the matrix at
-    /// location (i, j).
+    /// As for [`mean_outer`](Self::mean_outer), the implicit zeros are
+    /// accounted for, and the variances are `NaN` if the inner dimension
+    /// is zero.
     ///
-    /// This function returns a vector containing the degree of each vertex,
-    /// that is to say the number of neighbor of each vertex. We do not
-    /// count diagonal entries as a neighbor.
-    pub fn degrees(&self) -> Vec<usize> {
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let mat = CsMat::new((2, 4), vec![0, 2, 2], vec![0, 3], vec![2., 6.]);
+    /// // mean 2, squared deviations 0 + 4 + 4 + 16
+    /// assert_eq!(mat.var_outer(), ndarray::arr1(&[6., 0.]));
+    /// ```
+    pub fn var_outer(&self) -> Array<N, Ix1>
+    where
+        N: Float,
+    {
+        let inner_dims = self.inner_dims();
+        let means = self.mean_outer();
         self.outer_iterator()
-            .enumerate()
-            .map(|(outer_dim, outer)| {
-                outer
-                    .indices()
+            .zip(means.iter())
+            .map(|(vec, &mean)| {
+                let nb_zeros = N::from(inner_dims - vec.nnz()).unwrap();
+                let squared_devs = vec
+                    .data()
                     .iter()
-                    .filter(|ind| ind.index() != outer_dim)
-                    .count()
+                    .fold(N::zero(), |acc, &x| acc + (x - mean) * (x - mean));
+                (squared_devs + nb_zeros * mean * mean)
+                    / N::from(inner_dims).unwrap()
             })
             .collect()
     }
 
-    /// Get a view into the i-th outer dimension (eg i-th row for a CSR matrix)
-    pub fn outer_view(&self, i: usize) -> Option<CsVecViewI<N, I>> {
-        if i >= self.outer_dims() {
-            return None;
+    /// Compute `diag(left) * self * diag(right)` in a single pass, ie the
+    /// entry `(i, j)` of the result is `left[i] * a_ij * right[j]`. The
+    /// result has the same structure and storage as this matrix.
+    ///
+    /// This is the two-sided scaling used to equilibrate a matrix, see
+    /// [`scale_rows`](Self::scale_rows) and
+    /// [`scale_cols`](Self::scale_cols) for in-place one-sided versions.
+    ///
+    /// # Panics
+    ///
+    /// If `left.len()` is not equal to the number of rows, or if
+    /// `right.len()` is not equal to the number of columns.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let mat = CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 1], vec![1., 2., 3.]);
+    /// let scaled = mat.diagonal_scale(&[1., 2.], &[3., 0.5]);
+    /// assert_eq!(scaled.data(), &[3., 1., 3.]);
+    /// ```
+    pub fn diagonal_scale(&self, left: &[N], right: &[N]) -> CsMatI<N, I, Iptr>
+    where
+        for<'r> &'r N: Mul<&'r N, Output = N>,
+    {
+        assert_eq!(left.len(), self.rows(), "Dimension mismatch");
+        assert_eq!(right.len(), self.cols(), "Dimension mismatch");
+        let (outer_diag, inner_diag) = match self.storage {
+            CSR => (left, right),
+            CSC => (right, left),
+        };
+        let mut data = Vec::with_capacity(self.nnz());
+        for (outer_factor, range) in
+            outer_diag.iter().zip(self.indptr.iter_outer_sz())
+        {
+            for (inner, val) in
+                self.indices[range.clone()].iter().zip(&self.data[range])
+            {
+                let scaled = outer_factor * val;
+                data.push(&scaled * &inner_diag[inner.index()]);
+            }
         }
-        let range = self.indptr.outer_inds_sz(i);
-        // CsMat invariants imply CsVec invariants
-        Some(CsVecViewI::new_trusted(
-            self.inner_dims(),
-            // TODO: unsafe slice indexing
-            &self.indices[range.clone()],
-            &self.data[range],
-        ))
+        CsMatI::new_trusted(
+            self.storage,
+            self.shape(),
+            self.indptr.to_proper().into_owned(),
+            self.indices.to_vec(),
+            data,
+        )
     }
 
-    /// Get the diagonal of a sparse matrix
-    pub fn diag(&self) -> CsVecI<N, I>
+    /// Compute the L2-norm of each row of the matrix
+    ///
+    /// Empty rows have a norm of zero.
+    pub fn row_norms(&self) -> Vec<N>
     where
-        N: Clone,
+        N: Float,
     {
-        let shape = self.shape();
-        let smallest_dim: usize = cmp::min(shape.0, shape.1);
-        // Assuming most matrices have dense diagonals, it seems prudent
-        // to allocate a bit of memory up front
-        let heuristic = smallest_dim / 2;
-        let mut index_vec = Vec::with_capacity(heuristic);
-        let mut data_vec = Vec::with_capacity(heuristic);
+        let squared_norms = match self.storage() {
+            CSR => self.outer_sums_by(|&x| x * x),
+            CSC => self.inner_sums_by(|&x| x * x),
+        };
+        squared_norms.into_iter().map(Float::sqrt).collect()
+    }
 
-        for i in 0..smallest_dim {
-            let optional_index = self.nnz_index(i, i);
-            if let Some(idx) = optional_index {
-                data_vec.push(self[idx].clone());
-                index_vec.push(I::from_usize(i));
-            }
+    /// Compute the L2-norm of each column of the matrix
+    ///
+    /// Empty columns have a norm of zero.
+    pub fn col_norms(&self) -> Vec<N>
+    where
+        N: Float,
+    {
+        let squared_norms = match self.storage() {
+            CSR => self.inner_sums_by(|&x| x * x),
+            CSC => self.outer_sums_by(|&x| x * x),
+        };
+        squared_norms.into_iter().map(Float::sqrt).collect()
+    }
+
+    /// Compute the Frobenius norm of the matrix, ie the square root of the
+    /// sum of the squared modulus of its entries.
+    pub fn frobenius_norm(&self) -> N::Real
+    where
+        N: crate::Modulus,
+        N::Real: Float,
+    {
+        self.data
+            .iter()
+            .fold(N::Real::zero(), |acc, x| acc + x.squared_modulus())
+            .sqrt()
+    }
+
+    /// Compute the infinity norm of the matrix, ie the maximum over the rows
+    /// of the sum of the absolute values of the row's entries.
+    ///
+    /// Returns zero for an empty matrix.
+    pub fn norm_inf(&self) -> N
+    where
+        N: Num + Signed + PartialOrd + Clone,
+    {
+        let row_sums = match self.storage() {
+            CSR => self.outer_sums_by(Signed::abs),
+            CSC => self.inner_sums_by(Signed::abs),
+        };
+        max_or_zero(row_sums)
+    }
+
+    /// Compute the 1-norm of the matrix, ie the maximum over the columns
+    /// of the sum of the absolute values of the column's entries.
+    ///
+    /// Returns zero for an empty matrix.
+    pub fn norm_1(&self) -> N
+    where
+        N: Num + Signed + PartialOrd + Clone,
+    {
+        let col_sums = match self.storage() {
+            CSR => self.inner_sums_by(Signed::abs),
+            CSC => self.outer_sums_by(Signed::abs),
+        };
+        max_or_zero(col_sums)
+    }
+
+    /// Sum the values mapped by `f` for each outer dimension
+    fn outer_sums_by<F>(&self, f: F) -> Vec<N>
+    where
+        N: Num + Clone,
+        F: Fn(&N) -> N,
+    {
+        self.outer_iterator()
+            .map(|vec| vec.data().iter().fold(N::zero(), |acc, x| acc + f(x)))
+            .collect()
+    }
+
+    /// Sum the values mapped by `f` for each inner dimension
+    fn inner_sums_by<F>(&self, f: F) -> Vec<N>
+    where
+        N: Num + Clone,
+        F: Fn(&N) -> N,
+    {
+        let mut sums = vec![N::zero(); self.inner_dims()];
+        for (ind, x) in self.indices.iter().zip(self.data.iter()) {
+            let sum = &mut sums[ind.index_unchecked()];
+            *sum = sum.clone() + f(x);
         }
-        data_vec.shrink_to_fit();
-        index_vec.shrink_to_fit();
-        CsVecI::new_trusted(smallest_dim, index_vec, data_vec)
+        sums
     }
 
     /// Iteration over all entries on the diagonal
@@ -1207,6 +2987,20 @@ where
     }
 
     /// Return a new sparse matrix with the same sparsity pattern, with all non-zero values mapped by the function `f`.
+    ///
+    /// The structure arrays are copied, and the scalar type can be changed
+    /// by `f`. See [`map_inplace`](CsMatBase::map_inplace) to modify the
+    /// values without allocating.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let mat = CsMat::new((2, 2), vec![0, 1, 2], vec![1, 0], vec![1.5f64, -2.]);
+    /// let single: CsMat<f32> = mat.map(|&x| x as f32);
+    /// assert_eq!(single.data(), &[1.5f32, -2.]);
+    /// assert_eq!(single.indices(), mat.indices());
+    /// ```
     pub fn map<F, N2>(&self, f: F) -> CsMatI<N2, I, Iptr>
     where
         F: FnMut(&N) -> N2,
@@ -1223,6 +3017,72 @@ where
         }
     }
 
+    /// Convert the values of this matrix to another scalar type, when this
+    /// conversion can fail, eg for narrowing conversions. The structure
+    /// arrays are copied unchanged.
+    ///
+    /// Only exact conversions succeed: a value is converted using
+    /// [`NumCast`](num_traits::NumCast), and the conversion fails if the
+    /// result does not convert back to the original value. In particular,
+    /// a float with a fractional part is not truncated to an integer, and a
+    /// `NaN` value cannot be converted.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first value, in storage order, that cannot be
+    /// converted, along with its location.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let mat = CsMat::new((2, 2), vec![0, 1, 2], vec![1, 0], vec![2f64, 3e10]);
+    /// let err = mat.try_cast::<i32>().unwrap_err();
+    /// assert_eq!((err.value, err.row, err.col), (3e10, 1, 0));
+    /// let mat = mat.map(|&x| x.min(1e3));
+    /// assert_eq!(mat.try_cast::<i32>().unwrap().data(), &[2, 1000]);
+    /// let mat = mat.map(|&x| x + 0.5);
+    /// assert_eq!(mat.try_cast::<i32>().unwrap_err().value, 2.5);
+    /// ```
+    pub fn try_cast<M>(&self) -> Result<CsMatI<M, I, Iptr>, CastError<N>>
+    where
+        N: NumCast + PartialEq + Clone,
+        M: NumCast + Clone,
+    {
+        let mut data = Vec::with_capacity(self.nnz());
+        for (outer, range) in self.indptr.iter_outer_sz().enumerate() {
+            for (inner, val) in
+                self.indices[range.clone()].iter().zip(&self.data[range])
+            {
+                let exact = M::from(val.clone()).filter(|converted| {
+                    N::from(converted.clone()).as_ref() == Some(val)
+                });
+                match exact {
+                    Some(converted) => data.push(converted),
+                    None => {
+                        let (row, col) = match self.storage {
+                            CSR => (outer, inner.index()),
+                            CSC => (inner.index(), outer),
+                        };
+                        return Err(CastError {
+                            value: val.clone(),
+                            row,
+                            col,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(CsMatI {
+            storage: self.storage,
+            nrows: self.nrows,
+            ncols: self.ncols,
+            indptr: self.indptr.to_owned(),
+            indices: self.indices.to_vec(),
+            data,
+        })
+    }
+
     /// Access an element given its `outer_ind` and `inner_ind`.
     /// Will return None if there is no non-zero element at this location.
     ///
@@ -1308,6 +3168,121 @@ where
             inner_iter: self.indices.iter().zip(self.data.iter()).enumerate(),
         }
     }
+
+    /// Get an iterator that yields the non-zero entries of this matrix as
+    /// `(row, col, value)` tuples, in row-major order whatever the storage
+    /// of the matrix.
+    ///
+    /// For a CSC matrix, the entries are first sorted by row, see
+    /// [`iter_by_inner`](Self::iter_by_inner).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let mat = CsMat::new_csc((2, 2), vec![0, 2, 3], vec![0, 1, 0], vec![1, 2, 3]);
+    /// let entries: Vec<_> = mat.iter_rc().collect();
+    /// assert_eq!(entries, vec![(0, 0, &1), (0, 1, &3), (1, 0, &2)]);
+    /// ```
+    pub fn iter_rc(&self) -> impl Iterator<Item = (usize, usize, &N)> + '_ {
+        let (csr_entries, csc_entries) = match self.storage {
+            CSR => (Some(self.iter()), None),
+            CSC => (None, Some(self.iter_by_inner())),
+        };
+        csr_entries
+            .into_iter()
+            .flatten()
+            .map(|(val, (row, col))| (row.index(), col.index(), val))
+            .chain(csc_entries.into_iter().flatten())
+    }
+
+    /// Get an iterator that yields the non-zero entries of this matrix as
+    /// `(row, col, value)` tuples, grouped by inner index, ie in
+    /// column-major order for a CSR matrix and in row-major order for a CSC
+    /// matrix. Inside a group, the entries are sorted by outer index.
+    ///
+    /// The entries are first sorted with a counting sort, which takes
+    /// `O(nnz + inner_dims)` time and allocates `O(nnz + inner_dims)`
+    /// memory when this method is called, without copying the values.
+    /// When the entries need to be visited this way several times,
+    /// converting the matrix with
+    /// [`to_other_storage`](Self::to_other_storage) once is cheaper.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let mat = CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 0], vec![1, 2, 3]);
+    /// let entries: Vec<_> = mat.iter_by_inner().collect();
+    /// assert_eq!(entries, vec![(0, 0, &1), (1, 0, &3), (0, 1, &2)]);
+    /// ```
+    pub fn iter_by_inner(
+        &self,
+    ) -> impl Iterator<Item = (usize, usize, &N)> + '_ {
+        // counting sort of the entries by inner index
+        let mut next = vec![0; self.inner_dims() + 1];
+        for inner in self.indices.iter() {
+            next[inner.index() + 1] += 1;
+        }
+        for inner in 0..self.inner_dims() {
+            next[inner + 1] += next[inner];
+        }
+        let mut order = vec![(0, 0); self.nnz()];
+        for (outer, range) in self.indptr.iter_outer_sz().enumerate() {
+            for pos in range {
+                let inner = self.indices[pos].index();
+                order[next[inner]] = (outer, pos);
+                next[inner] += 1;
+            }
+        }
+        let storage = self.storage;
+        order.into_iter().map(move |(outer, pos)| {
+            let inner = self.indices[pos].index();
+            let val = &self.data[pos];
+            match storage {
+                CSR => (outer, inner, val),
+                CSC => (inner, outer, val),
+            }
+        })
+    }
+
+    /// Fold the non-zero entries of this matrix into an accumulator, calling
+    /// `f(acc, row, col, value)` for each of them.
+    ///
+    /// The entries are visited in storage order, ie in row-major order for
+    /// a CSR matrix and in column-major order for a CSC matrix. Use
+    /// [`iter_rc`](Self::iter_rc) if a row-major order is needed whatever
+    /// the storage.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let mat = CsMat::new((3, 3), vec![0, 2, 3, 4], vec![0, 2, 1, 0], vec![1, 2, 3, 4]);
+    /// // sum of the entries strictly below the diagonal
+    /// let lower_sum = mat.fold_nonzero(0, |acc, row, col, &val| {
+    ///     if row > col { acc + val } else { acc }
+    /// });
+    /// assert_eq!(lower_sum, 4);
+    /// ```
+    pub fn fold_nonzero<B, F>(&self, init: B, mut f: F) -> B
+    where
+        F: FnMut(B, usize, usize, &N) -> B,
+    {
+        let mut acc = init;
+        for (outer, range) in self.indptr.iter_outer_sz().enumerate() {
+            for (inner, val) in
+                self.indices[range.clone()].iter().zip(&self.data[range])
+            {
+                let inner = inner.index();
+                acc = match self.storage {
+                    CSR => f(acc, outer, inner, val),
+                    CSC => f(acc, inner, outer, val),
+                };
+            }
+        }
+        acc
+    }
 }
 
 /// # Methods to convert between storage orders
@@ -1369,6 +3344,31 @@ where
             CSC => self.to_other_storage(),
         }
     }
+
+    /// Create the transpose of this matrix, with the same storage as this
+    /// matrix: the transpose of a CSR matrix is a new CSR matrix.
+    ///
+    /// Contrary to [`transpose_view`](CsMatBase::transpose_view), which only
+    /// reinterprets the storage order, this performs an O(nnz) counting sort
+    /// of the entries by inner index. This is useful to pass the transpose
+    /// to routines requiring a specific storage order.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let mat = CsMat::new((2, 3), vec![0, 2, 3], vec![0, 2, 1], vec![1, 2, 3]);
+    /// let transposed = mat.transpose_materialized();
+    /// assert!(transposed.is_csr());
+    /// assert_eq!(transposed.shape(), (3, 2));
+    /// assert_eq!(transposed, mat.transpose_view().to_csr());
+    /// ```
+    pub fn transpose_materialized(&self) -> CsMatI<N, I, Iptr>
+    where
+        N: Clone,
+    {
+        self.transpose_view().to_other_storage()
+    }
 }
 
 impl<N, I, Iptr> CsMatI<N, I, Iptr>
@@ -1434,6 +3434,99 @@ where
         }
     }
 
+    /// Multiply each row of the matrix by the corresponding element of
+    /// `diag`, ie compute `D * self` in place with `D` the diagonal matrix
+    /// whose diagonal is `diag`.
+    ///
+    /// # Panics
+    ///
+    /// If `diag.len()` is not equal to the number of rows.
+    pub fn scale_rows(&mut self, diag: &[N])
+    where
+        for<'r> N: MulAssign<&'r N>,
+    {
+        assert_eq!(diag.len(), self.rows(), "Dimension mismatch");
+        match self.storage() {
+            CSR => self.scale_outer(diag),
+            CSC => self.scale_inner(diag),
+        }
+    }
+
+    /// Multiply each column of the matrix by the corresponding element of
+    /// `diag`, ie compute `self * D` in place with `D` the diagonal matrix
+    /// whose diagonal is `diag`.
+    ///
+    /// # Panics
+    ///
+    /// If `diag.len()` is not equal to the number of columns.
+    pub fn scale_cols(&mut self, diag: &[N])
+    where
+        for<'r> N: MulAssign<&'r N>,
+    {
+        assert_eq!(diag.len(), self.cols(), "Dimension mismatch");
+        match self.storage() {
+            CSR => self.scale_inner(diag),
+            CSC => self.scale_outer(diag),
+        }
+    }
+
+    fn scale_outer(&mut self, diag: &[N])
+    where
+        for<'r> N: MulAssign<&'r N>,
+    {
+        for (mut vec, factor) in self.outer_iterator_mut().zip(diag) {
+            for (_, val) in vec.iter_mut() {
+                *val *= factor;
+            }
+        }
+    }
+
+    fn scale_inner(&mut self, diag: &[N])
+    where
+        for<'r> N: MulAssign<&'r N>,
+    {
+        for (ind, val) in self.indices.iter().zip(self.data.iter_mut()) {
+            *val *= &diag[ind.index_unchecked()];
+        }
+    }
+
+    /// Divide each outer slice by the sum of its entries, so that every
+    /// outer slice sums to one. For a CSR matrix this normalizes the rows,
+    /// giving a row-stochastic matrix when the entries are non-negative,
+    /// while for a CSC matrix this normalizes the columns.
+    ///
+    /// Outer slices whose sum is zero, including empty ones, are left
+    /// untouched.
+    ///
+    /// Returns the sums of the outer slices before normalization, which
+    /// can be used to detect degenerate slices.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let mut mat = CsMat::new((3, 2), vec![0, 2, 2, 3], vec![0, 1, 1], vec![1., 3., 2.]);
+    /// let sums = mat.normalize_outer();
+    /// assert_eq!(sums, vec![4., 0., 2.]);
+    /// assert_eq!(mat.data(), &[0.25, 0.75, 1.]);
+    /// ```
+    pub fn normalize_outer(&mut self) -> Vec<N>
+    where
+        N: Float,
+    {
+        let mut sums = Vec::with_capacity(self.outer_dims());
+        for mut vec in self.outer_iterator_mut() {
+            let sum = vec.data().iter().fold(N::zero(), |acc, &x| acc + x);
+            if !sum.is_zero() {
+                for (_, val) in vec.iter_mut() {
+                    *val = *val / sum;
+                }
+            }
+            sums.push(sum);
+        }
+        sums
+    }
+
     /// Get a mutable view into the i-th outer dimension
     /// (eg i-th row for a CSR matrix)
     pub fn outer_view_mut(&mut self, i: usize) -> Option<CsVecViewMutI<N, I>> {
@@ -1450,13 +3543,13 @@ where
     }
 
     /// Get a mutable reference to the element located at row i and column j.
-    /// Will return None if there is no non-zero element at this location.
+    /// Will return None if there is no non-zero element at this location,
+    /// including when the location is out of bounds.
     ///
     /// This access is logarithmic in the number of non-zeros
     /// in the corresponding outer slice. It is therefore advisable not to rely
     /// on this for algorithms, and prefer [`outer_iterator_mut`](Self::outer_iterator_mut)
     /// which accesses elements in storage order.
-    /// TODO: `outer_iterator_mut` is not yet implemented
     pub fn get_mut(&mut self, i: usize, j: usize) -> Option<&mut N> {
         match self.storage {
             CSR => self.get_outer_inner_mut(i, j),
@@ -1494,10 +3587,7 @@ where
     pub fn set(&mut self, row: usize, col: usize, val: N) {
         let outer = outer_dimension(self.storage(), row, col);
         let inner = inner_dimension(self.storage(), row, col);
-        let vec::NnzIndex(index) = self
-            .outer_view(outer)
-            .and_then(|vec| vec.nnz_index(inner))
-            .unwrap();
+        let NnzIndex(index) = self.nnz_index_outer_inner(outer, inner).unwrap();
         self.data[index] = val;
     }
 
@@ -1511,6 +3601,27 @@ where
         }
     }
 
+    /// Clamp every stored value into the interval `[min, max]`. The
+    /// structure of the matrix is not modified, in particular the implicit
+    /// zeros are left as is even if zero is outside of the interval.
+    ///
+    /// # Panics
+    ///
+    /// If `min > max`.
+    pub fn clamp(&mut self, min: N, max: N)
+    where
+        N: PartialOrd + Clone,
+    {
+        assert!(min <= max, "min should not be greater than max");
+        for val in &mut self.data[..] {
+            if *val < min {
+                *val = min.clone();
+            } else if *val > max {
+                *val = max.clone();
+            }
+        }
+    }
+
     /// Return a mutable outer iterator for the matrix
     ///
     /// This iterator yields mutable sparse vector views for each outer
@@ -2087,6 +4198,27 @@ where
     }
 }
 
+/// Product of a matrix view with a dense vector view, taking both operands
+/// by value since views are cheap to copy.
+///
+/// # Panics
+///
+/// If the number of columns of the matrix does not match the length of
+/// the vector.
+impl<'a, 'b, N, I, Iptr> Mul<ndarray::ArrayView1<'b, N>>
+    for CsMatViewI<'a, N, I, Iptr>
+where
+    N: 'a + Clone + crate::MulAcc + num_traits::Zero,
+    I: 'a + SpIndex,
+    Iptr: 'a + SpIndex,
+{
+    type Output = Array<N, Ix1>;
+
+    fn mul(self, rhs: ndarray::ArrayView1<'b, N>) -> Array<N, Ix1> {
+        &self * &rhs
+    }
+}
+
 impl<N, I, Iptr, IpS, IS, DS> Index<[usize; 2]>
     for CsMatBase<N, I, IpS, IS, DS, Iptr>
 where
@@ -2229,11 +4361,11 @@ where
 
 #[cfg(test)]
 mod test {
-    use super::CompressedStorage::CSR;
-    use crate::errors::StructureErrorKind;
+    use super::CompressedStorage::{CSC, CSR};
+    use crate::errors::{CastError, StructureError, StructureErrorKind};
     use crate::sparse::{CsMat, CsMatI, CsMatView, CsVec};
     use crate::test_data::{mat1, mat1_csc, mat1_times_2};
-    use ndarray::{arr2, Array};
+    use ndarray::{arr1, arr2, Array, Axis};
 
     #[test]
     fn test_copy() {
@@ -2294,22 +4426,466 @@ mod test {
 
     #[test]
     #[should_panic]
-    fn test_new_csr_data_indices_mismatch2() {
-        let indptr_ok: &[usize] = &[0, 1, 2, 3];
-        let indices_ok: &[usize] = &[0, 1, 2];
-        let data_fail1: &[f64] = &[1., 1., 1., 1.];
-        let res = CsMatView::try_new((3, 3), indptr_ok, indices_ok, data_fail1);
-        res.unwrap(); //unreachable
+    fn test_new_csr_data_indices_mismatch2() {
+        let indptr_ok: &[usize] = &[0, 1, 2, 3];
+        let indices_ok: &[usize] = &[0, 1, 2];
+        let data_fail1: &[f64] = &[1., 1., 1., 1.];
+        let res = CsMatView::try_new((3, 3), indptr_ok, indices_ok, data_fail1);
+        res.unwrap(); //unreachable
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_new_csr_data_indices_mismatch3() {
+        let indptr_ok: &[usize] = &[0, 1, 2, 3];
+        let indices_ok: &[usize] = &[0, 1, 2];
+        let data_fail2: &[f64] = &[1., 1.];
+        let res = CsMatView::try_new((3, 3), indptr_ok, indices_ok, data_fail2);
+        res.unwrap(); //unreachable
+    }
+
+    #[test]
+    fn from_raw_storage() {
+        for mat in &[mat1(), mat1_csc()] {
+            let (storage, shape) = (mat.storage(), mat.shape());
+            let (indptr, indices, data) = mat.clone().into_raw_storage();
+            let res = CsMat::from_raw_storage(
+                storage,
+                shape,
+                indptr.clone(),
+                indices.clone(),
+                data.clone(),
+            );
+            assert_eq!(res.as_ref(), Ok(mat));
+            let res = unsafe {
+                CsMat::from_raw_storage_unchecked(
+                    storage, shape, indptr, indices, data,
+                )
+            };
+            assert_eq!(&res, mat);
+        }
+
+        let res = CsMat::from_raw_storage(
+            CSC,
+            (2, 3),
+            vec![0, 1, 3, 3],
+            vec![1, 1, 2],
+            vec![1., 2., 3.],
+        );
+        assert_eq!(
+            res,
+            Err(StructureError::IndexOutOfBounds {
+                outer: 1,
+                index: 2,
+                bound: 2
+            })
+        );
+        let res = CsMat::from_raw_storage(
+            CSR,
+            (2, 2),
+            vec![0, 1, 2],
+            vec![1, 1],
+            vec![1.],
+        );
+        assert_eq!(res.unwrap_err().kind(), StructureErrorKind::SizeMismatch);
+    }
+
+    #[test]
+    fn test_try_new_checked() {
+        let indptr_ok: &[usize] = &[0, 1, 2, 3];
+        let indices_ok: &[usize] = &[0, 1, 2];
+        let data_ok: &[f64] = &[1., 1., 1.];
+        let m =
+            CsMatView::try_new_checked((3, 3), indptr_ok, indices_ok, data_ok);
+        assert_eq!(
+            m,
+            Ok(CsMatView::new((3, 3), indptr_ok, indices_ok, data_ok))
+        );
+        let m = CsMatView::try_new_csc_checked(
+            (3, 3),
+            indptr_ok,
+            indices_ok,
+            data_ok,
+        );
+        assert!(m.unwrap().is_csc());
+
+        let indptr: &[usize] = &[0, 2, 1, 3];
+        let res =
+            CsMatView::try_new_checked((3, 3), indptr, indices_ok, data_ok);
+        assert_eq!(res, Err(StructureError::DecreasingIndptr { position: 1 }));
+
+        let indptr: &[usize] = &[0, 1, 2, 4];
+        let res =
+            CsMatView::try_new_checked((3, 3), indptr, indices_ok, data_ok);
+        assert_eq!(
+            res,
+            Err(StructureError::IndptrNnzMismatch {
+                indptr_nnz: 4,
+                nnz: 3
+            })
+        );
+
+        let indices: &[usize] = &[0, 3, 2];
+        let res =
+            CsMatView::try_new_checked((3, 3), indptr_ok, indices, data_ok);
+        let err = res.unwrap_err();
+        assert_eq!(
+            err,
+            StructureError::IndexOutOfBounds {
+                outer: 1,
+                index: 3,
+                bound: 3
+            }
+        );
+        assert_eq!(err.kind(), StructureErrorKind::OutOfRange);
+        let res =
+            CsMatView::try_new_csc_checked((2, 3), indptr_ok, indices, data_ok);
+        assert_eq!(
+            res,
+            Err(StructureError::IndexOutOfBounds {
+                outer: 1,
+                index: 3,
+                bound: 2
+            })
+        );
+
+        let indptr: &[usize] = &[0, 3, 3, 3];
+        let indices: &[usize] = &[0, 2, 2];
+        let res = CsMatView::try_new_checked((3, 3), indptr, indices, data_ok);
+        let err = res.unwrap_err();
+        assert_eq!(err, StructureError::UnsortedIndices { outer: 0 });
+        assert_eq!(
+            format!("{}", err),
+            "Structure Error (unsorted): Indices are not sorted \
+             (in outer slice 0)"
+        );
+
+        let data: &[f64] = &[1., 1.];
+        let res =
+            CsMatView::try_new_checked((3, 3), indptr_ok, indices_ok, data);
+        assert_eq!(err_kind(res), StructureErrorKind::SizeMismatch);
+        let indptr: &[usize] = &[0, 1, 3];
+        let res =
+            CsMatView::try_new_checked((3, 3), indptr, indices_ok, data_ok);
+        assert_eq!(err_kind(res), StructureErrorKind::SizeMismatch);
+    }
+
+    fn err_kind<T>(res: Result<T, StructureError>) -> StructureErrorKind {
+        match res {
+            Ok(_) => panic!("expected an error"),
+            Err(e) => e.kind(),
+        }
+    }
+
+    #[test]
+    fn sort_indices() {
+        let indptr = vec![0, 3, 3, 5];
+        let indices = vec![2, 0, 1, 1, 0];
+        let data = vec![3., 1., 2., 5., 4.];
+        // Safety: only the sorting invariant is broken, and we sort before
+        // using the matrix
+        let mut mat =
+            unsafe { CsMat::new_unchecked(CSR, (3, 3), indptr, indices, data) };
+        assert!(!mat.is_sorted());
+        mat.sort_indices();
+        assert!(mat.is_sorted());
+        let expected = CsMat::new(
+            (3, 3),
+            vec![0, 3, 3, 5],
+            vec![0, 1, 2, 0, 1],
+            vec![1., 2., 3., 4., 5.],
+        );
+        assert_eq!(mat, expected);
+
+        let mut mat = mat1();
+        assert!(mat.is_sorted());
+        mat.sort_indices();
+        assert_eq!(mat, mat1());
+        assert!(mat.slice_outer(1..4).is_sorted());
+    }
+
+    #[test]
+    fn prune() {
+        // | 1 0 0 |
+        // | 0 0 2 |
+        // | 0 0 0 |
+        let mut mat = CsMat::new(
+            (3, 3),
+            vec![0, 2, 5, 6],
+            vec![0, 2, 0, 1, 2, 1],
+            vec![1., 0., 0., 0., 2., 0.],
+        );
+        assert_eq!(mat.prune(), 4);
+        let expected =
+            CsMat::new((3, 3), vec![0, 1, 2, 2], vec![0, 2], vec![1., 2.]);
+        assert_eq!(mat, expected);
+        assert_eq!(mat.prune(), 0);
+        assert_eq!(mat, expected);
+
+        let mut mat = mat1();
+        assert_eq!(mat.prune_by(|&x| x > 4.), 3);
+        let expected = CsMat::new(
+            (5, 5),
+            vec![0, 0, 1, 2, 3, 4],
+            vec![4, 2, 1, 3],
+            vec![5., 5., 8., 7.],
+        );
+        assert_eq!(mat, expected);
+        assert_eq!(mat.prune_by(|_| false), 4);
+        assert_eq!(mat, CsMat::zero((5, 5)));
+    }
+
+    #[test]
+    fn threshold() {
+        // | 0  0 -2 -1  0 |
+        // | 0  0  0 -3  0 |
+        // | 0  0  0  0  0 |
+        // | 0  3  0  0  0 |
+        // | 0  0  0  2  0 |
+        // with explicit zeros at (1, 4) and (2, 2)
+        let mut mat = mat1().map(|&x| x - 5.);
+        assert_eq!(mat.threshold(2.), 3);
+        let expected = CsMat::new(
+            (5, 5),
+            vec![0, 1, 2, 2, 3, 4],
+            vec![2, 3, 1, 3],
+            vec![-2., -3., 3., 2.],
+        );
+        assert_eq!(mat, expected);
+        assert_eq!(mat.threshold(0.), 0);
+        assert_eq!(mat, expected);
+        assert_eq!(mat.threshold(3.), 2);
+        assert_eq!(mat.threshold(3.5), 2);
+        assert_eq!(mat.nnz(), 0);
+    }
+
+    #[test]
+    fn clamp() {
+        let mut mat = mat1();
+        mat.clamp(3., 5.);
+        let expected = CsMat::new(
+            (5, 5),
+            vec![0, 2, 4, 5, 6, 7],
+            vec![2, 3, 3, 4, 2, 1, 3],
+            vec![3., 4., 3., 5., 5., 5., 5.],
+        );
+        assert_eq!(mat, expected);
+    }
+
+    #[test]
+    #[should_panic]
+    fn clamp_fail() {
+        let mut mat = mat1();
+        mat.clamp(5., 3.);
+    }
+
+    #[test]
+    fn canonicalize() {
+        // | 0 3 0 |
+        // | 0 0 0 |
+        // | 2 0 4 |
+        // with unsorted indices, duplicates and explicit zeros
+        let mut mat = unsafe {
+            CsMat::new_unchecked(
+                CSR,
+                (3, 3),
+                vec![0, 3, 6, 9],
+                vec![1, 0, 1, 2, 2, 0, 2, 0, 2],
+                vec![1., 0., 2., 1., -1., 0., 4., 2., 0.],
+            )
+        };
+        mat.canonicalize();
+        let expected = CsMat::new(
+            (3, 3),
+            vec![0, 1, 1, 3],
+            vec![1, 0, 2],
+            vec![3., 2., 4.],
+        );
+        assert_eq!(mat, expected);
+        mat.check_compressed_structure().unwrap();
+
+        // Already canonical matrices are left untouched
+        let mut mat = mat1();
+        mat.canonicalize();
+        assert_eq!(mat, mat1());
+        let mut mat = CsMat::<f64>::zero((3, 2)).to_csc();
+        mat.canonicalize();
+        assert_eq!(mat, CsMat::zero((3, 2)).to_csc());
+    }
+
+    #[test]
+    fn iter_rc() {
+        let mat = mat1();
+        let expected: Vec<_> = mat
+            .iter()
+            .map(|(val, (row, col))| (row, col, val))
+            .collect();
+        assert_eq!(mat.iter_rc().collect::<Vec<_>>(), expected);
+        let mat_csc = mat.to_csc();
+        assert_eq!(mat_csc.iter_rc().collect::<Vec<_>>(), expected);
+        assert_eq!(CsMat::<f64>::zero((3, 2)).to_csc().iter_rc().count(), 0);
+    }
+
+    #[test]
+    fn fold_nonzero() {
+        for mat in &[mat1(), mat1().to_csc()] {
+            let visited = mat.fold_nonzero(Vec::new(), |mut acc, i, j, &v| {
+                acc.push((i, j, v));
+                acc
+            });
+            let expected: Vec<_> =
+                mat.iter().map(|(&v, (i, j))| (i, j, v)).collect();
+            assert_eq!(visited, expected);
+
+            // count of the entries in the top left 2x3 region
+            let count = mat.fold_nonzero(0, |count, i, j, _| {
+                count + (i < 2 && j < 3) as usize
+            });
+            assert_eq!(count, 1);
+        }
+        let empty = CsMat::<f64>::zero((3, 2));
+        assert_eq!(empty.fold_nonzero(7, |acc, _, _, _| acc + 1), 7);
+    }
+
+    #[test]
+    #[cfg(feature = "multi_thread")]
+    fn par_outer_iterator() {
+        use rayon::prelude::*;
+        for mat in &[mat1(), mat1_csc(), CsMat::zero((0, 3))] {
+            let par: Vec<_> = mat.par_outer_iterator().collect();
+            let serial: Vec<_> = mat.outer_iterator().enumerate().collect();
+            assert_eq!(par, serial);
+            assert_eq!(mat.par_outer_iterator().len(), mat.outer_dims());
+        }
+        let nnz: usize =
+            mat1().par_outer_iterator().map(|(_, v)| v.nnz()).sum();
+        assert_eq!(nnz, mat1().nnz());
+    }
+
+    #[test]
+    fn transpose_materialized() {
+        let mat = mat1();
+        let transposed = mat.transpose_materialized();
+        assert_eq!(transposed.storage(), CSR);
+        assert_eq!(transposed.to_dense(), mat.to_dense().t());
+        assert_eq!(transposed.transpose_materialized(), mat);
+        let mat = mat.to_csc();
+        let transposed = mat.transpose_materialized();
+        assert_eq!(transposed.storage(), CSC);
+        assert_eq!(transposed.to_dense(), mat.to_dense().t());
+        assert!(transposed.is_sorted());
+    }
+
+    #[test]
+    fn nnz_per_outer_inner() {
+        // | 1 2 3 4 |
+        // | . 5 . . |
+        // | . . . 6 |
+        let mat = CsMat::new(
+            (3, 4),
+            vec![0, 4, 5, 6],
+            vec![0, 1, 2, 3, 1, 3],
+            vec![1, 2, 3, 4, 5, 6],
+        );
+        assert_eq!(mat.nnz_per_outer(), vec![4, 1, 1]);
+        assert_eq!(mat.nnz_per_inner(), vec![1, 2, 1, 2]);
+        let csc = mat.to_csc();
+        assert_eq!(csc.nnz_per_outer(), vec![1, 2, 1, 2]);
+        assert_eq!(csc.nnz_per_inner(), vec![4, 1, 1]);
+        for mat in &[mat.view(), csc.view()] {
+            assert_eq!(mat.nnz_per_row(), vec![4, 1, 1]);
+            assert_eq!(mat.nnz_per_col(), vec![1, 2, 1, 2]);
+        }
+    }
+
+    #[test]
+    fn try_append_outer_csvec() {
+        let mut mat = CsMat::<f64>::zero((0, 5));
+        for row in mat1().outer_iterator() {
+            mat.try_append_outer_csvec(row).unwrap();
+        }
+        assert_eq!(mat, mat1());
+
+        let mut mat = mat1_csc();
+        let col = CsVec::new(5, vec![1, 4], vec![1., 2.]);
+        mat.try_append_outer_csvec(col.view()).unwrap();
+        assert_eq!(mat.shape(), (5, 6));
+        assert_eq!(mat.get(4, 5), Some(&2.));
+
+        let expected = mat.clone();
+        let bad_dim = CsVec::new(4, vec![1], vec![1.]);
+        let err = mat.try_append_outer_csvec(bad_dim.view()).unwrap_err();
+        assert_eq!(err.kind(), StructureErrorKind::SizeMismatch);
+        assert_eq!(mat, expected);
+
+        // an indptr which does not start at zero
+        let mut mat =
+            CsMat::new((2, 3), vec![2, 3, 5], vec![1, 0, 2], vec![1., 2., 3.]);
+        mat.try_append_outer_csvec(CsVec::new(3, vec![2], vec![4.]).view())
+            .unwrap();
+        mat.check_compressed_structure().unwrap();
+        assert_eq!(mat.indptr().raw_storage(), &[2, 3, 5, 6]);
+        assert_eq!(mat.get(2, 2), Some(&4.));
+        assert_eq!(mat.outer_view(1).unwrap().nnz(), 2);
+
+        // vectors whose structure was not checked
+        let expected = mat.clone();
+        let unsorted = CsVec::new_trusted(3, vec![2, 0], vec![1., 2.]);
+        assert_eq!(
+            mat.try_append_outer_csvec(unsorted.view()),
+            Err(StructureError::UnsortedIndices { outer: 3 })
+        );
+        let out_of_bounds = CsVec::new_trusted(3, vec![0, 3], vec![1., 2.]);
+        assert_eq!(
+            mat.try_append_outer_csvec(out_of_bounds.view()),
+            Err(StructureError::IndexOutOfBounds {
+                outer: 3,
+                index: 3,
+                bound: 3
+            })
+        );
+        assert_eq!(mat, expected);
+    }
+
+    #[test]
+    fn to_dense_into() {
+        use ndarray::ShapeBuilder;
+        let mat = mat1();
+        let expected = mat.to_dense();
+        let mut row_major = Array::from_elem((5, 5), 1.);
+        let mut col_major = Array::from_elem((5, 5).f(), 1.);
+        for mat in &[mat.view(), mat.to_csc().view()] {
+            mat.to_dense_into(row_major.view_mut());
+            assert_eq!(row_major, expected);
+            mat.to_dense_into(col_major.view_mut());
+            assert_eq!(col_major, expected);
+            row_major.fill(2.);
+        }
+        // strided view
+        let mut buffer = Array::from_elem((10, 5), 1.);
+        mat.to_dense_into(buffer.slice_mut(ndarray::s![..;2, ..]));
+        assert_eq!(buffer.slice(ndarray::s![..;2, ..]), expected);
+        assert_eq!(buffer.slice(ndarray::s![1..;2, ..]), Array::ones((5, 5)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn to_dense_into_shape_mismatch() {
+        let mut buffer = Array::zeros((5, 4));
+        mat1().to_dense_into(buffer.view_mut());
     }
 
     #[test]
-    #[should_panic]
-    fn test_new_csr_data_indices_mismatch3() {
-        let indptr_ok: &[usize] = &[0, 1, 2, 3];
-        let indices_ok: &[usize] = &[0, 1, 2];
-        let data_fail2: &[f64] = &[1., 1.];
-        let res = CsMatView::try_new((3, 3), indptr_ok, indices_ok, data_fail2);
-        res.unwrap(); //unreachable
+    fn from_dense() {
+        let dense = mat1().to_dense();
+        let csr = CsMat::from_dense(dense.view(), CSR);
+        assert_eq!(csr, mat1());
+        let csc = CsMat::from_dense(dense.view(), CSC);
+        assert_eq!(csc, mat1().to_csc());
+        let csc = CsMat::from_dense(dense.t(), CSC);
+        assert_eq!(csc, mat1().transpose_materialized().to_csc());
+        let empty = CsMat::<f64>::from_dense(Array::zeros((2, 3)).view(), CSC);
+        assert_eq!(empty, CsMat::zero((2, 3)).to_csc());
     }
 
     #[test]
@@ -2424,6 +5000,61 @@ mod test {
         assert_eq!(m_sparse, expected_output);
     }
 
+    #[test]
+    fn from_diag() {
+        let mat: CsMat<f64> = CsMat::from_diag(&[1.; 4]);
+        assert_eq!(mat, CsMat::eye(4));
+
+        let diag = [2., 0., 0., -1., 3.];
+        let mat: CsMatI<f64, i32> = CsMatI::from_diag(&diag);
+        assert!(mat.is_csr());
+        assert_eq!(mat.nnz(), 3);
+        assert_eq!(mat.to_dense(), Array::from_diag(&arr1(&diag)));
+        assert_eq!(mat.diag().to_dense(), arr1(&diag));
+
+        let empty: CsMat<f64> = CsMat::from_diag(&[]);
+        assert_eq!(empty, CsMat::zero((0, 0)));
+    }
+
+    #[test]
+    fn from_triplet_iter() {
+        // | 0 0 3 4 0 |
+        // | 0 0 0 2 5 |
+        // | 0 0 5 0 0 |
+        // | 0 8 0 0 0 |
+        // | 0 0 0 7 0 |
+        // shuffled triplets of mat1, with some entries split in two
+        let triplets = vec![
+            (4, 3, 7.),
+            (1, 4, 5.),
+            (0, 3, 1.),
+            (3, 1, 8.),
+            (2, 2, 5.),
+            (0, 2, 3.),
+            (1, 3, 2.),
+            (0, 3, 3.),
+        ];
+        let mat = CsMat::from_triplet_iter(CSR, (5, 5), triplets.clone());
+        assert_eq!(mat, Ok(mat1()));
+        let mat =
+            CsMat::from_triplet_iter(CSC, (5, 5), triplets.iter().cloned());
+        assert_eq!(mat, Ok(mat1_csc()));
+        let mat: CsMatI<f64, u16, u32> =
+            CsMatI::from_triplet_iter(CSR, (5, 5), triplets).unwrap();
+        assert_eq!(mat.to_other_types(), mat1());
+
+        // explicit zeros are kept, empty iterators give empty matrices
+        let mat = CsMat::from_triplet_iter(CSC, (2, 3), vec![(1, 1, 0)]);
+        assert_eq!(mat.unwrap().nnz(), 1);
+        let mat = CsMat::<i32>::from_triplet_iter(CSR, (2, 3), None);
+        assert_eq!(mat, Ok(CsMat::zero((2, 3))));
+
+        let mat = CsMat::from_triplet_iter(CSR, (2, 3), vec![(0, 3, 1.)]);
+        assert_eq!(mat.unwrap_err().kind(), StructureErrorKind::OutOfRange);
+        let mat = CsMat::from_triplet_iter(CSC, (2, 3), vec![(2, 0, 1.)]);
+        assert!(mat.is_err());
+    }
+
     #[test]
     fn test_csc_from_dense() {
         let m = Array::eye(3);
@@ -2563,6 +5194,69 @@ mod test {
         assert_eq!(mat[[2, 2]], 4.);
         assert_eq!(mat.get(0, 0), None);
         assert_eq!(mat.get(4, 4), None);
+        // out of bounds locations
+        assert_eq!(mat.get(5, 2), None);
+        assert_eq!(mat.get(0, 5), None);
+        assert_eq!(mat.to_csc().get(0, 7), None);
+        // out of bounds inner indices need not be representable
+        let mat = CsMatI::<f64, u16>::new_csc(
+            (3, 3),
+            vec![0, 1, 3, 4],
+            vec![1, 0, 2, 2],
+            vec![1., 2., 3., 4.],
+        );
+        assert_eq!(mat.get(70_000, 1), None);
+    }
+
+    #[test]
+    fn set() {
+        for mat in &[mat1(), mat1().to_csc()] {
+            let mut mat = mat.clone();
+            let mut dense = mat.to_dense();
+            mat.set(1, 4, 0.);
+            mat.set(4, 3, -1.);
+            dense[[1, 4]] = 0.;
+            dense[[4, 3]] = -1.;
+            assert_eq!(mat.to_dense(), dense);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_fail_no_entry() {
+        let mut mat = mat1();
+        mat.set(0, 0, 1.);
+    }
+
+    #[test]
+    fn set_or_insert() {
+        let mut mat = mat1();
+        let mut dense = mat.to_dense();
+        let mut csc = mat.to_csc();
+        let updates = [
+            (0, 0, 1., false),
+            (0, 3, -1., false),
+            (4, 4, 2., true),
+            (1, 4, 0., true),
+            (2, 0, 0., true),
+            (3, 1, 0., false),
+        ];
+        for &(row, col, val, prune) in &updates {
+            mat.set_or_insert(row, col, val, prune).unwrap();
+            csc.set_or_insert(row, col, val, prune).unwrap();
+            dense[[row, col]] = val;
+        }
+        assert_eq!(mat.to_dense(), dense);
+        assert_eq!(csc.to_dense(), dense);
+        assert!(mat.check_compressed_structure().is_ok());
+        assert!(csc.check_compressed_structure().is_ok());
+        // (1, 4) was removed, (3, 1) is an explicit zero
+        assert_eq!(mat.get(1, 4), None);
+        assert_eq!(mat.get(3, 1), Some(&0.));
+        assert_eq!(mat.nnz(), 8);
+        let res = mat.set_or_insert(0, 5, 1., false);
+        assert_eq!(res.unwrap_err().kind(), StructureErrorKind::OutOfRange);
+        assert_eq!(mat.shape(), (5, 5));
     }
 
     #[test]
@@ -2578,6 +5272,8 @@ mod test {
         );
 
         *mat.get_mut(2, 1).unwrap() = 3.;
+        assert_eq!(mat.get_mut(3, 1), None);
+        assert_eq!(mat.get_mut(1, 3), None);
 
         let exp = CsMat::new_csc(
             (3, 3),
@@ -2624,6 +5320,375 @@ mod test {
         assert_eq!(res, mat);
     }
 
+    #[test]
+    fn try_cast() {
+        let mat = mat1();
+        let converted = mat.try_cast::<u8>().unwrap();
+        assert_eq!(converted.indices(), mat.indices());
+        assert_eq!(converted.data(), &[3, 4, 2, 5, 5, 8, 7]);
+
+        let mut mat = mat.to_csc();
+        mat.map_inplace(|&x| if x == 5. { -x } else { x });
+        let err = mat.try_cast::<u8>().unwrap_err();
+        assert_eq!(
+            err,
+            CastError {
+                value: -5.,
+                row: 2,
+                col: 2,
+            }
+        );
+        let mat = CsMat::new((1, 2), vec![0, 2], vec![0, 1], vec![1i64, 300]);
+        let err = mat.try_cast::<i8>().unwrap_err();
+        assert_eq!((err.value, err.row, err.col), (300, 0, 1));
+        assert_eq!(mat.try_cast::<f32>().unwrap().data(), &[1., 300.]);
+
+        // non exact conversions are rejected instead of truncated
+        let mat = CsMat::new((1, 2), vec![0, 2], vec![0, 1], vec![2., 1.5]);
+        let err = mat.try_cast::<u8>().unwrap_err();
+        assert_eq!((err.value, err.row, err.col), (1.5, 0, 1));
+        let mat = CsMat::new((1, 1), vec![0, 1], vec![0], vec![0.1f64]);
+        assert!(mat.try_cast::<f32>().is_err());
+        let mat = CsMat::new((1, 1), vec![0, 1], vec![0], vec![f64::NAN]);
+        assert!(mat.try_cast::<f32>().is_err());
+    }
+
+    #[test]
+    fn mul_view_dense_vec() {
+        let mat = mat1();
+        let x = arr1(&[1., 2., 3., 4., 5.]);
+        let expected = &mat * &x;
+        assert_eq!(mat.view() * x.view(), expected);
+        assert_eq!(mat.to_csc().view() * x.view(), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "Dimension mismatch")]
+    fn mul_view_dense_vec_fail() {
+        let mat = mat1();
+        let x = arr1(&[1., 2., 3.]);
+        let _ = mat.view() * x.view();
+    }
+
+    #[test]
+    fn pow() {
+        let mat = mat1();
+        let mut expected = CsMat::eye(5);
+        for n in 0..7 {
+            assert_eq!(mat.pow(n), expected);
+            assert_eq!(mat.to_csc().pow(n), expected.to_csc());
+            expected = &expected * &mat;
+        }
+        assert!(mat.to_csc().pow(0).is_csc());
+        let empty = CsMat::<f64>::zero((0, 0));
+        assert_eq!(empty.pow(3), empty);
+    }
+
+    #[test]
+    #[should_panic(expected = "Non square matrix")]
+    fn pow_fail() {
+        let _ = CsMat::<f64>::zero((2, 3)).pow(2);
+    }
+
+    #[test]
+    fn mul_choose_storage() {
+        let a = mat1();
+        let b = crate::test_data::mat2();
+        let expected = &a * &b;
+        for a in &[a.view(), a.to_csc().view()] {
+            for b in &[b.view(), b.to_csc().view()] {
+                let c = a.mul_choose_storage(b, CSR);
+                assert!(c.is_csr());
+                assert_eq!(c, expected);
+                let c = a.mul_choose_storage(b, CSC);
+                assert!(c.is_csc());
+                assert_eq!(c, expected.to_csc());
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Dimension mismatch")]
+    fn mul_choose_storage_fail() {
+        let a = CsMat::<f64>::zero((2, 3));
+        let _ = a.mul_choose_storage(&a.view(), CSC);
+    }
+
+    #[test]
+    fn checked_mul() {
+        use crate::errors::ArithmeticError;
+        let a = mat1().map(|&x| x as i32);
+        let b = crate::test_data::mat2().map(|&x| x as i32);
+        let expected = &a * &b;
+        for a in &[a.view(), a.to_csc().view()] {
+            for b in &[b.view(), b.to_csc().view()] {
+                let c = a.checked_mul(b).unwrap();
+                assert_eq!(c.storage(), a.storage());
+                assert_eq!(c.to_csr(), expected);
+            }
+        }
+
+        // | 1 200 |   | 1   0 |   | 201 200 |
+        // | 0   1 | * | 1 200 | = |   1 200 |  overflows at (0, 1) for i16
+        let a = CsMat::new(
+            (2, 2),
+            vec![0, 2, 3],
+            vec![0, 1, 1],
+            vec![1i16, 200, 1],
+        );
+        let b = CsMat::new(
+            (2, 2),
+            vec![0, 1, 3],
+            vec![0, 0, 1],
+            vec![1i16, 1, 200],
+        );
+        let overflow = Err(ArithmeticError::Overflow { row: 0, col: 1 });
+        assert_eq!(a.checked_mul(&b.view()), overflow);
+        assert_eq!(a.checked_mul(&b.to_csc().view()), overflow);
+        assert_eq!(a.to_csc().checked_mul(&b.view()), overflow);
+        let a = a.map(|&x| i32::from(x));
+        let b = b.map(|&x| i32::from(x));
+        assert_eq!(a.checked_mul(&b.view()).unwrap(), &a * &b);
+    }
+
+    #[test]
+    #[should_panic(expected = "Dimension mismatch")]
+    fn checked_mul_fail() {
+        let a = CsMat::<i32>::zero((2, 3));
+        let _ = a.checked_mul(&a.view());
+    }
+
+    #[test]
+    fn trans_mul_vec() {
+        let mat = CsMat::new(
+            (3, 4),
+            vec![0, 2, 3, 5],
+            vec![0, 3, 1, 0, 2],
+            vec![1., 2., 3., 4., 5.],
+        );
+        let x = arr1(&[1., 2., -1.]);
+        let expected = arr1(&[-3., 6., -5., 2.]);
+        assert_eq!(mat.trans_mul_vec(x.view()), expected);
+        assert_eq!(mat.to_csc().trans_mul_vec(x.view()), expected);
+        assert_eq!(mat.view().trans_mul_vec(x.view()), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "Dimension mismatch")]
+    fn trans_mul_vec_fail() {
+        let x = arr1(&[1., 2., -1.]);
+        let _ = mat1().trans_mul_vec(x.view());
+    }
+
+    #[test]
+    fn outer_inner_to_dense() {
+        let mat = mat1();
+        let dense = mat.to_dense();
+        let csc = mat.to_csc();
+        for i in 0..5 {
+            assert_eq!(mat.outer_to_dense(i), dense.row(i));
+            assert_eq!(mat.inner_to_dense(i), dense.column(i));
+            assert_eq!(csc.outer_to_dense(i), dense.column(i));
+            assert_eq!(csc.inner_to_dense(i), dense.row(i));
+        }
+        let wide = CsMat::new((1, 3), vec![0, 1], vec![1], vec![2.]);
+        assert_eq!(wide.outer_to_dense(0), arr1(&[0., 2., 0.]));
+        assert_eq!(wide.inner_to_dense(1), arr1(&[2.]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Out of bounds index")]
+    fn inner_to_dense_fail() {
+        let _ = mat1().to_csc().inner_to_dense(5);
+    }
+
+    #[test]
+    fn bandwidth() {
+        let mat = mat1();
+        assert_eq!(mat.bandwidth(), (2, 3));
+        assert_eq!(mat.to_csc().bandwidth(), (2, 3));
+        assert_eq!(mat.transpose_view().bandwidth(), (3, 2));
+        assert_eq!(mat.triu(1).bandwidth(), (0, 3));
+        assert_eq!(CsMat::<f64>::zero((3, 4)).bandwidth(), (0, 0));
+        let wide = CsMat::new((1, 4), vec![0, 1], vec![3], vec![1.]);
+        assert_eq!(wide.bandwidth(), (0, 3));
+        assert_eq!(wide.transpose_view().bandwidth(), (3, 0));
+    }
+
+    #[test]
+    fn triu_tril() {
+        let mat = mat1();
+        let dense = mat.to_dense();
+        for &k in &[-5, -2, -1, 0, 1, 3, 5] {
+            let mut upper = dense.clone();
+            let mut lower = dense.clone();
+            for ((row, col), val) in upper.indexed_iter_mut() {
+                if (col as isize) - (row as isize) < k {
+                    *val = 0.;
+                }
+            }
+            for ((row, col), val) in lower.indexed_iter_mut() {
+                if (col as isize) - (row as isize) > k {
+                    *val = 0.;
+                }
+            }
+            assert_eq!(mat.triu(k).to_dense(), upper);
+            assert_eq!(mat.tril(k).to_dense(), lower);
+            let csc = mat.to_csc();
+            assert!(csc.triu(k).is_csc());
+            assert_eq!(csc.triu(k).to_dense(), upper);
+            assert_eq!(csc.tril(k).to_dense(), lower);
+        }
+        assert!(mat.triu(0).is_upper_triangular());
+        assert!(mat.tril(0).is_lower_triangular());
+        assert_eq!(&mat.triu(1) + &mat.tril(0), mat);
+    }
+
+    #[test]
+    fn triangular() {
+        let mat = mat1();
+        assert!(!mat.is_lower_triangular());
+        assert!(!mat.is_upper_triangular());
+        // | 1 . . |
+        // | 2 3 . |
+        // | . 4 5 |
+        let lower = CsMat::new(
+            (3, 3),
+            vec![0, 1, 3, 5],
+            vec![0, 0, 1, 1, 2],
+            vec![1, 2, 3, 4, 5],
+        );
+        assert!(lower.is_lower_triangular());
+        assert!(!lower.is_upper_triangular());
+        assert!(lower.to_csc().is_lower_triangular());
+        assert!(!lower.to_csc().is_upper_triangular());
+        let upper = lower.transpose_view();
+        assert!(upper.is_upper_triangular());
+        assert!(!upper.is_lower_triangular());
+        assert!(upper.to_other_storage().is_upper_triangular());
+        let diag = CsMat::<f64>::eye(3);
+        assert!(diag.is_lower_triangular() && diag.is_upper_triangular());
+        let zero = CsMat::<f64>::zero((2, 3));
+        assert!(zero.is_lower_triangular() && zero.is_upper_triangular());
+    }
+
+    #[test]
+    fn symmetric() {
+        let mat = mat1();
+        assert!(!mat.is_symmetric());
+        assert!(!mat.is_structurally_symmetric());
+        let sym = &mat + &mat.transpose_view();
+        assert!(sym.is_symmetric());
+        assert!(sym.to_csc().is_symmetric());
+        assert!(sym.is_structurally_symmetric());
+        let mut skew = sym.clone();
+        skew.set(0, 2, -1.);
+        assert!(!skew.is_symmetric());
+        assert!(skew.is_structurally_symmetric());
+        // an explicit zero is part of the pattern
+        let mat =
+            CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 1], vec![1, 0, 1]);
+        assert!(!mat.is_structurally_symmetric());
+        assert!(CsMat::<f64>::eye(3).is_symmetric());
+        assert!(!CsMat::<f64>::zero((2, 3)).is_symmetric());
+        assert!(!CsMat::<f64>::zero((2, 3)).is_structurally_symmetric());
+    }
+
+    #[test]
+    fn select_outer_inner() {
+        let mat = mat1();
+        let dense = mat.to_dense();
+        let res = mat.select_outer(&[4, 0, 4, 2]);
+        assert_eq!(res.shape(), (4, 5));
+        assert_eq!(res.to_dense(), dense.select(Axis(0), &[4, 0, 4, 2]));
+        let res = mat.select_inner(&[3, 2, 3]);
+        assert_eq!(res.shape(), (5, 3));
+        assert!(res.is_sorted());
+        assert_eq!(res.to_dense(), dense.select(Axis(1), &[3, 2, 3]));
+        let csc = mat.to_csc();
+        let res = csc.select_outer(&[3, 3]);
+        assert!(res.is_csc());
+        assert_eq!(res.to_dense(), dense.select(Axis(1), &[3, 3]));
+        let res = csc.select_inner(&[1, 0]);
+        assert_eq!(res.to_dense(), dense.select(Axis(0), &[1, 0]));
+        assert_eq!(mat.select_outer(&[]).shape(), (0, 5));
+        assert_eq!(mat.select_outer(&[0, 1, 2, 3, 4]), mat);
+        assert_eq!(mat.select_inner(&[0, 1, 2, 3, 4]), mat);
+    }
+
+    #[test]
+    #[should_panic(expected = "Out of bounds index")]
+    fn select_outer_fail() {
+        let _ = mat1().select_outer(&[0, 5]);
+    }
+
+    #[test]
+    fn without_outer_inner() {
+        let mat = mat1();
+        let dense = mat.to_dense();
+        let res = mat.without_outer(&[3, 0, 3]);
+        assert_eq!(res.shape(), (3, 5));
+        assert_eq!(res.to_dense(), dense.select(Axis(0), &[1, 2, 4]));
+        let res = mat.without_inner(&[4, 2]);
+        assert_eq!(res.shape(), (5, 3));
+        assert_eq!(res.to_dense(), dense.select(Axis(1), &[0, 1, 3]));
+        let csc = mat.to_csc();
+        let res = csc.without_outer(&[1]);
+        assert!(res.is_csc());
+        assert_eq!(res.to_dense(), dense.select(Axis(1), &[0, 2, 3, 4]));
+        let res = csc.without_inner(&[0, 1]);
+        assert_eq!(res.to_dense(), dense.select(Axis(0), &[2, 3, 4]));
+        assert_eq!(mat.without_outer(&[]), mat);
+        assert_eq!(mat.without_inner(&[0, 1, 2, 3, 4]).shape(), (5, 0));
+        // an indptr not starting at zero
+        let view = mat.slice_outer(1..4);
+        assert_eq!(
+            view.without_outer(&[0]).to_dense(),
+            dense.select(Axis(0), &[2, 3])
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Out of bounds index")]
+    fn without_inner_fail() {
+        let _ = mat1().without_inner(&[5]);
+    }
+
+    #[test]
+    fn to_index_type() {
+        let mat = mat1_csc();
+        let converted = mat.to_index_type::<i32>().unwrap();
+        assert_eq!(converted.storage(), CSC);
+        assert_eq!(converted.indptr().raw_storage(), &[0, 0, 1, 3, 6, 7]);
+        assert_eq!(converted.indices(), &[3, 0, 2, 0, 1, 4, 1]);
+        assert_eq!(converted.data(), mat.data());
+        assert_eq!(converted.to_index_type::<usize>().unwrap(), mat);
+
+        // slicing gives an indptr that does not start at zero
+        let sliced = mat.slice_outer(3..5).to_index_type::<u16>().unwrap();
+        assert_eq!(sliced.indptr().raw_storage(), &[0, 3, 4]);
+
+        // the column indices do not fit in an i16
+        let mat = CsMat::new((1, 40_000), vec![0, 1], vec![39_999], vec![1.]);
+        let err = mat.to_index_type::<i16>().unwrap_err();
+        assert_eq!(err.kind(), StructureErrorKind::OutOfRange);
+    }
+
+    #[test]
+    fn map_type_change() {
+        let mat = mat1();
+        let rounded: CsMatI<i32, usize> = mat.map(|&x| x as i32 * 2);
+        assert_eq!(rounded.indptr(), mat.indptr());
+        assert_eq!(rounded.indices(), mat.indices());
+        assert_eq!(rounded.data(), &[6, 8, 4, 10, 10, 16, 14]);
+        // the structure of a sliced view is preserved
+        let sliced = mat.slice_outer(1..3).map(|&x| x as f32);
+        assert_eq!(
+            sliced.to_dense(),
+            mat.slice_outer(1..3).to_dense().mapv(|x| x as f32)
+        );
+    }
+
     #[test]
     fn insert() {
         // | 0 1 0 |
@@ -2856,6 +5921,320 @@ mod test {
         assert_eq!(iter.next(), None);
     }
 
+    #[test]
+    fn diag_offset() {
+        // | 1 0 0 3 1 3|
+        // | 0 2 0 0 0 0|
+        // | 0 0 0 1 0 1|
+        // | 3 0 1 1 0 0|
+        // | 1 0 0 0 1 0|
+        let mat = CsMat::new_csc(
+            (5, 6),
+            vec![0, 3, 4, 5, 8, 10, 12],
+            vec![0, 3, 4, 1, 3, 0, 2, 3, 0, 4, 0, 2],
+            vec![1, 3, 1, 2, 1, 3, 1, 1, 1, 1, 3, 1],
+        );
+        let csr = mat.to_csr();
+
+        for mat in &[mat.view(), csr.view()] {
+            assert_eq!(mat.diag_offset(0), mat.diag());
+
+            let expected = CsVec::new(5, vec![2], vec![1]);
+            assert_eq!(mat.diag_offset(1), expected);
+
+            assert_eq!(mat.diag_offset(2), CsVec::empty(4));
+
+            let expected = CsVec::new(3, vec![0, 2], vec![3, 1]);
+            assert_eq!(mat.diag_offset(3), expected);
+
+            let expected = CsVec::new(4, vec![2], vec![1]);
+            assert_eq!(mat.diag_offset(-1), expected);
+
+            let expected = CsVec::new(2, vec![0], vec![3]);
+            assert_eq!(mat.diag_offset(-3), expected);
+
+            let expected = CsVec::new(1, vec![0], vec![1]);
+            assert_eq!(mat.diag_offset(-4), expected);
+
+            assert_eq!(mat.diag_offset(6).dim(), 0);
+            assert_eq!(mat.diag_offset(-7).dim(), 0);
+        }
+    }
+
+    #[test]
+    fn split_diag() {
+        // | 2 -1  0  0 |
+        // |-1  2 -1  0 |
+        // | 0 -1  0 -1 |
+        // | 0  0 -1  2 |
+        let mat = CsMat::new(
+            (4, 4),
+            vec![0, 2, 5, 7, 9],
+            vec![0, 1, 0, 1, 2, 1, 3, 2, 3],
+            vec![2, -1, -1, 2, -1, -1, -1, -1, 2],
+        );
+        let expected_diag = CsVec::new(4, vec![0, 1, 3], vec![2, 2, 2]);
+        let expected_off_diag = CsMat::new(
+            (4, 4),
+            vec![0, 1, 3, 5, 6],
+            vec![1, 0, 2, 1, 3, 2],
+            vec![-1; 6],
+        );
+        let (diag, off_diag) = mat.split_diag();
+        assert_eq!(diag, expected_diag);
+        assert_eq!(diag, mat.diag());
+        assert_eq!(off_diag, expected_off_diag);
+
+        let (diag, off_diag) = mat.to_csc().split_diag();
+        assert_eq!(diag, expected_diag);
+        assert!(off_diag.is_csc());
+        assert_eq!(off_diag, expected_off_diag.to_csc());
+
+        // Rectangular matrix
+        let mat =
+            CsMat::new((2, 3), vec![0, 1, 3], vec![2, 1, 2], vec![1, 2, 3]);
+        let (diag, off_diag) = mat.split_diag();
+        assert_eq!(diag, CsVec::new(2, vec![1], vec![2]));
+        assert_eq!(
+            off_diag,
+            CsMat::new((2, 3), vec![0, 1, 2], vec![2, 2], vec![1, 3])
+        );
+    }
+
+    #[test]
+    fn trace() {
+        // | 1 0 0 3 1 3|
+        // | 0 2 0 0 0 0|
+        // | 0 0 0 1 0 1|
+        // | 3 0 1 1 0 0|
+        // | 1 0 0 0 1 0|
+        let mat = CsMat::new_csc(
+            (5, 6),
+            vec![0, 3, 4, 5, 8, 10, 12],
+            vec![0, 3, 4, 1, 3, 0, 2, 3, 0, 4, 0, 2],
+            vec![1, 3, 1, 2, 1, 3, 1, 1, 1, 1, 3, 1],
+        );
+        assert_eq!(mat.trace(), 5);
+        assert_eq!(mat.to_csr().trace(), 5);
+        assert_eq!(mat.transpose_view().trace(), 5);
+
+        let mat = mat1();
+        assert_eq!(mat.trace(), 5.);
+        assert_eq!(mat1_csc().trace(), 5.);
+        let eye: CsMat<f64> = CsMat::eye(4);
+        assert_eq!(eye.trace(), 4.);
+        let empty: CsMat<f64> = CsMat::zero((3, 0));
+        assert_eq!(empty.trace(), 0.);
+    }
+
+    #[test]
+    fn scale_rows_cols() {
+        // | 0 0 3 4 0 |
+        // | 0 0 0 2 5 |
+        // | 0 0 5 0 0 |
+        // | 0 8 0 0 0 |
+        // | 0 0 0 7 0 |
+        let diag = [1., 2., -1., 0.5, 3.];
+        let expected_rows = CsMat::new(
+            (5, 5),
+            vec![0, 2, 4, 5, 6, 7],
+            vec![2, 3, 3, 4, 2, 1, 3],
+            vec![3., 4., 4., 10., -5., 4., 21.],
+        );
+        let expected_cols = CsMat::new(
+            (5, 5),
+            vec![0, 2, 4, 5, 6, 7],
+            vec![2, 3, 3, 4, 2, 1, 3],
+            vec![-3., 2., 1., 15., -5., 16., 3.5],
+        );
+
+        let mut mat = mat1();
+        mat.scale_rows(&diag);
+        assert_eq!(mat, expected_rows);
+        let mut mat = mat1_csc();
+        mat.scale_rows(&diag);
+        assert_eq!(mat, expected_rows.to_csc());
+
+        let mut mat = mat1();
+        mat.scale_cols(&diag);
+        assert_eq!(mat, expected_cols);
+        let mut mat = mat1_csc();
+        mat.scale_cols(&diag);
+        assert_eq!(mat, expected_cols.to_csc());
+    }
+
+    #[test]
+    fn diagonal_scale() {
+        let left = [1., 2., -1., 0.5, 3.];
+        let right = [2., 1., 0.5, -1., 4.];
+        let mut expected = mat1();
+        expected.scale_rows(&left);
+        expected.scale_cols(&right);
+        assert_eq!(mat1().diagonal_scale(&left, &right), expected);
+        assert_eq!(mat1_csc().diagonal_scale(&left, &right), expected.to_csc());
+
+        // rectangular
+        let mat =
+            CsMat::new((2, 3), vec![0, 2, 3], vec![0, 2, 1], vec![1, 2, 3]);
+        let scaled = mat.to_csc().diagonal_scale(&[2, 3], &[1, 2, 3]);
+        assert_eq!(
+            scaled,
+            CsMat::new((2, 3), vec![0, 2, 3], vec![0, 2, 1], vec![2, 12, 18])
+                .to_csc()
+        );
+
+        // the structure of a middle view is rebased
+        let mat = mat1();
+        let view = mat.slice_outer(2..4);
+        let scaled = view.diagonal_scale(&[2., 3.], &[1.; 5]);
+        assert_eq!(scaled.indptr().raw_storage(), &[0, 1, 2]);
+        assert_eq!(scaled.data(), &[10., 24.]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Dimension mismatch")]
+    fn diagonal_scale_fail_dim() {
+        let _ = mat1().diagonal_scale(&[1.; 5], &[1.; 4]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn scale_rows_fail_dim() {
+        let mut mat = mat1();
+        mat.scale_rows(&[1., 2.]);
+    }
+
+    #[test]
+    fn normalize_outer() {
+        // | 0 0 3 4 0 |
+        // | 0 0 0 2 5 |
+        // | 0 0 5 0 0 |
+        // | 0 8 0 0 0 |
+        // | 0 0 0 7 0 |
+        let mut mat = mat1();
+        let sums = mat.normalize_outer();
+        assert_eq!(sums, vec![7., 7., 5., 8., 7.]);
+        for (row_sum, expected) in mat.row_sums().iter().zip(&sums) {
+            assert!((row_sum - 1.).abs() < 1e-12);
+            assert!(*expected > 0.);
+        }
+        assert_eq!(mat.get(0, 2), Some(&(3. / 7.)));
+
+        // CSC matrices get their columns normalized, and empty or
+        // zero-sum slices are left untouched
+        let mut mat = mat1().to_csc();
+        mat.set(1, 4, 0.);
+        mat.set(1, 3, 5.);
+        let sums = mat.normalize_outer();
+        assert_eq!(sums, vec![0., 8., 8., 16., 0.]);
+        assert_eq!(mat.get(1, 4), Some(&0.));
+        assert_eq!(mat.get(3, 1), Some(&1.));
+        assert_eq!(mat.get(0, 3), Some(&0.25));
+        assert_eq!(mat.get(4, 3), Some(&(7. / 16.)));
+    }
+
+    #[test]
+    fn mean_var_outer() {
+        let mat = mat1();
+        let dense = mat.to_dense();
+        let close = |a: &Array<f64, ndarray::Ix1>,
+                     b: &Array<f64, ndarray::Ix1>| {
+            a.iter().zip(b.iter()).all(|(x, y)| (x - y).abs() < 1e-12)
+        };
+        assert!(close(&mat.mean_outer(), &dense.mean_axis(Axis(1)).unwrap()));
+        assert!(close(&mat.var_outer(), &dense.var_axis(Axis(1), 0.)));
+
+        let mat = mat1_csc();
+        let dense = mat.to_dense();
+        assert!(close(&mat.mean_outer(), &dense.mean_axis(Axis(0)).unwrap()));
+        assert!(close(&mat.var_outer(), &dense.var_axis(Axis(0), 0.)));
+
+        let empty = CsMat::<f64>::zero((2, 0));
+        assert!(empty.mean_outer().iter().all(|x| x.is_nan()));
+        assert!(empty.var_outer().iter().all(|x| x.is_nan()));
+    }
+
+    #[test]
+    fn row_col_sums() {
+        // | 0 0 3 4 0 |
+        // | 0 0 0 2 5 |
+        // | 0 0 5 0 0 |
+        // | 0 8 0 0 0 |
+        // | 0 0 0 7 0 |
+        let expected_rows = ndarray::arr1(&[7., 7., 5., 8., 7.]);
+        let expected_cols = ndarray::arr1(&[0., 8., 8., 13., 5.]);
+        for mat in &[mat1(), mat1_csc()] {
+            assert_eq!(mat.row_sums(), expected_rows);
+            assert_eq!(mat.col_sums(), expected_cols);
+        }
+        let empty: CsMat<i32> = CsMat::zero((2, 3));
+        assert_eq!(empty.row_sums(), ndarray::arr1(&[0, 0]));
+        assert_eq!(empty.col_sums(), ndarray::arr1(&[0, 0, 0]));
+    }
+
+    #[test]
+    fn row_col_norms() {
+        // | 0 0 3 4 0 |
+        // | 0 0 0 2 5 |
+        // | 0 0 5 0 0 |
+        // | 0 8 0 0 0 |
+        // | 0 0 0 7 0 |
+        let expected_rows = vec![5., 29f64.sqrt(), 5., 8., 7.];
+        let expected_cols = vec![0., 8., 34f64.sqrt(), 69f64.sqrt(), 5.];
+        for mat in &[mat1(), mat1_csc()] {
+            assert_eq!(mat.row_norms(), expected_rows);
+            assert_eq!(mat.col_norms(), expected_cols);
+        }
+    }
+
+    #[test]
+    fn frobenius_norm() {
+        let eye: CsMat<f64> = CsMat::eye(5);
+        assert_eq!(eye.frobenius_norm(), 5f64.sqrt());
+        let eye: CsMat<f32> = CsMat::eye_csc(4);
+        assert_eq!(eye.frobenius_norm(), 2.);
+        // sum of squares of mat1 is 9 + 16 + 4 + 25 + 25 + 64 + 49
+        assert_eq!(mat1().frobenius_norm(), 192f64.sqrt());
+        let empty: CsMat<f64> = CsMat::zero((3, 4));
+        assert_eq!(empty.frobenius_norm(), 0.);
+
+        use num_complex::Complex64;
+        let mat = CsMat::new(
+            (2, 2),
+            vec![0, 1, 2],
+            vec![0, 1],
+            vec![Complex64::new(3., 4.), Complex64::new(0., -1.)],
+        );
+        assert_eq!(mat.frobenius_norm(), 26f64.sqrt());
+    }
+
+    #[test]
+    fn norm_inf_norm_1() {
+        // | 1 0 -2  0 |
+        // | 0 0  3 -4 |
+        // | 5 0  0  0 |
+        let mat = CsMat::new(
+            (3, 4),
+            vec![0, 2, 4, 5],
+            vec![0, 2, 2, 3, 0],
+            vec![1, -2, 3, -4, 5],
+        );
+        assert_eq!(mat.norm_inf(), 7);
+        assert_eq!(mat.norm_1(), 6);
+        let mat = mat.to_csc();
+        assert_eq!(mat.norm_inf(), 7);
+        assert_eq!(mat.norm_1(), 6);
+        assert_eq!(mat.transpose_view().norm_inf(), 6);
+        assert_eq!(mat.transpose_view().norm_1(), 7);
+
+        let empty: CsMat<f64> = CsMat::zero((0, 0));
+        assert_eq!(empty.norm_inf(), 0.);
+        assert_eq!(empty.norm_1(), 0.);
+        let zero: CsMat<f64> = CsMat::zero((3, 2));
+        assert_eq!(zero.norm_inf(), 0.);
+        assert_eq!(zero.norm_1(), 0.);
+    }
+
     #[test]
     fn onehot_zero() {
         let onehot: CsMat<f32> = CsMat::zero((3, 3)).to_inner_onehot();
@@ -2959,6 +6338,47 @@ mod test {
         let b = crate::TriMat::<i32>::new((1, 9)).to_csr();
         let _c = &a * &b;
     }
+
+    #[test]
+    fn pattern() {
+        let mat = mat1();
+        let pattern = mat.pattern();
+        assert_eq!(pattern.indptr(), mat.indptr());
+        assert_eq!(pattern.indices(), mat.indices());
+        assert!(pattern.data().iter().all(|&x| x));
+        assert_eq!(mat1_times_2().pattern(), pattern);
+        assert_eq!(mat1_csc().pattern(), pattern.to_other_storage());
+        assert_eq!(
+            pattern.transpose_view().to_owned(),
+            mat.transpose_view().pattern()
+        );
+
+        // explicit zeros are part of the pattern
+        let mut mat = mat;
+        mat.map_inplace(|_| 0.);
+        assert_eq!(mat.pattern(), pattern);
+    }
+
+    #[test]
+    fn iter_by_inner() {
+        let mat = mat1();
+        let csc = mat1_csc();
+        // grouping by inner index gives the storage order of the other
+        // storage
+        let by_col: Vec<_> = mat.iter_by_inner().collect();
+        let col_major: Vec<_> = csc
+            .iter()
+            .map(|(val, (row, col))| (row, col, val))
+            .collect();
+        assert_eq!(by_col, col_major);
+        let by_row: Vec<_> = csc.iter_by_inner().collect();
+        let row_major: Vec<_> = mat.iter_rc().collect();
+        assert_eq!(by_row, row_major);
+        assert_eq!(csc.iter_rc().collect::<Vec<_>>(), row_major);
+
+        let empty = CsMat::<f64>::zero((3, 0));
+        assert_eq!(empty.iter_by_inner().count(), 0);
+    }
 }
 
 #[cfg(feature = "approx")]
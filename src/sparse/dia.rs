@@ -0,0 +1,312 @@
+//! Diagonal (DIA) storage for banded matrices
+//!
+//! In the DIA format, a matrix is stored as a set of diagonals, each
+//! diagonal being identified by its offset from the main diagonal: offset
+//! `0` is the main diagonal, positive offsets are above it and negative
+//! offsets below it. The values are stored in a dense array with one row per
+//! stored diagonal, and no index is needed for the individual entries. This
+//! makes the format very compact and cache friendly for banded matrices.
+
+use ndarray::{Array, Array2, ArrayView1, ArrayView2, Ix1};
+use num_traits::{Num, Zero};
+use std::ops::Deref;
+
+use crate::indexing::SpIndex;
+use crate::sparse::{CsMatBase, CsMatI};
+use crate::{MulAcc, CSR};
+
+/// A sparse matrix stored by diagonals.
+///
+/// The values of the diagonal of offset `offsets[k]` are stored in the
+/// row `k` of `data`, aligned by column: the entry `(i, j)` of the matrix,
+/// with `j - i == offsets[k]`, is stored in `data[[k, j]]`. The values of
+/// `data` falling outside of the matrix are padding and are ignored.
+#[derive(PartialEq, Debug, Clone)]
+pub struct DiaMat<N> {
+    nrows: usize,
+    ncols: usize,
+    offsets: Vec<isize>,
+    data: Array2<N>,
+}
+
+impl<N> DiaMat<N> {
+    /// Create a matrix from its diagonals.
+    ///
+    /// # Panics
+    ///
+    /// - if `data` does not have one row per offset and one column per
+    ///   column of the matrix
+    /// - if `offsets` are not sorted in increasing order without duplicates
+    /// - if an offset corresponds to a diagonal outside of the matrix
+    pub fn new(
+        shape: (usize, usize),
+        offsets: Vec<isize>,
+        data: Array2<N>,
+    ) -> Self {
+        let (nrows, ncols) = shape;
+        assert_eq!(data.shape(), &[offsets.len(), ncols], "Dimension mismatch");
+        assert!(
+            offsets.windows(2).all(|w| w[0] < w[1]),
+            "Unsorted diagonal offsets"
+        );
+        assert!(
+            offsets
+                .iter()
+                .all(|&d| d < ncols as isize && -d < nrows as isize),
+            "Diagonal offset out of bounds"
+        );
+        Self {
+            nrows,
+            ncols,
+            offsets,
+            data,
+        }
+    }
+
+    /// The number of rows of the matrix
+    pub fn rows(&self) -> usize {
+        self.nrows
+    }
+
+    /// The number of cols of the matrix
+    pub fn cols(&self) -> usize {
+        self.ncols
+    }
+
+    /// The shape of the matrix, as a `(rows, cols)` tuple
+    pub fn shape(&self) -> (usize, usize) {
+        (self.nrows, self.ncols)
+    }
+
+    /// The offsets of the stored diagonals, in increasing order
+    pub fn offsets(&self) -> &[isize] {
+        &self.offsets
+    }
+
+    /// The values of the stored diagonals, one row per diagonal
+    pub fn data(&self) -> ArrayView2<'_, N> {
+        self.data.view()
+    }
+
+    /// The range of columns spanned by the diagonal of offset `offset`
+    fn diag_cols(&self, offset: isize) -> std::ops::Range<usize> {
+        let start = if offset > 0 { offset as usize } else { 0 };
+        let end = if offset < 0 {
+            self.ncols.min(self.nrows - offset.wrapping_neg() as usize)
+        } else {
+            self.ncols.min(self.nrows + offset as usize)
+        };
+        start..end
+    }
+
+    /// Compute the product of this matrix with the dense vector `x`,
+    /// by iterating over the stored diagonals.
+    ///
+    /// # Panics
+    ///
+    /// If the length of `x` does not match the number of columns.
+    pub fn matvec(&self, x: ArrayView1<N>) -> Array<N, Ix1>
+    where
+        N: Num + Clone + MulAcc,
+    {
+        assert_eq!(self.cols(), x.len(), "Dimension mismatch");
+        let mut y = Array::from_elem(self.rows(), N::zero());
+        for (&offset, diag) in self.offsets.iter().zip(self.data.outer_iter()) {
+            for j in self.diag_cols(offset) {
+                let i = (j as isize - offset) as usize;
+                y[i].mul_acc(&diag[j], &x[j]);
+            }
+        }
+        y
+    }
+}
+
+/// # Conversions with the diagonal format
+impl<N, I, Iptr, IptrStorage, IndStorage, DataStorage>
+    CsMatBase<N, I, IptrStorage, IndStorage, DataStorage, Iptr>
+where
+    I: SpIndex,
+    Iptr: SpIndex,
+    IptrStorage: Deref<Target = [Iptr]>,
+    IndStorage: Deref<Target = [I]>,
+    DataStorage: Deref<Target = [N]>,
+{
+    /// Convert this matrix to the diagonal format.
+    ///
+    /// Returns `None` if the matrix is not banded enough for this format
+    /// to be worthwhile, ie if storing its diagonals would require more
+    /// than twice as many values as its number of non-zeros.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::{CsMat, TriMat};
+    /// use ndarray::arr1;
+    /// let mut tri = TriMat::new((4, 4));
+    /// for i in 0..4 {
+    ///     tri.add_triplet(i, i, 2.);
+    ///     if i > 0 {
+    ///         tri.add_triplet(i, i - 1, -1.);
+    ///     }
+    /// }
+    /// let mat: CsMat<f64> = tri.to_csr();
+    /// let dia = mat.to_dia().unwrap();
+    /// assert_eq!(dia.offsets(), &[-1, 0]);
+    /// let x = arr1(&[1., 2., 3., 4.]);
+    /// assert_eq!(dia.matvec(x.view()), &mat * &x);
+    /// assert_eq!(CsMat::from_dia(&dia), mat);
+    /// ```
+    pub fn to_dia(&self) -> Option<DiaMat<N>>
+    where
+        N: Zero + Clone,
+    {
+        let (nrows, ncols) = self.shape();
+        // Diagonal of offset d is found at position d + nrows - 1
+        let mut has_diag = vec![false; (nrows + ncols).saturating_sub(1)];
+        for (_, (row, col)) in self.iter() {
+            has_diag[col.index() + nrows - 1 - row.index()] = true;
+        }
+        let offsets: Vec<isize> = has_diag
+            .iter()
+            .enumerate()
+            .filter(|&(_, &has)| has)
+            .map(|(pos, _)| pos as isize - (nrows as isize - 1))
+            .collect();
+        if offsets.len() * ncols > 2 * self.nnz() {
+            return None;
+        }
+        let mut diag_pos = vec![0; has_diag.len()];
+        for (k, &offset) in offsets.iter().enumerate() {
+            diag_pos[(offset + nrows as isize - 1) as usize] = k;
+        }
+        let mut data = Array2::from_elem((offsets.len(), ncols), N::zero());
+        for (val, (row, col)) in self.iter() {
+            let k = diag_pos[col.index() + nrows - 1 - row.index()];
+            data[[k, col.index()]] = val.clone();
+        }
+        Some(DiaMat {
+            nrows,
+            ncols,
+            offsets,
+            data,
+        })
+    }
+}
+
+impl<N, I: SpIndex, Iptr: SpIndex> CsMatI<N, I, Iptr> {
+    /// Create a CSR matrix from a matrix stored in the diagonal format.
+    /// The zero values of the diagonals are not kept.
+    pub fn from_dia(dia: &DiaMat<N>) -> Self
+    where
+        N: Zero + Clone,
+    {
+        let (nrows, ncols) = dia.shape();
+        let mut indptr = Vec::with_capacity(nrows + 1);
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        indptr.push(Iptr::zero());
+        for i in 0..nrows {
+            // increasing offsets give increasing columns
+            for (&offset, diag) in dia.offsets.iter().zip(dia.data.outer_iter())
+            {
+                let j = i as isize + offset;
+                if j < 0 || j >= ncols as isize {
+                    continue;
+                }
+                let val = &diag[j as usize];
+                if !val.is_zero() {
+                    indices.push(I::from_usize(j as usize));
+                    data.push(val.clone());
+                }
+            }
+            indptr.push(Iptr::from_usize(indices.len()));
+        }
+        Self::new_trusted(CSR, (nrows, ncols), indptr, indices, data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DiaMat;
+    use crate::sparse::CsMat;
+    use ndarray::{arr1, arr2};
+
+    fn banded() -> CsMat<f64> {
+        // | 1 2 . . . |
+        // | . 3 4 . . |
+        // | 5 . 6 7 . |
+        // | . 8 . 9 1 |
+        CsMat::new(
+            (4, 5),
+            vec![0, 2, 4, 7, 10],
+            vec![0, 1, 1, 2, 0, 2, 3, 1, 3, 4],
+            vec![1., 2., 3., 4., 5., 6., 7., 8., 9., 1.],
+        )
+    }
+
+    #[test]
+    fn to_dia() {
+        let mat = banded();
+        let dia = mat.to_dia().unwrap();
+        assert_eq!(dia.shape(), (4, 5));
+        assert_eq!(dia.offsets(), &[-2, 0, 1]);
+        let expected = arr2(&[
+            [5., 8., 0., 0., 0.],
+            [1., 3., 6., 9., 0.],
+            [0., 2., 4., 7., 1.],
+        ]);
+        assert_eq!(dia.data(), expected.view());
+        assert_eq!(mat.to_csc().to_dia(), Some(dia.clone()));
+        assert_eq!(CsMat::from_dia(&dia), mat);
+    }
+
+    #[test]
+    fn to_dia_not_banded() {
+        // an anti-diagonal needs one diagonal per non-zero
+        let mat = CsMat::new(
+            (4, 4),
+            vec![0, 1, 2, 3, 4],
+            vec![3, 2, 1, 0],
+            vec![1., 2., 3., 4.],
+        );
+        assert!(mat.to_dia().is_none());
+        let dia = CsMat::<f64>::zero((3, 2)).to_dia().unwrap();
+        assert!(dia.offsets().is_empty());
+        assert_eq!(CsMat::from_dia(&dia), CsMat::zero((3, 2)));
+    }
+
+    #[test]
+    fn dia_matvec() {
+        let mat = banded();
+        let x = arr1(&[1., -1., 2., 0.5, 3.]);
+        let dia = mat.to_dia().unwrap();
+        assert_eq!(dia.matvec(x.view()), &mat * &x);
+        let mat = mat.transpose_into();
+        let x = arr1(&[1., -1., 2., 0.5]);
+        let dia = mat.to_dia().unwrap();
+        assert_eq!(dia.offsets(), &[-1, 0, 2]);
+        assert_eq!(dia.matvec(x.view()), &mat * &x);
+    }
+
+    #[test]
+    fn dia_new() {
+        let dia = DiaMat::new(
+            (3, 3),
+            vec![-1, 0],
+            arr2(&[[1., 2., 0.], [3., 4., 5.]]),
+        );
+        let expected = CsMat::new(
+            (3, 3),
+            vec![0, 1, 3, 5],
+            vec![0, 0, 1, 1, 2],
+            vec![3., 1., 4., 2., 5.],
+        );
+        assert_eq!(CsMat::from_dia(&dia), expected);
+    }
+
+    #[test]
+    #[should_panic]
+    fn dia_new_bad_offset() {
+        let _ = DiaMat::new((3, 3), vec![3], arr2(&[[1., 2., 3.]]));
+    }
+}
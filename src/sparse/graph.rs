@@ -0,0 +1,461 @@
+//! Graph algorithms on the sparsity pattern of square matrices
+//!
+//! A square sparse matrix can be seen as the adjacency structure of a graph,
+//! where there is an edge between vertices `i` and `j` if the matrix has a
+//! stored entry at location `(i, j)`. As in the rest of the crate, explicitly
+//! stored zeros are part of the sparsity pattern and thus define edges.
+
+use std::collections::VecDeque;
+
+use num_traits::Float;
+
+use crate::indexing::SpIndex;
+use crate::sparse::{CsMatI, CsMatViewI, CsStructureI};
+
+/// The kinds of graph Laplacian matrices that can be built by
+/// [`laplacian`](laplacian).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LaplacianKind {
+    /// The combinatorial Laplacian `D - A`
+    Combinatorial,
+    /// The symmetric normalized Laplacian `I - D^-1/2 A D^-1/2`
+    SymmetricNormalized,
+}
+
+/// Compute the connected components of the graph whose adjacency structure
+/// is given by the sparsity pattern of `mat`.
+///
+/// The pattern is treated as an undirected graph, ie an entry at `(i, j)`
+/// connects `i` and `j` even if there is no entry at `(j, i)`. Self-loops,
+/// ie diagonal entries, have no influence on the result.
+///
+/// Returns the number of components, and the component label of each
+/// vertex. Labels are in `0..nb_components`, and are attributed in the order
+/// of the lowest vertex of each component.
+///
+/// # Panics
+///
+/// If `mat` is not square.
+///
+/// # Example
+///
+/// ```rust
+/// use sprs::{graph, CsMat};
+/// // 0 - 2   1   3 - 4
+/// let mat = CsMat::new(
+///     (5, 5),
+///     vec![0, 1, 1, 1, 2, 2],
+///     vec![2, 4],
+///     vec![1., 1.],
+/// );
+/// let (nb_components, labels) = graph::connected_components(mat.view());
+/// assert_eq!(nb_components, 3);
+/// assert_eq!(labels, vec![0, 1, 0, 2, 2]);
+/// ```
+pub fn connected_components<N, I, Iptr>(
+    mat: CsMatViewI<N, I, Iptr>,
+) -> (usize, Vec<usize>)
+where
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    assert_eq!(mat.rows(), mat.cols(), "Non square matrix");
+    let nb_vertices = mat.rows();
+    let adjacency = symmetrized_structure(mat);
+    let mut labels = vec![usize::MAX; nb_vertices];
+    let mut nb_components = 0;
+    let mut queue = VecDeque::new();
+    for root in 0..nb_vertices {
+        if labels[root] != usize::MAX {
+            continue;
+        }
+        labels[root] = nb_components;
+        queue.push_back(root);
+        while let Some(vertex) = queue.pop_front() {
+            let outer = adjacency.outer_view(vertex).unwrap();
+            for &neighbor in outer.indices() {
+                let neighbor = neighbor.index();
+                if labels[neighbor] == usize::MAX {
+                    labels[neighbor] = nb_components;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        nb_components += 1;
+    }
+    (nb_components, labels)
+}
+
+/// Compute the order in which a breadth-first search starting from the
+/// vertex `start` visits the vertices of the graph whose adjacency
+/// structure is given by the sparsity pattern of `mat`.
+///
+/// The pattern is treated as a directed graph, with an edge from `i` to `j`
+/// for an entry at `(i, j)`, ie the outgoing edges of a vertex are given by
+/// its row. The neighbors of a vertex are visited by increasing index.
+///
+/// If `include_unreachable` is `false`, only the vertices reachable from
+/// `start` are returned. Otherwise, the search is restarted from the lowest
+/// unvisited vertex until all vertices have been visited, and the result is
+/// a permutation of the vertices.
+///
+/// # Panics
+///
+/// - if `mat` is not square
+/// - if `start` is out of bounds
+///
+/// # Example
+///
+/// ```rust
+/// use sprs::{graph, CsMat};
+/// // 0 -> 2 -> 1   3
+/// let mat = CsMat::new(
+///     (4, 4),
+///     vec![0, 1, 1, 2, 2],
+///     vec![2, 1],
+///     vec![1., 1.],
+/// );
+/// assert_eq!(graph::bfs_order(mat.view(), 0, false), vec![0, 2, 1]);
+/// assert_eq!(graph::bfs_order(mat.view(), 2, true), vec![2, 1, 0, 3]);
+/// ```
+pub fn bfs_order<N, I, Iptr>(
+    mat: CsMatViewI<N, I, Iptr>,
+    start: usize,
+    include_unreachable: bool,
+) -> Vec<usize>
+where
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    assert_eq!(mat.rows(), mat.cols(), "Non square matrix");
+    let nb_vertices = mat.rows();
+    assert!(start < nb_vertices, "Out of bounds index");
+    let adjacency = if mat.is_csr() {
+        mat.structure_view().to_owned()
+    } else {
+        mat.structure_view().to_other_storage()
+    };
+    let mut visited = vec![false; nb_vertices];
+    let mut order = Vec::with_capacity(nb_vertices);
+    let mut queue = VecDeque::new();
+    let roots = std::iter::once(start).chain(0..nb_vertices);
+    for root in roots {
+        if visited[root] {
+            continue;
+        }
+        if root != start && !include_unreachable {
+            break;
+        }
+        visited[root] = true;
+        queue.push_back(root);
+        while let Some(vertex) = queue.pop_front() {
+            order.push(vertex);
+            let outer = adjacency.outer_view(vertex).unwrap();
+            for &neighbor in outer.indices() {
+                let neighbor = neighbor.index();
+                if !visited[neighbor] {
+                    visited[neighbor] = true;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+    }
+    order
+}
+
+/// Build the Laplacian matrix of the weighted graph whose adjacency matrix
+/// is `adjacency`, which is expected to be symmetric.
+///
+/// The degree of a vertex is the sum of the weights of its row, and `D` is
+/// the diagonal matrix of the degrees. Depending on `kind`, the result is
+/// the combinatorial Laplacian `D - A` or the symmetric normalized
+/// Laplacian `I - D^-1/2 A D^-1/2`. In the normalized case, the row and
+/// column of an isolated vertex, ie of a vertex of degree zero, are filled
+/// with zeros instead of NaN, its diagonal entry being stored as an explicit
+/// zero.
+///
+/// The result has the storage of `adjacency`, its pattern is the pattern of
+/// `adjacency` with the diagonal added.
+///
+/// # Panics
+///
+/// If `adjacency` is not square.
+///
+/// # Example
+///
+/// ```rust
+/// use sprs::graph::{laplacian, LaplacianKind};
+/// use sprs::CsMat;
+/// use ndarray::arr2;
+/// // 0 - 1 - 2, with weights 1 and 3
+/// let adjacency = CsMat::csr_from_dense(
+///     arr2(&[[0., 1., 0.], [1., 0., 3.], [0., 3., 0.]]).view(),
+///     0.,
+/// );
+/// let lap = laplacian(adjacency.view(), LaplacianKind::Combinatorial);
+/// let expected = arr2(&[[1., -1., 0.], [-1., 4., -3.], [0., -3., 3.]]);
+/// assert_eq!(lap.to_dense(), expected);
+/// ```
+pub fn laplacian<N, I, Iptr>(
+    adjacency: CsMatViewI<N, I, Iptr>,
+    kind: LaplacianKind,
+) -> CsMatI<N, I, Iptr>
+where
+    N: Float,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    assert_eq!(adjacency.rows(), adjacency.cols(), "Non square matrix");
+    let n = adjacency.rows();
+    let mut degrees = vec![N::zero(); n];
+    for (&val, (row, _)) in adjacency.iter() {
+        degrees[row.index()] = degrees[row.index()] + val;
+    }
+    let inv_sqrt_degrees: Vec<N> = degrees
+        .iter()
+        .map(|&d| if d == N::zero() { d } else { d.sqrt().recip() })
+        .collect();
+    let off_diag = |val: N, i: usize, j: usize| match kind {
+        LaplacianKind::Combinatorial => -val,
+        LaplacianKind::SymmetricNormalized => {
+            -val * inv_sqrt_degrees[i] * inv_sqrt_degrees[j]
+        }
+    };
+    let diag = |self_loop: N, i: usize| match kind {
+        LaplacianKind::Combinatorial => degrees[i] - self_loop,
+        LaplacianKind::SymmetricNormalized => {
+            if degrees[i] == N::zero() {
+                N::zero()
+            } else {
+                N::one() - self_loop / degrees[i]
+            }
+        }
+    };
+
+    let mut indptr = Vec::with_capacity(n + 1);
+    let mut indices = Vec::with_capacity(adjacency.nnz() + n);
+    let mut data = Vec::with_capacity(adjacency.nnz() + n);
+    indptr.push(Iptr::zero());
+    for (outer, vec) in adjacency.outer_iterator().enumerate() {
+        let mut self_loop = N::zero();
+        let mut diag_pos = None;
+        for (inner, &val) in vec.iter() {
+            if inner == outer {
+                self_loop = val;
+                continue;
+            }
+            if inner > outer && diag_pos.is_none() {
+                diag_pos = Some(indices.len());
+                indices.push(I::from_usize(outer));
+                data.push(N::zero());
+            }
+            indices.push(I::from_usize(inner));
+            data.push(off_diag(val, inner, outer));
+        }
+        let diag_pos = diag_pos.unwrap_or_else(|| {
+            indices.push(I::from_usize(outer));
+            data.push(N::zero());
+            indices.len() - 1
+        });
+        data[diag_pos] = diag(self_loop, outer);
+        indptr.push(Iptr::from_usize(indices.len()));
+    }
+    CsMatI::new_trusted(adjacency.storage(), (n, n), indptr, indices, data)
+}
+
+/// Compute the union of the sparsity patterns of a square matrix and of its
+/// transpose, ie the adjacency structure of the undirected graph it
+/// represents. The returned structure has the same storage as `mat`.
+pub(crate) fn symmetrized_structure<N, I, Iptr>(
+    mat: CsMatViewI<N, I, Iptr>,
+) -> CsStructureI<I, Iptr>
+where
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    assert_eq!(mat.rows(), mat.cols(), "Non square matrix");
+    let transposed = mat.structure_view().to_other_storage();
+    let mut indptr = Vec::with_capacity(mat.outer_dims() + 1);
+    let mut indices = Vec::with_capacity(2 * mat.nnz());
+    indptr.push(Iptr::zero());
+    for (outer, outer_t) in
+        mat.outer_iterator().zip(transposed.outer_iterator())
+    {
+        let (mut lhs, mut rhs) = (outer.indices(), outer_t.indices());
+        while let (Some(&l), Some(&r)) = (lhs.first(), rhs.first()) {
+            if l <= r {
+                indices.push(l);
+                lhs = &lhs[1..];
+            }
+            if r <= l {
+                if r < l {
+                    indices.push(r);
+                }
+                rhs = &rhs[1..];
+            }
+        }
+        indices.extend_from_slice(lhs);
+        indices.extend_from_slice(rhs);
+        indptr.push(Iptr::from_usize(indices.len()));
+    }
+    let data = vec![(); indices.len()];
+    CsStructureI::new_trusted(mat.storage(), mat.shape(), indptr, indices, data)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        bfs_order, connected_components, laplacian, symmetrized_structure,
+        LaplacianKind,
+    };
+    use crate::sparse::{CsMat, TriMat};
+    use ndarray::arr2;
+
+    #[test]
+    fn bfs_order_directed() {
+        // 4 -> 0 -> 3 -> 1
+        //      0 -> 1
+        // 2 -> 5
+        let mut tri = TriMat::new((6, 6));
+        for &(i, j) in &[(4, 0), (0, 3), (3, 1), (0, 1), (2, 5)] {
+            tri.add_triplet(i, j, 1.);
+        }
+        tri.add_triplet(3, 3, 1.);
+        let mat: CsMat<f64> = tri.to_csr();
+        for mat in &[mat.view(), mat.to_csc().view()] {
+            assert_eq!(bfs_order(mat.view(), 4, false), vec![4, 0, 1, 3]);
+            assert_eq!(bfs_order(mat.view(), 1, false), vec![1]);
+            assert_eq!(bfs_order(mat.view(), 3, true), vec![3, 1, 0, 2, 5, 4]);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Out of bounds index")]
+    fn bfs_order_bad_start() {
+        let mat = CsMat::<f64>::eye(3);
+        let _ = bfs_order(mat.view(), 3, true);
+    }
+
+    #[test]
+    fn laplacian_combinatorial() {
+        // 0 - 1 - 2   3, with a self-loop on 2
+        let adjacency = CsMat::csr_from_dense(
+            arr2(&[
+                [0., 2., 0., 0.],
+                [2., 0., 1., 0.],
+                [0., 1., 5., 0.],
+                [0., 0., 0., 0.],
+            ])
+            .view(),
+            0.,
+        );
+        let expected = arr2(&[
+            [2., -2., 0., 0.],
+            [-2., 3., -1., 0.],
+            [0., -1., 1., 0.],
+            [0., 0., 0., 0.],
+        ]);
+        for adjacency in &[adjacency.view(), adjacency.to_csc().view()] {
+            let lap = laplacian(adjacency.view(), LaplacianKind::Combinatorial);
+            assert_eq!(lap.storage(), adjacency.storage());
+            assert!(lap.is_sorted());
+            assert_eq!(lap.to_dense(), expected);
+            assert_eq!(lap.nnz(), adjacency.nnz() + 3);
+        }
+    }
+
+    #[test]
+    fn laplacian_normalized() {
+        // 0 - 1 - 2   3
+        let adjacency = CsMat::csr_from_dense(
+            arr2(&[
+                [0., 1., 0., 0.],
+                [1., 0., 4., 0.],
+                [0., 4., 0., 0.],
+                [0., 0., 0., 0.],
+            ])
+            .view(),
+            0.,
+        );
+        let lap =
+            laplacian(adjacency.view(), LaplacianKind::SymmetricNormalized);
+        let s5 = 5f64.sqrt();
+        let expected = arr2(&[
+            [1., -1. / s5, 0., 0.],
+            [-1. / s5, 1., -4. / (2. * s5), 0.],
+            [0., -4. / (2. * s5), 1., 0.],
+            [0., 0., 0., 0.],
+        ]);
+        let diff = lap.to_dense() - expected;
+        assert!(diff.iter().all(|x| x.abs() < 1e-12));
+        assert!(lap.data().iter().all(|x| x.is_finite()));
+    }
+
+    #[test]
+    fn connected_components_lap() {
+        // 0 - 1   2 - 3 - 4   5
+        let mut tri = TriMat::new((6, 6));
+        for &(i, j) in &[(0, 1), (2, 3), (3, 4)] {
+            tri.add_triplet(i, j, -1.);
+            tri.add_triplet(j, i, -1.);
+        }
+        for i in 0..6 {
+            tri.add_triplet(i, i, 2.);
+        }
+        let mat: CsMat<f64> = tri.to_csr();
+        let (nb, labels) = connected_components(mat.view());
+        assert_eq!(nb, 3);
+        assert_eq!(labels, vec![0, 0, 1, 1, 1, 2]);
+        let (nb, labels) = connected_components(mat.to_csc().view());
+        assert_eq!(nb, 3);
+        assert_eq!(labels, vec![0, 0, 1, 1, 1, 2]);
+    }
+
+    #[test]
+    fn connected_components_unsymmetric() {
+        // Edges only stored in one direction: 3 -> 0, 1 -> 2, 2 -> 4
+        let mut tri = TriMat::new((5, 5));
+        tri.add_triplet(3, 0, 1.);
+        tri.add_triplet(1, 2, 1.);
+        tri.add_triplet(2, 4, 0.);
+        let mat: CsMat<f64> = tri.to_csr();
+        let (nb, labels) = connected_components(mat.view());
+        assert_eq!(nb, 2);
+        assert_eq!(labels, vec![0, 1, 1, 0, 1]);
+    }
+
+    #[test]
+    fn symmetrized_structure_unsymmetric() {
+        // | x x . . |
+        // | . . x . |
+        // | x . x . |
+        // | . x . . |
+        let mat = CsMat::new(
+            (4, 4),
+            vec![0, 2, 3, 5, 6],
+            vec![0, 1, 2, 0, 2, 1],
+            vec![1.; 6],
+        );
+        let sym = symmetrized_structure(mat.view());
+        assert_eq!(sym.indptr().raw_storage(), &[0, 3, 6, 9, 10]);
+        assert_eq!(sym.indices(), &[0, 1, 2, 0, 2, 3, 0, 1, 2, 1]);
+        assert_eq!(sym, symmetrized_structure(mat.to_csc().view()).to_csr());
+    }
+
+    #[test]
+    fn connected_components_empty() {
+        let mat = CsMat::<f64>::zero((4, 4));
+        let (nb, labels) = connected_components(mat.view());
+        assert_eq!(nb, 4);
+        assert_eq!(labels, vec![0, 1, 2, 3]);
+        let mat = CsMat::<f64>::zero((0, 0));
+        assert_eq!(connected_components(mat.view()), (0, Vec::new()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn connected_components_non_square() {
+        let mat = CsMat::<f64>::zero((4, 3));
+        let _ = connected_components(mat.view());
+    }
+}
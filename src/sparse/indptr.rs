@@ -402,6 +402,12 @@ impl<Iptr: SpIndex> IndPtr<Iptr> {
             *val += Iptr::one();
         }
     }
+
+    pub(crate) fn record_removed_element(&mut self, outer_ind: usize) {
+        for val in self.storage[outer_ind + 1..].iter_mut() {
+            *val = *val - Iptr::one();
+        }
+    }
 }
 
 impl<'a, Iptr: SpIndex> IndPtrView<'a, Iptr> {
@@ -45,7 +45,11 @@ scalar_kronecker!(Complex64);
 ///
 /// # Panics
 ///
-/// * if indices are out of bounds for its type
+/// * if the shape of the product overflows `usize`
+/// * if the inner dimension of the product is too large to be represented by
+///   the index type `I`
+/// * if the number of non-zeros of the product is too large to be represented
+///   by the index pointer type `Iptr`
 #[must_use]
 pub fn kronecker_product<Nin, Nout, I, Iptr>(
     mut a: CsMatViewI<Nin, I, Iptr>,
@@ -64,13 +68,26 @@ where
             a.transpose_mut();
             b.transpose_mut();
         }
-        let nnz = a.nnz() * b.nnz();
         let a_shape = a.shape();
         let b_shape = b.shape();
-        let shape = (a_shape.0 * b_shape.0, a_shape.1 * b_shape.1);
+        let shape = a_shape
+            .0
+            .checked_mul(b_shape.0)
+            .zip(a_shape.1.checked_mul(b_shape.1))
+            .expect("Kronecker product shape overflows usize");
+        let nnz = a
+            .nnz()
+            .checked_mul(b.nnz())
+            .expect("Kronecker product nnz overflows usize");
+        if I::from(shape.1.saturating_sub(1)).is_none() {
+            panic!("Kronecker product shape does not fit the index type");
+        }
+        if Iptr::from(nnz).is_none() {
+            panic!("Kronecker product nnz does not fit the index pointer type");
+        }
         let mut values = Vec::with_capacity(nnz);
         let mut indices = Vec::with_capacity(nnz);
-        let mut indptr = Vec::with_capacity(shape.1 + 1);
+        let mut indptr = Vec::with_capacity(shape.0 + 1);
 
         let mut element_count = Iptr::zero();
         indptr.push(element_count);
@@ -78,7 +95,7 @@ where
             for b in b.outer_iterator() {
                 for (ai, a) in a.iter() {
                     for (bi, b) in b.iter() {
-                        indices.push(I::from(ai * b_shape.1 + bi).unwrap());
+                        indices.push(I::from_usize(ai * b_shape.1 + bi));
                         element_count += Iptr::one();
                         values.push(a.kron(b));
                     }
@@ -150,3 +167,11 @@ fn test_kronecker_product() {
     let c = kronecker_product(a.view(), b.view());
     check(c.view());
 }
+
+#[test]
+#[should_panic(expected = "does not fit the index type")]
+fn test_kronecker_product_index_overflow() {
+    let a = CsMatI::<f64, i16>::eye(200);
+    let b = CsMatI::<f64, i16>::eye(200);
+    let _: CsMatI<f64, i16> = kronecker_product(a.view(), b.view());
+}
@@ -1,15 +1,41 @@
+//! Sparse linear algebra
+//!
+//! This module contains solvers for sparse linear systems. Currently
+//! there are solver for sparse triangular systems and symmetric systems.
+//!
+//! The fallible routines of this module return a `LinalgError`, and not
+//! the crate-wide `SprsError`: a `LinalgError` only has the variants a
+//! linear algebra routine can produce, such as a non square or singular
+//! matrix or a solver breakdown, so callers do not have to handle
+//! structure or arithmetic errors that cannot happen. It converts into a
+//! `SprsError` using `?`.
+
 use crate::{DenseVector, DenseVectorMut};
-///! Sparse linear algebra
-///!
-///! This module contains solvers for sparse linear systems. Currently
-///! there are solver for sparse triangular systems and symmetric systems.
 use num_traits::Num;
 
+pub mod cholesky;
+pub mod condest;
+pub mod eigen;
+pub mod equilibrate;
 pub mod etree;
+pub mod ilu;
+pub mod iterative;
+pub mod lu;
 pub mod ordering;
 pub mod trisolve;
 
+pub use self::cholesky::{cholesky, SparseCholesky};
+pub use self::condest::condest;
+pub use self::eigen::power_iteration;
+pub use self::equilibrate::ruiz_equilibrate;
+pub use self::ilu::ilu0;
+pub use self::iterative::{
+    bicgstab, cg, jacobi_preconditioner, lsqr, pcg, sor, CgResult, DiagPrecond,
+    LsqrResult,
+};
+pub use self::lu::{lu, SparseLu};
 pub use self::ordering::reverse_cuthill_mckee;
+pub use self::trisolve::{trisolve_lower, trisolve_upper};
 
 /// Diagonal solve
 pub fn diag_solve<'a, N, V1, V2>(diag: V1, mut x: V2)
@@ -0,0 +1,295 @@
+//! Sparse Cholesky factorization
+//!
+//! The factor is computed row by row (up-looking algorithm), the non-zero
+//! pattern of each row being obtained by walking the elimination tree, which
+//! is built along the way. No fill-reducing ordering is performed, so it can
+//! be beneficial to reorder the matrix beforehand, for instance with
+//! [`reverse_cuthill_mckee`](crate::linalg::reverse_cuthill_mckee). For
+//! an `L D L^T` factorization with a fill-reducing ordering, see the
+//! `sprs-ldl` crate.
+
+use crate::errors::LinalgError;
+use crate::indexing::SpIndex;
+use crate::sparse::{CsMatI, CsMatViewI};
+use crate::CSC;
+use ndarray::{Array1, ArrayView1};
+use num_traits::Float;
+
+/// The Cholesky factorization `A = L L^T` of a symmetric positive definite
+/// sparse matrix `A`, where `L` is lower triangular with a positive diagonal.
+#[derive(Debug, Clone)]
+pub struct SparseCholesky<N, I: SpIndex = usize, Iptr: SpIndex = I> {
+    l: CsMatI<N, I, Iptr>,
+}
+
+/// Compute the Cholesky factorization of the symmetric positive definite
+/// matrix `a`, which can be stored as CSR or CSC.
+///
+/// Only the entries of each outer vector up to the diagonal are read, ie
+/// the upper triangle of a CSC matrix or the lower triangle of a CSR
+/// matrix, the other triangle being assumed symmetric.
+///
+/// Returns an error if `a` is not square, or
+/// `LinalgError::NotPositiveDefinite` if a non-positive pivot is met, which
+/// means `a` is not positive definite. This makes this function usable as
+/// a positive definiteness test.
+///
+/// # Example
+///
+/// ```rust
+/// use ndarray::arr1;
+/// use sprs::CsMat;
+/// use sprs::linalg::cholesky;
+/// // | 4 2 |
+/// // | 2 5 |
+/// let a = CsMat::new_csc((2, 2), vec![0, 2, 4], vec![0, 1, 0, 1],
+///                        vec![4., 2., 2., 5.]);
+/// let chol = cholesky(a.view()).unwrap();
+/// assert_eq!(chol.l().to_dense(), ndarray::arr2(&[[2., 0.], [1., 2.]]));
+/// let x = chol.solve(arr1(&[8., 12.]).view());
+/// assert_eq!(x, arr1(&[1., 2.]));
+/// ```
+pub fn cholesky<N, I, Iptr>(
+    a: CsMatViewI<N, I, Iptr>,
+) -> Result<SparseCholesky<N, I, Iptr>, LinalgError>
+where
+    N: Float,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    let n = a.rows();
+    if a.cols() != n {
+        return Err(LinalgError::NonSquareMatrix);
+    }
+    // The columns of L, with their diagonal entry first. Row k of L is
+    // appended at the end of the columns it touches when it is computed,
+    // so the row indices of each column stay sorted.
+    let mut l_cols: Vec<Vec<(usize, N)>> = vec![Vec::new(); n];
+    let mut parents: Vec<Option<usize>> = vec![None; n];
+    let mut marked = vec![false; n];
+    let mut x = vec![N::zero(); n];
+    let mut pattern = vec![0; n];
+
+    for k in 0..n {
+        // Symbolic step: the pattern of row k of L is the union of the
+        // paths from the non-zeros of the column to k in the etree
+        let mut top = n;
+        marked[k] = true;
+        for (i, &val) in a.outer_view(k).unwrap().iter() {
+            if i > k {
+                continue;
+            }
+            x[i] = x[i] + val;
+            let mut len = 0;
+            let mut node = i;
+            while !marked[node] {
+                marked[node] = true;
+                pattern[len] = node;
+                len += 1;
+                node = match parents[node] {
+                    Some(parent) => parent,
+                    None => {
+                        parents[node] = Some(k);
+                        k
+                    }
+                };
+            }
+            // Each path must be processed after the paths it leads to were
+            // discovered, and in its own order
+            pattern.copy_within(0..len, top - len);
+            top -= len;
+        }
+
+        // Numeric step: sparse triangular solve in topological order
+        let mut diag = x[k];
+        x[k] = N::zero();
+        marked[k] = false;
+        for &i in &pattern[top..] {
+            let col = &mut l_cols[i];
+            let l_ki = x[i] / col[0].1;
+            x[i] = N::zero();
+            marked[i] = false;
+            for &(row, val) in &col[1..] {
+                x[row] = x[row] - val * l_ki;
+            }
+            diag = diag - l_ki * l_ki;
+            col.push((k, l_ki));
+        }
+        if diag <= N::zero() || diag.is_nan() {
+            return Err(LinalgError::NotPositiveDefinite(k));
+        }
+        l_cols[k].push((k, diag.sqrt()));
+    }
+
+    let nnz = l_cols.iter().map(Vec::len).sum();
+    let mut indptr = Vec::with_capacity(n + 1);
+    let mut indices = Vec::with_capacity(nnz);
+    let mut data = Vec::with_capacity(nnz);
+    indptr.push(Iptr::zero());
+    for col in l_cols {
+        for (row, val) in col {
+            indices.push(I::from_usize(row));
+            data.push(val);
+        }
+        indptr.push(Iptr::from_usize(indices.len()));
+    }
+    Ok(SparseCholesky {
+        l: CsMatI::new_trusted(CSC, (n, n), indptr, indices, data),
+    })
+}
+
+impl<N, I, Iptr> SparseCholesky<N, I, Iptr>
+where
+    N: Float,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    /// The lower triangular factor `L`, stored as CSC
+    pub fn l(&self) -> CsMatViewI<'_, N, I, Iptr> {
+        self.l.view()
+    }
+
+    /// Solve the system `A x = b`
+    ///
+    /// # Panics
+    ///
+    /// If the dimension of `b` does not match the matrix dimension.
+    pub fn solve(&self, b: ArrayView1<N>) -> Array1<N> {
+        assert_eq!(b.len(), self.l.rows(), "Dimension mismatch");
+        let mut x = b.to_owned();
+        // L y = b, the diagonal being first in each column
+        for (j, col) in self.l.outer_iterator().enumerate() {
+            let (&diag, below) = col.data().split_first().unwrap();
+            let x_j = x[j] / diag;
+            x[j] = x_j;
+            for (&row, &val) in col.indices()[1..].iter().zip(below) {
+                x[row.index()] = x[row.index()] - val * x_j;
+            }
+        }
+        // L^T x = y
+        for (j, col) in self.l.outer_iterator().enumerate().rev() {
+            let (&diag, below) = col.data().split_first().unwrap();
+            let dot = col.indices()[1..]
+                .iter()
+                .zip(below)
+                .fold(N::zero(), |acc, (&row, &val)| {
+                    acc + val * x[row.index()]
+                });
+            x[j] = (x[j] - dot) / diag;
+        }
+        x
+    }
+
+    /// The natural logarithm of the determinant of `A`, which is positive
+    pub fn logdet(&self) -> N {
+        let two = N::one() + N::one();
+        self.l
+            .outer_iterator()
+            .fold(N::zero(), |acc, col| acc + two * col.data()[0].ln())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::cholesky;
+    use crate::errors::LinalgError;
+    use crate::sparse::CsMat;
+    use ndarray::{arr1, Array1};
+
+    /// The 2D Laplacian on a grid, which is symmetric positive definite
+    fn laplacian(size: usize) -> CsMat<f64> {
+        let n = size * size;
+        let mut tri = crate::TriMat::new((n, n));
+        for i in 0..size {
+            for j in 0..size {
+                let k = i * size + j;
+                tri.add_triplet(k, k, 4.);
+                if i > 0 {
+                    tri.add_triplet(k, k - size, -1.);
+                    tri.add_triplet(k - size, k, -1.);
+                }
+                if j > 0 {
+                    tri.add_triplet(k, k - 1, -1.);
+                    tri.add_triplet(k - 1, k, -1.);
+                }
+            }
+        }
+        tri.to_csc()
+    }
+
+    #[test]
+    fn cholesky_laplacian() {
+        let a = laplacian(5);
+        let chol = cholesky(a.view()).unwrap();
+        let l = chol.l();
+        assert!(l.is_csc());
+        assert_eq!(l.tril(0).nnz(), l.nnz());
+        let l_lt = &l * &l.transpose_view();
+        let diff = &l_lt - &a;
+        assert!(diff.data().iter().all(|x| x.abs() < 1e-12));
+
+        let x = Array1::from_iter((0..25).map(|i| i as f64 - 10.));
+        let b = &a * &x;
+        let res = chol.solve(b.view());
+        assert!(res.iter().zip(x.iter()).all(|(a, b)| (a - b).abs() < 1e-10));
+
+        // the same from a CSR input
+        let chol_csr = cholesky(a.to_csr().view()).unwrap();
+        assert_eq!(chol_csr.l(), chol.l());
+    }
+
+    #[test]
+    fn cholesky_logdet() {
+        let a = CsMat::new_csc(
+            (3, 3),
+            vec![0, 2, 3, 5],
+            vec![0, 2, 1, 0, 2],
+            vec![4., 2., 9., 2., 5.],
+        );
+        let chol = cholesky(a.view()).unwrap();
+        // det = 9 * (4 * 5 - 2 * 2)
+        assert!((chol.logdet() - 144f64.ln()).abs() < 1e-12);
+        let x = chol.solve(arr1(&[8., 9., 12.]).view());
+        assert!((&x - &arr1(&[1., 1., 2.])).iter().all(|v| v.abs() < 1e-12));
+    }
+
+    #[test]
+    fn cholesky_not_positive_definite() {
+        // indefinite: eigenvalues 3 and -1
+        let a = CsMat::new_csc(
+            (2, 2),
+            vec![0, 2, 4],
+            vec![0, 1, 0, 1],
+            vec![1., 2., 2., 1.],
+        );
+        assert_eq!(
+            cholesky(a.view()).unwrap_err(),
+            LinalgError::NotPositiveDefinite(1)
+        );
+        // negative definite
+        let a = CsMat::new_csc((1, 1), vec![0, 1], vec![0], vec![-1.]);
+        assert_eq!(
+            cholesky(a.view()).unwrap_err(),
+            LinalgError::NotPositiveDefinite(0)
+        );
+        // singular positive semi-definite, with an empty column
+        let a = CsMat::new_csc((2, 2), vec![0, 1, 1], vec![0], vec![1.]);
+        assert_eq!(
+            cholesky(a.view()).unwrap_err(),
+            LinalgError::NotPositiveDefinite(1)
+        );
+        let a = CsMat::<f64>::zero((2, 3));
+        assert_eq!(
+            cholesky(a.view()).unwrap_err(),
+            LinalgError::NonSquareMatrix
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Dimension mismatch")]
+    fn cholesky_solve_fail() {
+        let a = CsMat::<f64>::eye(2);
+        let chol = cholesky(a.view()).unwrap();
+        let _ = chol.solve(arr1(&[1., 2., 3.]).view());
+    }
+}
@@ -0,0 +1,138 @@
+//! Eigenvalue estimates for sparse matrices
+//!
+//! These methods only require sparse matrix / dense vector products, and
+//! are meant to provide cheap estimates of extremal eigenvalues, such as
+//! the spectral radius of a matrix.
+
+use crate::indexing::SpIndex;
+use crate::sparse::linalg::iterative::mat_vec_into;
+use crate::sparse::CsMatViewI;
+use ndarray::{Array1, LinalgScalar};
+use num_traits::Float;
+
+/// Estimate the dominant eigenvalue of the square matrix `a`, ie its
+/// eigenvalue of largest magnitude, along with an associated eigenvector,
+/// using the power iteration.
+///
+/// Starting from a deterministic vector, each iteration multiplies the
+/// current iterate by `a` and normalizes it in the 2-norm. The eigenvalue is
+/// estimated by the Rayleigh quotient of the iterate, and the iterations
+/// stop when its relative change goes below `tol`, or after `max_iter`
+/// iterations. The returned eigenvector has unit 2-norm.
+///
+/// The convergence speed depends on the ratio between the magnitudes of the
+/// two largest eigenvalues, and the iteration will not converge if the
+/// dominant eigenvalue is not unique, eg when `-λ` and `λ` are both
+/// eigenvalues.
+///
+/// # Panics
+///
+/// If `a` is not square.
+///
+/// # Example
+///
+/// ```rust
+/// use sprs::CsMat;
+/// // | 2 1 |
+/// // | 1 2 |
+/// let a = CsMat::new((2, 2), vec![0, 2, 4], vec![0, 1, 0, 1],
+///                    vec![2f64, 1., 1., 2.]);
+/// let (lambda, v) = sprs::linalg::power_iteration(a.view(), 100, 1e-12);
+/// assert!((lambda - 3.).abs() < 1e-10);
+/// assert!((v[0] - v[1]).abs() < 1e-5);
+/// ```
+pub fn power_iteration<N, I, Iptr>(
+    a: CsMatViewI<N, I, Iptr>,
+    max_iter: usize,
+    tol: N,
+) -> (N, Array1<N>)
+where
+    N: Float + LinalgScalar + crate::MulAcc,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    let n = a.rows();
+    if a.cols() != n {
+        panic!("Non square matrix passed to solver");
+    }
+    if n == 0 {
+        return (N::zero(), Array1::zeros(0));
+    }
+    // A non-constant seed, to avoid starting orthogonal to the dominant
+    // eigenvector of structured matrices such as graph laplacians.
+    let mut v =
+        Array1::from_iter((0..n).map(|i| N::from(i + 1).unwrap().sin()));
+    let norm = v.dot(&v).sqrt();
+    v.mapv_inplace(|x| x / norm);
+    let mut w = Array1::zeros(n);
+    let mut lambda = N::zero();
+    for _ in 0..max_iter {
+        mat_vec_into(a.view(), v.view(), w.view_mut());
+        let new_lambda = v.dot(&w);
+        let norm = w.dot(&w).sqrt();
+        if norm == N::zero() {
+            // v lies in the kernel of a
+            return (N::zero(), v);
+        }
+        std::mem::swap(&mut v, &mut w);
+        v.mapv_inplace(|x| x / norm);
+        let converged = (new_lambda - lambda).abs() <= tol * new_lambda.abs();
+        lambda = new_lambda;
+        if converged {
+            break;
+        }
+    }
+    (lambda, v)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sparse::CsMat;
+    use ndarray::{arr1, Array1};
+
+    #[test]
+    fn power_iteration_diag() {
+        let a: CsMat<f64> = CsMat::new(
+            (3, 3),
+            vec![0, 1, 2, 3],
+            vec![0, 1, 2],
+            vec![1., -4., 2.],
+        );
+        let (lambda, v) = super::power_iteration(a.view(), 1000, 1e-14);
+        assert!((lambda + 4.).abs() < 1e-10);
+        assert!((v[1].abs() - 1.).abs() < 1e-10);
+        assert!(v[0].abs() < 1e-6 && v[2].abs() < 1e-6);
+    }
+
+    #[test]
+    fn power_iteration_laplacian() {
+        // 1D laplacian, whose largest eigenvalue is 2 + 2 cos(pi / (n + 1))
+        let n = 10;
+        let mut tri = crate::TriMat::new((n, n));
+        for i in 0..n {
+            tri.add_triplet(i, i, 2.);
+            if i > 0 {
+                tri.add_triplet(i, i - 1, -1.);
+                tri.add_triplet(i - 1, i, -1.);
+            }
+        }
+        let a: CsMat<f64> = tri.to_csr();
+        let expected = 2. + 2. * (std::f64::consts::PI / (n + 1) as f64).cos();
+        for a in &[a.view(), a.to_csc().view()] {
+            let (lambda, v) = super::power_iteration(a.view(), 1000, 1e-14);
+            assert!((lambda - expected).abs() < 1e-8);
+            let residual: Array1<f64> = &(a * &v) - &(&v * lambda);
+            assert!(residual.iter().all(|r| r.abs() < 1e-3));
+        }
+    }
+
+    #[test]
+    fn power_iteration_kernel() {
+        let a = CsMat::<f64>::zero((2, 2));
+        let (lambda, v) = super::power_iteration(a.view(), 10, 1e-10);
+        assert_eq!(lambda, 0.);
+        assert_eq!(v.len(), 2);
+        let a = CsMat::<f64>::zero((0, 0));
+        assert_eq!(super::power_iteration(a.view(), 10, 1e-10).1, arr1(&[]));
+    }
+}
@@ -0,0 +1,146 @@
+//! Equilibration of sparse matrices
+//!
+//! Scaling the rows and columns of a badly scaled matrix before solving a
+//! linear system can substantially improve its conditioning. If
+//! `A_s = diag(r) A diag(c)`, the solution of `A x = b` is recovered from the
+//! solution of `A_s y = diag(r) b` as `x = diag(c) y`.
+
+use crate::indexing::SpIndex;
+use crate::sparse::{CsMatI, CsMatViewI};
+use num_traits::Float;
+use std::ops::MulAssign;
+
+/// Equilibrate `a` using the iterative scaling of Ruiz, bringing the
+/// infinity norms of all its rows and columns towards one.
+///
+/// At each iteration, every row and every column is divided by the square
+/// root of its current infinity norm. The iterations stop after `max_iter`
+/// iterations, or when the infinity norm of every non-empty row and column
+/// is within `tol` of one. Empty rows and columns are left unscaled.
+///
+/// Returns the row scaling `r`, the column scaling `c`, and the scaled
+/// matrix `diag(r) * a * diag(c)`, which has the storage of `a`.
+///
+/// # Example
+///
+/// ```rust
+/// use sprs::CsMat;
+/// use sprs::linalg::ruiz_equilibrate;
+/// // | 1e4  1e-2 |
+/// // | 1e2  0    |
+/// let a = CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 0],
+///                    vec![1e4f64, 1e-2, 1e2]);
+/// let (r, c, scaled) = ruiz_equilibrate(a.view(), 100, 1e-8);
+/// assert!(scaled.data().iter().all(|x| (x - 1.).abs() < 1e-8));
+/// assert!((scaled.data()[0] - r[0] * 1e4 * c[0]).abs() < 1e-12);
+/// ```
+pub fn ruiz_equilibrate<N, I, Iptr>(
+    a: CsMatViewI<N, I, Iptr>,
+    max_iter: usize,
+    tol: N,
+) -> (Vec<N>, Vec<N>, CsMatI<N, I, Iptr>)
+where
+    N: Float,
+    for<'r> N: MulAssign<&'r N>,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    let mut row_scaling = vec![N::one(); a.rows()];
+    let mut col_scaling = vec![N::one(); a.cols()];
+    let mut scaled = a.to_owned();
+    let mut row_norms = vec![N::zero(); a.rows()];
+    let mut col_norms = vec![N::zero(); a.cols()];
+    for _ in 0..max_iter {
+        row_norms.iter_mut().for_each(|norm| *norm = N::zero());
+        col_norms.iter_mut().for_each(|norm| *norm = N::zero());
+        for (val, (row, col)) in scaled.iter() {
+            let (row, col) = (row.index(), col.index());
+            row_norms[row] = row_norms[row].max(val.abs());
+            col_norms[col] = col_norms[col].max(val.abs());
+        }
+        let deviation = row_norms
+            .iter()
+            .chain(col_norms.iter())
+            .filter(|&&norm| norm > N::zero())
+            .fold(N::zero(), |dev, &norm| dev.max((N::one() - norm).abs()));
+        if deviation <= tol {
+            break;
+        }
+        let factor = |norm: &mut N| {
+            *norm = if *norm > N::zero() {
+                N::one() / norm.sqrt()
+            } else {
+                N::one()
+            }
+        };
+        row_norms.iter_mut().for_each(factor);
+        col_norms.iter_mut().for_each(factor);
+        scaled.scale_rows(&row_norms);
+        scaled.scale_cols(&col_norms);
+        for (scaling, factor) in row_scaling.iter_mut().zip(&row_norms) {
+            *scaling = *scaling * *factor;
+        }
+        for (scaling, factor) in col_scaling.iter_mut().zip(&col_norms) {
+            *scaling = *scaling * *factor;
+        }
+    }
+    (row_scaling, col_scaling, scaled)
+}
+
+#[cfg(test)]
+mod test {
+    use super::ruiz_equilibrate;
+    use crate::sparse::CsMat;
+
+    #[test]
+    fn ruiz_badly_scaled() {
+        // | 1e6   0    2e3 |
+        // | 0     1e-3 0   |
+        // | 5e-2  4    0   |
+        // | 0     0    7e8 |
+        let a = CsMat::new(
+            (4, 3),
+            vec![0, 2, 3, 5, 6],
+            vec![0, 2, 1, 0, 1, 2],
+            vec![1e6f64, 2e3, 1e-3, 5e-2, 4., 7e8],
+        );
+        for a in &[a.clone(), a.to_csc()] {
+            let (r, c, scaled) = ruiz_equilibrate(a.view(), 100, 1e-6);
+            assert_eq!(scaled.storage(), a.storage());
+            assert_eq!(scaled, a.diagonal_scale(&r, &c));
+            let mut row_norms = [0f64; 4];
+            let mut col_norms = [0f64; 3];
+            for (&val, (row, col)) in scaled.iter() {
+                row_norms[row] = row_norms[row].max(val.abs());
+                col_norms[col] = col_norms[col].max(val.abs());
+            }
+            for norm in row_norms.iter().chain(&col_norms) {
+                assert!((norm - 1.).abs() <= 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn ruiz_early_stop() {
+        // no iterations, or an already equilibrated matrix, leave the
+        // scalings at one
+        let a = CsMat::new(
+            (3, 4),
+            vec![0, 1, 3, 3],
+            vec![0, 1, 2],
+            vec![1f64, -1., 0.5],
+        );
+        let (r, c, scaled) = ruiz_equilibrate(a.view(), 0, 0.);
+        assert_eq!((r, c), (vec![1.; 3], vec![1.; 4]));
+        assert_eq!(scaled, a);
+        let eye = CsMat::<f64>::eye(3);
+        let (r, c, scaled) = ruiz_equilibrate(eye.view(), 10, 0.);
+        assert_eq!((r, c), (vec![1.; 3], vec![1.; 3]));
+        assert_eq!(scaled, eye);
+        // empty rows and columns are not scaled
+        let (r, c, _) = ruiz_equilibrate(a.view(), 50, 1e-10);
+        assert_eq!(r[2], 1.);
+        assert_eq!(c[3], 1.);
+        assert!(c[2] > 1.);
+    }
+}
@@ -0,0 +1,200 @@
+//! Incomplete LU factorizations
+//!
+//! Incomplete factorizations approximate the LU factors of a sparse matrix
+//! while restricting the fill-in, which makes them cheap to compute and to
+//! apply. They are mostly useful as preconditioners for iterative solvers.
+
+use crate::errors::{LinalgError, SingularMatrixInfo};
+use crate::indexing::SpIndex;
+use crate::sparse::{CsMatI, CsMatViewI};
+use crate::CSR;
+use num_traits::Num;
+
+/// Compute the incomplete LU factorization without fill-in, ILU(0), of the
+/// square CSR matrix `a`.
+///
+/// The returned factors `(L, U)` have the sparsity pattern of `a`: `L` is
+/// unit lower triangular, with its unit diagonal explicitly stored, and `U`
+/// is upper triangular. Their product matches `a` on the pattern of `a`.
+/// Both factors are CSR matrices which can be used with
+/// [`lsolve_csr_dense_rhs`](crate::linalg::trisolve::lsolve_csr_dense_rhs)
+/// and [`usolve_csr_dense_rhs`](crate::linalg::trisolve::usolve_csr_dense_rhs).
+///
+/// The inner indices of `a` are expected to be sorted.
+///
+/// Returns `LinalgError::NonSquareMatrix` if `a` is not square, or
+/// `LinalgError::SingularMatrix` if a zero pivot is encountered, which
+/// happens in particular when a diagonal element is not part of the
+/// pattern of `a`. See the [`linalg`](crate::linalg) module for the
+/// choice of error type.
+///
+/// # Panics
+///
+/// If `a` is not a CSR matrix.
+///
+/// # Example
+///
+/// ```rust
+/// use sprs::CsMat;
+/// use sprs::linalg::ilu0;
+/// // | 4 1 . |
+/// // | 1 4 1 |
+/// // | . 1 4 |
+/// let a = CsMat::new(
+///     (3, 3),
+///     vec![0, 2, 5, 7],
+///     vec![0, 1, 0, 1, 2, 1, 2],
+///     vec![4., 1., 1., 4., 1., 1., 4.],
+/// );
+/// let (l, u) = ilu0(a.view()).unwrap();
+/// // a tridiagonal matrix has no fill-in, the factorization is exact
+/// assert_eq!(&l * &u, a);
+/// ```
+#[allow(clippy::type_complexity)]
+pub fn ilu0<N, I, Iptr>(
+    a: CsMatViewI<N, I, Iptr>,
+) -> Result<(CsMatI<N, I, Iptr>, CsMatI<N, I, Iptr>), LinalgError>
+where
+    N: Clone + Num,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    if !a.is_csr() {
+        panic!("Storage mismatch");
+    }
+    let n = a.rows();
+    if a.cols() != n {
+        return Err(LinalgError::NonSquareMatrix);
+    }
+    let indptr = a.indptr();
+    let indices = a.indices();
+    let mut vals = a.data().to_vec();
+    // position of the diagonal element of each processed row
+    let mut diag_pos = vec![0; n];
+    // position of each column in the current row, if present
+    let mut col_pos = vec![None; n];
+
+    for i in 0..n {
+        let row = indptr.outer_inds_sz(i);
+        for p in row.clone() {
+            col_pos[indices[p].index()] = Some(p);
+        }
+        for p in row.clone() {
+            let k = indices[p].index();
+            if k >= i {
+                break;
+            }
+            let factor = vals[p].clone() / vals[diag_pos[k]].clone();
+            vals[p] = factor.clone();
+            // the entries of row k past its diagonal belong to U
+            for q in diag_pos[k] + 1..indptr.outer_inds_sz(k).end {
+                if let Some(pos) = col_pos[indices[q].index()] {
+                    let update = factor.clone() * vals[q].clone();
+                    vals[pos] = vals[pos].clone() - update;
+                }
+            }
+        }
+        match col_pos[i] {
+            Some(pos) if !vals[pos].is_zero() => diag_pos[i] = pos,
+            _ => {
+                return Err(LinalgError::SingularMatrix(SingularMatrixInfo {
+                    index: i,
+                    reason: "zero pivot",
+                }))
+            }
+        }
+        for p in row {
+            col_pos[indices[p].index()] = None;
+        }
+    }
+
+    let mut l_indptr = Vec::with_capacity(n + 1);
+    let mut l_indices = Vec::new();
+    let mut l_data = Vec::new();
+    let mut u_indptr = Vec::with_capacity(n + 1);
+    let mut u_indices = Vec::new();
+    let mut u_data = Vec::new();
+    l_indptr.push(Iptr::zero());
+    u_indptr.push(Iptr::zero());
+    for (i, &diag) in diag_pos.iter().enumerate() {
+        let row = indptr.outer_inds_sz(i);
+        l_indices.extend_from_slice(&indices[row.start..diag]);
+        l_data.extend_from_slice(&vals[row.start..diag]);
+        l_indices.push(I::from_usize(i));
+        l_data.push(N::one());
+        u_indices.extend_from_slice(&indices[diag..row.end]);
+        u_data.extend_from_slice(&vals[diag..row.end]);
+        l_indptr.push(Iptr::from_usize(l_indices.len()));
+        u_indptr.push(Iptr::from_usize(u_indices.len()));
+    }
+    let l = CsMatI::new_trusted(CSR, (n, n), l_indptr, l_indices, l_data);
+    let u = CsMatI::new_trusted(CSR, (n, n), u_indptr, u_indices, u_data);
+    Ok((l, u))
+}
+
+#[cfg(test)]
+mod test {
+    use super::ilu0;
+    use crate::errors::LinalgError;
+    use crate::linalg::trisolve;
+    use crate::sparse::CsMat;
+    use ndarray::{arr1, Array1};
+
+    #[test]
+    fn ilu0_no_fill() {
+        // | 2 . 1 . |
+        // | 1 3 . . |
+        // | . . 4 1 |
+        // | . 2 . 5 |
+        let a = CsMat::new(
+            (4, 4),
+            vec![0, 2, 4, 6, 8],
+            vec![0, 2, 0, 1, 2, 3, 1, 3],
+            vec![2., 1., 1., 3., 4., 1., 2., 5.],
+        );
+        let (l, u) = ilu0(a.view()).unwrap();
+        assert!(l.is_csr() && u.is_csr());
+        assert_eq!(l.indptr().raw_storage(), &[0, 1, 3, 4, 6]);
+        assert_eq!(u.indptr().raw_storage(), &[0, 2, 3, 5, 6]);
+        assert_eq!(l.indices(), &[0, 0, 1, 2, 1, 3]);
+        assert_eq!(u.indices(), &[0, 2, 1, 2, 3, 3]);
+        assert_eq!(l.data(), &[1., 0.5, 1., 1., 2. / 3., 1.]);
+        assert_eq!(u.data(), &[2., 1., 3., 4., 1., 5.]);
+
+        // The product has fill-in at (1, 2) but matches on the pattern of a
+        let prod = &l * &u;
+        for (val, (row, col)) in a.iter() {
+            assert_eq!(prod.get(row, col), Some(val));
+        }
+        assert_eq!(prod.get(1, 2), Some(&0.5));
+
+        // Solving with the factors
+        let b: Array1<f64> = arr1(&[3., 4., 5., 7.]);
+        let mut x = b.clone();
+        trisolve::lsolve_csr_dense_rhs(l.view(), x.view_mut()).unwrap();
+        trisolve::usolve_csr_dense_rhs(u.view(), x.view_mut()).unwrap();
+        let residual = &prod * &x - &b;
+        assert!(residual.iter().all(|r| r.abs() < 1e-12));
+    }
+
+    #[test]
+    fn ilu0_zero_pivot() {
+        // missing diagonal element
+        let a =
+            CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 0], vec![1., 2., 3.]);
+        match ilu0(a.view()) {
+            Err(LinalgError::SingularMatrix(info)) => assert_eq!(info.index, 1),
+            _ => panic!("expected a singular matrix error"),
+        }
+        // diagonal element cancelled by the elimination
+        let a = CsMat::new(
+            (2, 2),
+            vec![0, 2, 4],
+            vec![0, 1, 0, 1],
+            vec![1., 2., 3., 6.],
+        );
+        assert!(ilu0(a.view()).is_err());
+        let a = CsMat::new((1, 2), vec![0, 1], vec![0], vec![1.]);
+        assert_eq!(ilu0(a.view()), Err(LinalgError::NonSquareMatrix));
+    }
+}
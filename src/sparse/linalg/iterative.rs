@@ -0,0 +1,956 @@
+//! Iterative solvers for sparse linear systems
+//!
+//! These solvers only require sparse matrix / dense vector products, and
+//! are thus well suited to large systems where a direct factorization
+//! would produce too much fill-in.
+
+use crate::errors::{BreakdownInfo, LinalgError, SingularMatrixInfo};
+use crate::indexing::SpIndex;
+use crate::sparse::prod;
+use crate::sparse::CsMatViewI;
+use crate::{CSC, CSR};
+use ndarray::{Array1, ArrayView1, ArrayViewMut1, LinalgScalar};
+use num_traits::Float;
+
+/// Outcome of an iterative solve
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CgResult<N> {
+    /// The number of iterations that were performed
+    pub iterations: usize,
+    /// The norm of the residual `b - A x` of the returned solution, relative
+    /// to the norm of `b`
+    pub residual_norm: N,
+    /// Whether the relative residual went below the requested tolerance
+    pub converged: bool,
+}
+
+/// Outcome of a least squares solve with `lsqr`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LsqrResult<N> {
+    /// The computed solution
+    pub x: Array1<N>,
+    /// The number of iterations that were performed
+    pub iterations: usize,
+    /// The norm of the residual `b - A x`, relative to the norm of `b`. For
+    /// an overdetermined system this does not vanish at the solution.
+    pub residual_norm: N,
+    /// The norm of `A^T (b - A x)`, the residual of the normal equations,
+    /// which vanishes at a least squares solution
+    pub normal_residual_norm: N,
+    /// Whether one of the stopping criteria was met
+    pub converged: bool,
+}
+
+/// Compute `out = mat * x`, for any storage of `mat`
+pub(crate) fn mat_vec_into<N, I, Iptr>(
+    mat: CsMatViewI<N, I, Iptr>,
+    x: ArrayView1<N>,
+    mut out: ArrayViewMut1<N>,
+) where
+    N: LinalgScalar + crate::MulAcc,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    out.fill(N::zero());
+    match mat.storage() {
+        CSR => prod::mul_acc_mat_vec_csr(mat, x, out),
+        CSC => prod::mul_acc_mat_vec_csc(mat, x, out),
+    }
+}
+
+fn l2_norm<N: Float + LinalgScalar>(x: &ArrayView1<N>) -> N {
+    x.dot(x).sqrt()
+}
+
+/// Solve the system `A x = b` using the conjugate gradient method, where
+/// `A` is a symmetric positive definite matrix.
+///
+/// `x` should contain an initial guess for the solution, and will contain
+/// the computed solution on return. The iterations stop when the norm of the
+/// residual relative to the norm of `b` goes below `tol`, or after
+/// `max_iter` iterations.
+///
+/// An error is returned if the method breaks down, which happens when
+/// `p^T A p` is not positive for a search direction `p`, proving that `a`
+/// is not positive definite. In that case, `x` contains the last iterate
+/// before the breakdown.
+///
+/// # Panics
+///
+/// - if `a` is not square
+/// - if the dimensions of `b` or `x` don't match those of `a`
+///
+/// # Example
+///
+/// ```rust
+/// use ndarray::arr1;
+/// use sprs::CsMat;
+/// // | 4 1 |
+/// // | 1 3 |
+/// let a = CsMat::new((2, 2), vec![0, 2, 4], vec![0, 1, 0, 1],
+///                    vec![4f64, 1., 1., 3.]);
+/// let b = arr1(&[1., 2.]);
+/// let mut x = arr1(&[0., 0.]);
+/// let res = sprs::linalg::cg(a.view(), b.view(), x.view_mut(), 10, 1e-10)
+///     .unwrap();
+/// assert!(res.converged);
+/// assert!((x[0] - 1. / 11.).abs() < 1e-10);
+/// assert!((x[1] - 7. / 11.).abs() < 1e-10);
+/// ```
+pub fn cg<N, I, Iptr>(
+    a: CsMatViewI<N, I, Iptr>,
+    b: ArrayView1<N>,
+    x: ArrayViewMut1<N>,
+    max_iter: usize,
+    tol: N,
+) -> Result<CgResult<N>, LinalgError>
+where
+    N: Float + LinalgScalar + crate::MulAcc,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    pcg(a, b, x, max_iter, tol, |r| r.to_owned())
+}
+
+/// Solve the system `A x = b` using the preconditioned conjugate gradient
+/// method, where `A` is a symmetric positive definite matrix.
+///
+/// `precond` should compute the application of the preconditioner `M^-1`
+/// to a vector, where `M` is a symmetric positive definite approximation
+/// of `A`. See `cg` for the meaning of the other arguments, and for the
+/// breakdown error.
+///
+/// # Panics
+///
+/// - if `a` is not square
+/// - if the dimensions of `b` or `x` don't match those of `a`
+/// - if `precond` does not return a vector of the same dimension as `b`
+pub fn pcg<N, I, Iptr, P>(
+    a: CsMatViewI<N, I, Iptr>,
+    b: ArrayView1<N>,
+    mut x: ArrayViewMut1<N>,
+    max_iter: usize,
+    tol: N,
+    mut precond: P,
+) -> Result<CgResult<N>, LinalgError>
+where
+    N: Float + LinalgScalar + crate::MulAcc,
+    I: SpIndex,
+    Iptr: SpIndex,
+    P: FnMut(&ArrayView1<N>) -> Array1<N>,
+{
+    let n = a.rows();
+    if a.cols() != n {
+        panic!("Non square matrix passed to solver");
+    }
+    if b.len() != n || x.len() != n {
+        panic!("Dimension mismatch");
+    }
+
+    let b_norm = l2_norm(&b);
+    if b_norm == N::zero() {
+        x.fill(N::zero());
+        return Ok(CgResult {
+            iterations: 0,
+            residual_norm: N::zero(),
+            converged: true,
+        });
+    }
+
+    // r = b - A x
+    let mut ap = Array1::zeros(n);
+    mat_vec_into(a.view(), x.view(), ap.view_mut());
+    let mut r = &b - &ap;
+    let mut z = precond(&r.view());
+    assert_eq!(z.len(), n, "Dimension mismatch");
+    let mut p = z.clone();
+    let mut rz = r.dot(&z);
+
+    let mut iterations = 0;
+    let mut residual_norm = l2_norm(&r.view()) / b_norm;
+    while residual_norm >= tol && iterations < max_iter {
+        mat_vec_into(a.view(), p.view(), ap.view_mut());
+        let p_ap = p.dot(&ap);
+        if p_ap <= N::zero() {
+            return Err(LinalgError::Breakdown(BreakdownInfo {
+                iteration: iterations,
+                reason: "matrix is not positive definite",
+            }));
+        }
+        let alpha = rz / p_ap;
+        x.scaled_add(alpha, &p);
+        r.scaled_add(-alpha, &ap);
+        iterations += 1;
+        residual_norm = l2_norm(&r.view()) / b_norm;
+
+        z = precond(&r.view());
+        assert_eq!(z.len(), n, "Dimension mismatch");
+        let rz_new = r.dot(&z);
+        let beta = rz_new / rz;
+        rz = rz_new;
+        p.zip_mut_with(&z, |p, &z| *p = z + beta * *p);
+    }
+
+    Ok(CgResult {
+        iterations,
+        residual_norm,
+        converged: residual_norm < tol,
+    })
+}
+
+/// Solve the system `A x = b` using the stabilized bi-conjugate gradient
+/// method (BiCGSTAB), which is suitable for general non-symmetric matrices.
+///
+/// `x` should contain an initial guess for the solution, and will contain
+/// the computed solution on return. The iterations stop when the norm of the
+/// residual relative to the norm of `b` goes below `tol`, or after
+/// `max_iter` iterations.
+///
+/// An error is returned if the method breaks down, which happens when
+/// one of its internal scalars `rho` or `omega` vanishes. In that case, `x`
+/// contains the last iterate before the breakdown.
+///
+/// # Panics
+///
+/// - if `a` is not square
+/// - if the dimensions of `b` or `x` don't match those of `a`
+pub fn bicgstab<N, I, Iptr>(
+    a: CsMatViewI<N, I, Iptr>,
+    b: ArrayView1<N>,
+    mut x: ArrayViewMut1<N>,
+    max_iter: usize,
+    tol: N,
+) -> Result<CgResult<N>, LinalgError>
+where
+    N: Float + LinalgScalar + crate::MulAcc,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    let n = a.rows();
+    if a.cols() != n {
+        panic!("Non square matrix passed to solver");
+    }
+    if b.len() != n || x.len() != n {
+        panic!("Dimension mismatch");
+    }
+
+    let b_norm = l2_norm(&b);
+    if b_norm == N::zero() {
+        x.fill(N::zero());
+        return Ok(CgResult {
+            iterations: 0,
+            residual_norm: N::zero(),
+            converged: true,
+        });
+    }
+
+    // r = b - A x
+    let mut v = Array1::zeros(n);
+    mat_vec_into(a.view(), x.view(), v.view_mut());
+    let mut r = &b - &v;
+    let r_hat = r.clone();
+    let r_hat_norm = l2_norm(&r_hat.view());
+    v.fill(N::zero());
+    let mut p = Array1::zeros(n);
+    let mut t = Array1::zeros(n);
+    let (mut rho, mut alpha, mut omega) = (N::one(), N::one(), N::one());
+
+    let breakdown = |iteration, reason| {
+        Err(LinalgError::Breakdown(BreakdownInfo { iteration, reason }))
+    };
+
+    let mut iterations = 0;
+    let mut residual_norm = l2_norm(&r.view()) / b_norm;
+    while residual_norm >= tol && iterations < max_iter {
+        let rho_new = r_hat.dot(&r);
+        let r_norm = residual_norm * b_norm;
+        if rho_new.abs() <= N::epsilon() * r_hat_norm * r_norm {
+            return breakdown(iterations, "rho vanished");
+        }
+        let beta = (rho_new / rho) * (alpha / omega);
+        rho = rho_new;
+        // p = r + beta * (p - omega * v)
+        p.scaled_add(-omega, &v);
+        p.zip_mut_with(&r, |p, &r| *p = r + beta * *p);
+        mat_vec_into(a.view(), p.view(), v.view_mut());
+        let r_hat_v = r_hat.dot(&v);
+        if r_hat_v == N::zero() {
+            return breakdown(iterations, "r_hat.v vanished");
+        }
+        alpha = rho / r_hat_v;
+        // s = r - alpha * v, stored in r
+        r.scaled_add(-alpha, &v);
+        x.scaled_add(alpha, &p);
+        iterations += 1;
+        residual_norm = l2_norm(&r.view()) / b_norm;
+        if residual_norm < tol {
+            break;
+        }
+        mat_vec_into(a.view(), r.view(), t.view_mut());
+        let t_t = t.dot(&t);
+        if t_t == N::zero() {
+            return breakdown(iterations, "omega vanished");
+        }
+        // omega scales like the inverse of A, so it is compared relatively
+        let t_s = t.dot(&r);
+        let s_norm = residual_norm * b_norm;
+        if t_s.abs() <= N::epsilon() * t_t.sqrt() * s_norm {
+            return breakdown(iterations, "omega vanished");
+        }
+        omega = t_s / t_t;
+        x.scaled_add(omega, &r);
+        r.scaled_add(-omega, &t);
+        residual_norm = l2_norm(&r.view()) / b_norm;
+    }
+
+    Ok(CgResult {
+        iterations,
+        residual_norm,
+        converged: residual_norm < tol,
+    })
+}
+
+/// Solve the least squares problem `min ||b - A x||` using the LSQR
+/// algorithm of Paige and Saunders, for a matrix `A` of any shape.
+///
+/// LSQR is mathematically equivalent to applying the conjugate gradient
+/// method to the normal equations `A^T A x = A^T b`, but it only requires
+/// products by `A` and `A^T` and never forms `A^T A`, which makes it
+/// numerically more reliable. Starting from `x = 0`, it converges to the
+/// least squares solution of minimum norm, which makes it usable for
+/// overdetermined (`m > n`), underdetermined and rank deficient systems.
+///
+/// The iterations stop after `max_iter` iterations, or when either
+/// - the residual norm relative to the norm of `b` goes below `tol`, which
+///   happens for compatible systems;
+/// - the norm of `A^T r` goes below `tol * ||A||_F * ||r||`, where `r` is the
+///   residual, which happens at a least squares solution.
+///
+/// # Panics
+///
+/// - if the dimension of `b` does not match the number of rows of `a`
+///
+/// # Example
+///
+/// ```rust
+/// use ndarray::arr1;
+/// use sprs::CsMat;
+/// // fit y = c0 + c1 t through the points (0, 1), (1, 2), (2, 4)
+/// // | 1 0 |
+/// // | 1 1 |
+/// // | 1 2 |
+/// let a = CsMat::new((3, 2), vec![0, 1, 3, 5], vec![0, 0, 1, 0, 1],
+///                    vec![1f64, 1., 1., 1., 2.]);
+/// let b = arr1(&[1., 2., 4.]);
+/// let res = sprs::linalg::lsqr(a.view(), b.view(), 10, 1e-10);
+/// assert!(res.converged);
+/// assert!((res.x[0] - 5. / 6.).abs() < 1e-10);
+/// assert!((res.x[1] - 3. / 2.).abs() < 1e-10);
+/// ```
+pub fn lsqr<N, I, Iptr>(
+    a: CsMatViewI<N, I, Iptr>,
+    b: ArrayView1<N>,
+    max_iter: usize,
+    tol: N,
+) -> LsqrResult<N>
+where
+    N: Float + LinalgScalar + crate::MulAcc,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    let (m, n) = a.shape();
+    if b.len() != m {
+        panic!("Dimension mismatch");
+    }
+    let mut x = Array1::zeros(n);
+    let b_norm = l2_norm(&b);
+    let a_norm = a
+        .data()
+        .iter()
+        .fold(N::zero(), |acc, &val| acc + val * val)
+        .sqrt();
+    let a_t = a.transpose_view();
+
+    // Golub-Kahan bidiagonalization: beta u = b, alpha v = A^T u
+    let mut u = b.to_owned();
+    let mut beta = b_norm;
+    if beta > N::zero() {
+        u.mapv_inplace(|val| val / beta);
+    }
+    let mut v = Array1::zeros(n);
+    mat_vec_into(a_t.view(), u.view(), v.view_mut());
+    let mut alpha = l2_norm(&v.view());
+    if alpha > N::zero() {
+        v.mapv_inplace(|val| val / alpha);
+    }
+    let mut w = v.clone();
+    let mut av = Array1::zeros(m);
+    let mut atu = Array1::zeros(n);
+    let mut phi_bar = beta;
+    let mut rho_bar = alpha;
+
+    // The residual norm is phi_bar, and the norm of A^T r is
+    // phi_bar * alpha * |c|, with c = 1 before the first iteration
+    let relative = |r_norm: N| {
+        if b_norm == N::zero() {
+            N::zero()
+        } else {
+            r_norm / b_norm
+        }
+    };
+    let converged = |r_norm: N, normal_r_norm: N| {
+        relative(r_norm) < tol || normal_r_norm <= tol * a_norm * r_norm
+    };
+    let mut normal_residual_norm = alpha * beta;
+    let mut iterations = 0;
+    while !converged(phi_bar, normal_residual_norm) && iterations < max_iter {
+        // beta u = A v - alpha u
+        mat_vec_into(a.view(), v.view(), av.view_mut());
+        u.zip_mut_with(&av, |u, &av| *u = av - alpha * *u);
+        beta = l2_norm(&u.view());
+        if beta > N::zero() {
+            u.mapv_inplace(|val| val / beta);
+        }
+        // alpha v = A^T u - beta v
+        mat_vec_into(a_t.view(), u.view(), atu.view_mut());
+        v.zip_mut_with(&atu, |v, &atu| *v = atu - beta * *v);
+        alpha = l2_norm(&v.view());
+        if alpha > N::zero() {
+            v.mapv_inplace(|val| val / alpha);
+        }
+
+        // Plane rotation eliminating the subdiagonal beta
+        let rho = rho_bar.hypot(beta);
+        let c = rho_bar / rho;
+        let s = beta / rho;
+        let theta = s * alpha;
+        rho_bar = -c * alpha;
+        let phi = c * phi_bar;
+        phi_bar = s * phi_bar;
+
+        x.scaled_add(phi / rho, &w);
+        w.zip_mut_with(&v, |w, &v| *w = v - (theta / rho) * *w);
+        iterations += 1;
+        normal_residual_norm = phi_bar * alpha * c.abs();
+    }
+
+    LsqrResult {
+        x,
+        iterations,
+        residual_norm: relative(phi_bar),
+        normal_residual_norm,
+        converged: converged(phi_bar, normal_residual_norm),
+    }
+}
+
+/// Solve the system `A x = b` using the successive over-relaxation (SOR)
+/// method, with relaxation parameter `omega`. `omega = 1` gives the
+/// Gauss-Seidel method.
+///
+/// Each iteration sweeps the rows in order, updating each component of `x`
+/// using the already updated previous components:
+/// `x_i <- (1 - omega) x_i + omega (b_i - sum_{j != i} a_ij x_j) / a_ii`.
+/// The method converges for symmetric positive definite matrices when
+/// `0 < omega < 2`, and for strictly diagonally dominant matrices when
+/// `omega = 1`. It is also commonly used as a smoother, with a small number
+/// of iterations.
+///
+/// `x` should contain an initial guess for the solution, and will contain
+/// the computed solution on return. The iterations stop when the norm of the
+/// residual relative to the norm of `b` goes below `tol`, or after
+/// `max_iter` iterations. A CSC matrix is converted to CSR once before
+/// iterating, as the sweeps need to access the rows.
+///
+/// Returns an error if a diagonal element of `a` is zero or not stored.
+///
+/// # Panics
+///
+/// - if `a` is not square
+/// - if the dimensions of `b` or `x` don't match those of `a`
+///
+/// # Example
+///
+/// ```rust
+/// use ndarray::arr1;
+/// use sprs::CsMat;
+/// // | 4 1 |
+/// // | 1 3 |
+/// let a = CsMat::new((2, 2), vec![0, 2, 4], vec![0, 1, 0, 1],
+///                    vec![4f64, 1., 1., 3.]);
+/// let b = arr1(&[1., 2.]);
+/// let mut x = arr1(&[0., 0.]);
+/// let res = sprs::linalg::sor(a.view(), b.view(), x.view_mut(), 1.1, 100,
+///                             1e-10).unwrap();
+/// assert!(res.converged);
+/// assert!((x[0] - 1. / 11.).abs() < 1e-9);
+/// assert!((x[1] - 7. / 11.).abs() < 1e-9);
+/// ```
+pub fn sor<N, I, Iptr>(
+    a: CsMatViewI<N, I, Iptr>,
+    b: ArrayView1<N>,
+    mut x: ArrayViewMut1<N>,
+    omega: N,
+    max_iter: usize,
+    tol: N,
+) -> Result<CgResult<N>, LinalgError>
+where
+    N: Float + LinalgScalar + crate::MulAcc + Default,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    let n = a.rows();
+    if a.cols() != n {
+        panic!("Non square matrix passed to solver");
+    }
+    if b.len() != n || x.len() != n {
+        panic!("Dimension mismatch");
+    }
+    let csr;
+    let a = if a.is_csr() {
+        a
+    } else {
+        csr = a.to_csr();
+        csr.view()
+    };
+    let mut inv_diag = Vec::with_capacity(n);
+    for (index, diag) in a.diag_iter().enumerate() {
+        match diag {
+            Some(&d) if d != N::zero() => inv_diag.push(d.recip()),
+            _ => {
+                return Err(LinalgError::SingularMatrix(SingularMatrixInfo {
+                    index,
+                    reason: "diagonal element is 0",
+                }))
+            }
+        }
+    }
+
+    let b_norm = l2_norm(&b);
+    if b_norm == N::zero() {
+        x.fill(N::zero());
+        return Ok(CgResult {
+            iterations: 0,
+            residual_norm: N::zero(),
+            converged: true,
+        });
+    }
+    let mut r = Array1::zeros(n);
+    let residual_norm = |x: &ArrayViewMut1<N>, r: &mut Array1<N>| {
+        mat_vec_into(a.view(), x.view(), r.view_mut());
+        r.zip_mut_with(&b, |r, &b| *r = b - *r);
+        l2_norm(&r.view()) / b_norm
+    };
+
+    let mut iterations = 0;
+    let mut res_norm = residual_norm(&x, &mut r);
+    while res_norm >= tol && iterations < max_iter {
+        for (i, row) in a.outer_iterator().enumerate() {
+            let mut sigma = N::zero();
+            for (j, &val) in row.iter() {
+                if j != i {
+                    sigma = sigma + val * x[j];
+                }
+            }
+            let gs = (b[i] - sigma) * inv_diag[i];
+            x[i] = (N::one() - omega) * x[i] + omega * gs;
+        }
+        iterations += 1;
+        res_norm = residual_norm(&x, &mut r);
+    }
+
+    Ok(CgResult {
+        iterations,
+        residual_norm: res_norm,
+        converged: res_norm < tol,
+    })
+}
+
+/// A diagonal preconditioner, applying the inverse of a diagonal matrix
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagPrecond<N> {
+    inv_diag: Array1<N>,
+}
+
+impl<N: LinalgScalar> DiagPrecond<N> {
+    /// The inverse of the diagonal entries
+    pub fn inv_diag(&self) -> ArrayView1<'_, N> {
+        self.inv_diag.view()
+    }
+
+    /// Apply the preconditioner to the vector `r`
+    ///
+    /// # Panics
+    ///
+    /// If the dimension of `r` does not match the preconditioner's.
+    pub fn apply(&self, r: ArrayView1<N>) -> Array1<N> {
+        assert_eq!(r.len(), self.inv_diag.len(), "Dimension mismatch");
+        &r * &self.inv_diag
+    }
+}
+
+/// Build the Jacobi preconditioner of the square matrix `a`, which applies
+/// the inverse of the diagonal of `a`. The inverse of the diagonal is
+/// computed once, so that applying the preconditioner only requires an
+/// element-wise product.
+///
+/// Returns `LinalgError::NonSquareMatrix` if `a` is not square, or
+/// `LinalgError::SingularMatrix` if one of its diagonal entries is zero or
+/// not stored, as a zero diagonal cannot be inverted.
+///
+/// # Example
+///
+/// ```rust
+/// use ndarray::{arr1, Array1};
+/// use sprs::CsMat;
+/// use sprs::linalg::jacobi_preconditioner;
+/// let a = CsMat::new((2, 2), vec![0, 2, 4], vec![0, 1, 0, 1],
+///                    vec![4f64, 1., 1., 2.]);
+/// let precond = jacobi_preconditioner(a.view()).unwrap();
+/// assert_eq!(precond.apply(arr1(&[1., 1.]).view()), arr1(&[0.25, 0.5]));
+/// let b = arr1(&[1., 2.]);
+/// let mut x = Array1::zeros(2);
+/// let res = sprs::linalg::pcg(a.view(), b.view(), x.view_mut(), 10, 1e-10,
+///                             |r| precond.apply(r.view())).unwrap();
+/// assert!(res.converged);
+/// ```
+pub fn jacobi_preconditioner<N, I, Iptr>(
+    a: CsMatViewI<N, I, Iptr>,
+) -> Result<DiagPrecond<N>, LinalgError>
+where
+    N: Float + LinalgScalar,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    if a.rows() != a.cols() {
+        return Err(LinalgError::NonSquareMatrix);
+    }
+    let mut inv_diag = Array1::zeros(a.rows());
+    for (index, (inv, diag)) in
+        inv_diag.iter_mut().zip(a.diag_iter()).enumerate()
+    {
+        match diag {
+            Some(&d) if d != N::zero() => *inv = d.recip(),
+            _ => {
+                return Err(LinalgError::SingularMatrix(SingularMatrixInfo {
+                    index,
+                    reason: "diagonal element is 0",
+                }))
+            }
+        }
+    }
+    Ok(DiagPrecond { inv_diag })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sparse::CsMat;
+    use ndarray::{arr1, Array1};
+
+    /// 1D Laplacian, which is symmetric positive definite
+    fn laplacian_1d(n: usize) -> CsMat<f64> {
+        let mut tri = crate::TriMat::new((n, n));
+        for i in 0..n {
+            tri.add_triplet(i, i, 2.);
+            if i > 0 {
+                tri.add_triplet(i, i - 1, -1.);
+            }
+            if i + 1 < n {
+                tri.add_triplet(i, i + 1, -1.);
+            }
+        }
+        tri.to_csr()
+    }
+
+    #[test]
+    fn cg_laplacian() {
+        let a = laplacian_1d(10);
+        let expected = Array1::from_iter((0..10).map(|i| i as f64));
+        let b = &a * &expected;
+        for a in &[a.view(), a.to_csc().view()] {
+            let mut x = Array1::zeros(10);
+            let res = super::cg(a.view(), b.view(), x.view_mut(), 100, 1e-12)
+                .unwrap();
+            assert!(res.converged);
+            // CG converges in at most n iterations in exact arithmetic
+            assert!(res.iterations <= 11);
+            assert!(res.residual_norm < 1e-12);
+            for (x, e) in x.iter().zip(expected.iter()) {
+                assert!((x - e).abs() < 1e-8);
+            }
+        }
+    }
+
+    #[test]
+    fn pcg_jacobi() {
+        let mut a = laplacian_1d(10);
+        // make the diagonal non-uniform so the preconditioner matters
+        a.scale_rows(&[1., 2., 3., 4., 5., 6., 7., 8., 9., 10.]);
+        a.scale_cols(&[1., 2., 3., 4., 5., 6., 7., 8., 9., 10.]);
+        let diag = Array1::from_iter(a.diag_iter().map(|d| *d.unwrap()));
+        let expected = Array1::from_iter((0..10).map(|i| 1. / (i + 1) as f64));
+        let b = &a * &expected;
+        let mut x = Array1::zeros(10);
+        let res =
+            super::pcg(a.view(), b.view(), x.view_mut(), 100, 1e-12, |r| {
+                r / &diag
+            })
+            .unwrap();
+        assert!(res.converged);
+        for (x, e) in x.iter().zip(expected.iter()) {
+            assert!((x - e).abs() < 1e-8);
+        }
+    }
+
+    #[test]
+    fn sor_laplacian() {
+        let a = laplacian_1d(10);
+        let expected = Array1::from_iter((0..10).map(|i| i as f64));
+        let b = &a * &expected;
+        let mut gs_iterations = 0;
+        for &omega in &[1., 1.5] {
+            for a in &[a.view(), a.to_csc().view()] {
+                let mut x = Array1::zeros(10);
+                let res = super::sor(
+                    a.view(),
+                    b.view(),
+                    x.view_mut(),
+                    omega,
+                    1000,
+                    1e-10,
+                )
+                .unwrap();
+                assert!(res.converged);
+                assert!(res.residual_norm < 1e-10);
+                for (x, e) in x.iter().zip(expected.iter()) {
+                    assert!((x - e).abs() < 1e-7);
+                }
+                if omega == 1. {
+                    gs_iterations = res.iterations;
+                } else {
+                    // over-relaxation accelerates the convergence here
+                    assert!(res.iterations < gs_iterations);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sor_max_iter() {
+        let a = laplacian_1d(10);
+        let b = Array1::from_elem(10, 1.);
+        let mut x = Array1::zeros(10);
+        let res =
+            super::sor(a.view(), b.view(), x.view_mut(), 1., 3, 1e-10).unwrap();
+        assert_eq!(res.iterations, 3);
+        assert!(!res.converged);
+    }
+
+    #[test]
+    fn sor_zero_diagonal() {
+        use crate::errors::LinalgError;
+        let a = CsMat::new((2, 2), vec![0, 1, 2], vec![1, 0], vec![1., 1.]);
+        let b = arr1(&[1., 1.]);
+        let mut x = Array1::zeros(2);
+        match super::sor(a.view(), b.view(), x.view_mut(), 1., 10, 1e-10) {
+            Err(LinalgError::SingularMatrix(info)) => assert_eq!(info.index, 0),
+            _ => panic!("expected a singular matrix error"),
+        }
+    }
+
+    #[test]
+    fn jacobi_preconditioner() {
+        let mut a = laplacian_1d(4);
+        a.scale_rows(&[1., 2., 4., 8.]);
+        let precond = super::jacobi_preconditioner(a.view()).unwrap();
+        assert_eq!(precond.inv_diag(), arr1(&[0.5, 0.25, 0.125, 0.0625]));
+        let r = arr1(&[1., 2., 3., 4.]);
+        assert_eq!(precond.apply(r.view()), arr1(&[0.5, 0.5, 0.375, 0.25]));
+
+        // missing diagonal entry
+        let a = CsMat::new((2, 2), vec![0, 1, 2], vec![0, 0], vec![1., 2.]);
+        assert!(super::jacobi_preconditioner(a.view()).is_err());
+        // explicit zero on the diagonal
+        let a = CsMat::new((2, 2), vec![0, 1, 2], vec![0, 1], vec![1., 0.]);
+        assert!(super::jacobi_preconditioner(a.view()).is_err());
+    }
+
+    #[test]
+    fn cg_max_iter() {
+        let a = laplacian_1d(10);
+        let b = Array1::from_elem(10, 1.);
+        let mut x = Array1::zeros(10);
+        let res =
+            super::cg(a.view(), b.view(), x.view_mut(), 2, 1e-12).unwrap();
+        assert!(!res.converged);
+        assert_eq!(res.iterations, 2);
+    }
+
+    #[test]
+    fn cg_zero_rhs() {
+        let a = laplacian_1d(3);
+        let b = Array1::zeros(3);
+        let mut x = arr1(&[1., 2., 3.]);
+        let res =
+            super::cg(a.view(), b.view(), x.view_mut(), 10, 1e-12).unwrap();
+        assert!(res.converged);
+        assert_eq!(x, arr1(&[0., 0., 0.]));
+    }
+
+    #[test]
+    fn cg_not_positive_definite() {
+        use crate::errors::LinalgError;
+        // symmetric indefinite, eigenvalues 3 and -1
+        let a = CsMat::new(
+            (2, 2),
+            vec![0, 2, 4],
+            vec![0, 1, 0, 1],
+            vec![1., 2., 2., 1.],
+        );
+        // p^T A p = -2 for the first search direction p = b
+        let b = arr1(&[1., -1.]);
+        let mut x = Array1::zeros(2);
+        match super::cg(a.view(), b.view(), x.view_mut(), 10, 1e-12) {
+            Err(LinalgError::Breakdown(info)) => assert_eq!(info.iteration, 0),
+            _ => panic!("expected a breakdown error"),
+        }
+        assert_eq!(x, arr1(&[0., 0.]));
+    }
+
+    #[test]
+    fn bicgstab_non_symmetric() {
+        // | 4 1 0 0 |
+        // | 2 5 1 0 |
+        // | 0 1 6 2 |
+        // | 1 0 3 7 |
+        let a = CsMat::new(
+            (4, 4),
+            vec![0, 2, 5, 8, 11],
+            vec![0, 1, 0, 1, 2, 1, 2, 3, 0, 2, 3],
+            vec![4f64, 1., 2., 5., 1., 1., 6., 2., 1., 3., 7.],
+        );
+        let expected = arr1(&[1., -2., 3., -4.]);
+        let b = &a * &expected;
+        for a in &[a.view(), a.to_csc().view()] {
+            let mut x = Array1::zeros(4);
+            let res =
+                super::bicgstab(a.view(), b.view(), x.view_mut(), 100, 1e-12)
+                    .unwrap();
+            assert!(res.converged);
+            assert!(res.residual_norm < 1e-12);
+            let residual = &b - &(a * &x);
+            assert!(residual.dot(&residual).sqrt() < 1e-10);
+            for (x, e) in x.iter().zip(expected.iter()) {
+                assert!((x - e).abs() < 1e-8);
+            }
+        }
+    }
+
+    #[test]
+    fn bicgstab_large_entries() {
+        // omega is about 1e-20 for this well conditioned system, which
+        // should not be mistaken for a breakdown
+        let a = CsMat::new(
+            (3, 3),
+            vec![0, 2, 5, 7],
+            vec![0, 1, 0, 1, 2, 1, 2],
+            vec![4e20, 1e20, 1e20, 5e20, 2e20, 1e20, 6e20],
+        );
+        let expected = arr1(&[1f64, -2., 3.]);
+        let b = &a * &expected;
+        let mut x = Array1::zeros(3);
+        let res = super::bicgstab(a.view(), b.view(), x.view_mut(), 100, 1e-12)
+            .unwrap();
+        assert!(res.converged);
+        for (x, e) in x.iter().zip(expected.iter()) {
+            assert!((x - e).abs() < 1e-8);
+        }
+    }
+
+    #[test]
+    fn bicgstab_breakdown() {
+        // With this matrix and this right hand side, r_hat.v vanishes
+        // at the first iteration:
+        // | 0 1 |
+        // | 1 0 |
+        let a = CsMat::new((2, 2), vec![0, 1, 2], vec![1, 0], vec![1., 1.]);
+        let b = arr1(&[1., 0.]);
+        let mut x = Array1::zeros(2);
+        let res = super::bicgstab(a.view(), b.view(), x.view_mut(), 10, 1e-12);
+        match res {
+            Err(crate::errors::LinalgError::Breakdown(info)) => {
+                assert_eq!(info.iteration, 0);
+            }
+            _ => panic!("expected a breakdown"),
+        }
+    }
+
+    #[test]
+    fn lsqr_overdetermined() {
+        let a = CsMat::new(
+            (6, 3),
+            vec![0, 2, 3, 5, 6, 8, 10],
+            vec![0, 2, 1, 0, 1, 2, 0, 1, 1, 2],
+            vec![1f64, 2., 3., -1., 1., 4., 2., 1., -2., 1.],
+        );
+        let b = arr1(&[1., 2., 3., 4., 5., 6.]);
+        for a in &[a.view(), a.to_csc().view()] {
+            let res = super::lsqr(a.view(), b.view(), 100, 1e-12);
+            assert!(res.converged);
+            assert!(res.iterations <= 4);
+            // the residual is orthogonal to the range of A
+            let residual = &b - &(a * &res.x);
+            let normal_residual = &a.transpose_view() * &residual;
+            assert!(normal_residual.iter().all(|v| v.abs() < 1e-10));
+            let r_norm = residual.dot(&residual).sqrt();
+            let b_norm = b.dot(&b).sqrt();
+            assert!((res.residual_norm - r_norm / b_norm).abs() < 1e-10);
+            assert!(res.residual_norm > 0.1);
+        }
+    }
+
+    #[test]
+    fn lsqr_compatible() {
+        let a = laplacian_1d(10);
+        let expected = Array1::from_iter((0..10).map(|i| i as f64 - 4.));
+        let b = &a * &expected;
+        let res = super::lsqr(a.view(), b.view(), 100, 1e-12);
+        assert!(res.converged);
+        assert!(res.residual_norm < 1e-12);
+        for (x, e) in res.x.iter().zip(expected.iter()) {
+            assert!((x - e).abs() < 1e-8);
+        }
+    }
+
+    #[test]
+    fn lsqr_minimum_norm() {
+        // rank deficient, all solutions satisfy x0 + x1 = 1
+        // | 1 1 |
+        // | 1 1 |
+        // | 0 0 |
+        let a = CsMat::new(
+            (3, 2),
+            vec![0, 2, 4, 4],
+            vec![0, 1, 0, 1],
+            vec![1f64, 1., 1., 1.],
+        );
+        let b = arr1(&[1., 1., 3.]);
+        let res = super::lsqr(a.view(), b.view(), 10, 1e-12);
+        assert!(res.converged);
+        assert!((res.x[0] - 0.5).abs() < 1e-12);
+        assert!((res.x[1] - 0.5).abs() < 1e-12);
+
+        let b = arr1(&[0., 0., 0.]);
+        let res = super::lsqr(a.view(), b.view(), 10, 1e-12);
+        assert!(res.converged);
+        assert_eq!(res.iterations, 0);
+        assert_eq!(res.x, arr1(&[0., 0.]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Dimension mismatch")]
+    fn lsqr_dimension_mismatch() {
+        let a = laplacian_1d(3);
+        let b = arr1(&[1., 2.]);
+        let _ = super::lsqr(a.view(), b.view(), 10, 1e-12);
+    }
+}
@@ -0,0 +1,434 @@
+//! Sparse LU factorization
+//!
+//! The factorization is computed column by column following the left-looking
+//! algorithm of Gilbert and Peierls, with partial pivoting. No fill-reducing
+//! ordering is performed, so it can be beneficial to reorder the matrix
+//! beforehand, for instance with
+//! [`reverse_cuthill_mckee`](crate::linalg::reverse_cuthill_mckee).
+
+use crate::errors::{LinalgError, SingularMatrixInfo};
+use crate::indexing::SpIndex;
+use crate::sparse::permutation::PermOwnedI;
+use crate::sparse::{CsMatI, CsMatViewI};
+use crate::CSC;
+use ndarray::{Array1, ArrayView1};
+use num_traits::Float;
+
+/// The LU factorization `P A = L U` of a square sparse matrix `A`, where
+/// `P` is a row permutation, `L` is unit lower triangular and `U` is upper
+/// triangular.
+#[derive(Debug, Clone)]
+pub struct SparseLu<N, I: SpIndex = usize, Iptr: SpIndex = I> {
+    l: CsMatI<N, I, Iptr>,
+    u: CsMatI<N, I, Iptr>,
+    perm: PermOwnedI<I>,
+}
+
+/// Compute the LU factorization with partial pivoting of the square
+/// matrix `a`, which can be stored as CSR or CSC. A CSR matrix is converted
+/// to CSC first.
+///
+/// At each step, the pivot is the entry of largest magnitude in the current
+/// column among the rows which have not been pivotal yet.
+///
+/// Returns an error if `a` is not square, or if it is singular, ie if no
+/// non-zero pivot can be found for a column.
+///
+/// # Example
+///
+/// ```rust
+/// use ndarray::arr1;
+/// use sprs::CsMat;
+/// use sprs::linalg::lu;
+/// // | 0 2 |
+/// // | 1 1 |
+/// let a = CsMat::new((2, 2), vec![0, 1, 3], vec![1, 0, 1], vec![2., 1., 1.]);
+/// let lu = lu(a.view()).unwrap();
+/// assert_eq!(lu.det(), -2.);
+/// let x = lu.solve(arr1(&[4., 3.]).view());
+/// assert_eq!(x, arr1(&[1., 2.]));
+/// ```
+pub fn lu<N, I, Iptr>(
+    a: CsMatViewI<N, I, Iptr>,
+) -> Result<SparseLu<N, I, Iptr>, LinalgError>
+where
+    N: Float + Default,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    let n = a.rows();
+    if a.cols() != n {
+        return Err(LinalgError::NonSquareMatrix);
+    }
+    let a_csc_tmp;
+    let a_csc = if a.is_csc() {
+        a
+    } else {
+        a_csc_tmp = a.to_other_storage();
+        a_csc_tmp.view()
+    };
+
+    // The columns of L are stored with their diagonal entry first, and with
+    // row indices in the numbering of `a` until the permutation is known.
+    let mut l_indptr = Vec::with_capacity(n + 1);
+    let mut l_rows: Vec<usize> = Vec::new();
+    let mut l_vals = Vec::new();
+    let mut u_indptr = Vec::with_capacity(n + 1);
+    let mut u_rows = Vec::new();
+    let mut u_vals = Vec::new();
+    l_indptr.push(0);
+    u_indptr.push(0);
+    // pivot step of each row of a, if it has already been pivotal
+    let mut pinv: Vec<Option<usize>> = vec![None; n];
+    let mut x = vec![N::zero(); n];
+    let mut marked = vec![false; n];
+    let mut reach = Vec::new();
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+
+    for k in 0..n {
+        let col = a_csc.outer_view(k).unwrap();
+
+        // Symbolic step: the rows reachable from the pattern of the column
+        // in the graph of L, in postorder
+        let children = |row: usize, pinv: &[Option<usize>]| match pinv[row] {
+            Some(j) => l_indptr[j] + 1..l_indptr[j + 1],
+            None => 0..0,
+        };
+        reach.clear();
+        for (root, _) in col.iter() {
+            if marked[root] {
+                continue;
+            }
+            marked[root] = true;
+            stack.push((root, children(root, &pinv).start));
+            while let Some((row, mut pos)) = stack.pop() {
+                let end = children(row, &pinv).end;
+                let mut descended = false;
+                while pos < end {
+                    let child = l_rows[pos];
+                    pos += 1;
+                    if !marked[child] {
+                        marked[child] = true;
+                        stack.push((row, pos));
+                        stack.push((child, children(child, &pinv).start));
+                        descended = true;
+                        break;
+                    }
+                }
+                if !descended {
+                    reach.push(row);
+                }
+            }
+        }
+
+        // Numeric step: sparse triangular solve in topological order
+        for (row, &val) in col.iter() {
+            x[row] = val;
+        }
+        for &row in reach.iter().rev() {
+            if let Some(j) = pinv[row] {
+                let x_row = x[row];
+                for p in l_indptr[j] + 1..l_indptr[j + 1] {
+                    let target = l_rows[p];
+                    x[target] = x[target] - l_vals[p] * x_row;
+                }
+            }
+        }
+
+        // Partial pivoting among the rows which have not been pivotal yet
+        let mut pivot_row = None;
+        let mut pivot_abs = N::zero();
+        for &row in &reach {
+            match pinv[row] {
+                Some(j) => {
+                    u_rows.push(j);
+                    u_vals.push(x[row]);
+                }
+                None => {
+                    if x[row].abs() > pivot_abs {
+                        pivot_abs = x[row].abs();
+                        pivot_row = Some(row);
+                    }
+                }
+            }
+        }
+        let pivot_row = match pivot_row {
+            Some(row) => row,
+            None => {
+                return Err(LinalgError::SingularMatrix(SingularMatrixInfo {
+                    index: k,
+                    reason: "no non-zero pivot",
+                }))
+            }
+        };
+        let pivot = x[pivot_row];
+        u_rows.push(k);
+        u_vals.push(pivot);
+        u_indptr.push(u_rows.len());
+        pinv[pivot_row] = Some(k);
+        l_rows.push(pivot_row);
+        l_vals.push(N::one());
+        for &row in &reach {
+            if pinv[row].is_none() {
+                l_rows.push(row);
+                l_vals.push(x[row] / pivot);
+            }
+            x[row] = N::zero();
+            marked[row] = false;
+        }
+        l_indptr.push(l_rows.len());
+    }
+
+    // All rows are now pivotal, renumber the rows of L accordingly
+    let mut perm = vec![I::zero(); n];
+    for (row, step) in pinv.iter().enumerate() {
+        perm[step.unwrap()] = I::from_usize(row);
+    }
+    for row in l_rows.iter_mut() {
+        *row = pinv[*row].unwrap();
+    }
+    let l = sorted_csc(n, l_indptr, l_rows, l_vals);
+    let u = sorted_csc(n, u_indptr, u_rows, u_vals);
+    Ok(SparseLu {
+        l,
+        u,
+        perm: PermOwnedI::new_trusted(perm),
+    })
+}
+
+/// Build a square CSC matrix from columns whose row indices are unsorted
+fn sorted_csc<N, I, Iptr>(
+    n: usize,
+    indptr: Vec<usize>,
+    rows: Vec<usize>,
+    vals: Vec<N>,
+) -> CsMatI<N, I, Iptr>
+where
+    N: Copy,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    let mut indices = Vec::with_capacity(rows.len());
+    let mut data = Vec::with_capacity(vals.len());
+    let mut col = Vec::new();
+    for bounds in indptr.windows(2) {
+        col.clear();
+        col.extend(
+            rows[bounds[0]..bounds[1]]
+                .iter()
+                .zip(&vals[bounds[0]..bounds[1]]),
+        );
+        col.sort_unstable_by_key(|&(&row, _)| row);
+        indices.extend(col.iter().map(|&(&row, _)| I::from_usize(row)));
+        data.extend(col.iter().map(|&(_, &val)| val));
+    }
+    let indptr = indptr.into_iter().map(Iptr::from_usize).collect();
+    CsMatI::new_trusted(CSC, (n, n), indptr, indices, data)
+}
+
+impl<N, I, Iptr> SparseLu<N, I, Iptr>
+where
+    N: Float,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    /// The unit lower triangular factor `L`, stored as CSC
+    pub fn l(&self) -> CsMatViewI<'_, N, I, Iptr> {
+        self.l.view()
+    }
+
+    /// The upper triangular factor `U`, stored as CSC
+    pub fn u(&self) -> CsMatViewI<'_, N, I, Iptr> {
+        self.u.view()
+    }
+
+    /// The row permutation `P`: the row `i` of `P A` is the row
+    /// `perm.at(i)` of `A`, so that `L U` is equal to `A.permute_rows(perm)`.
+    pub fn row_perm(&self) -> &PermOwnedI<I> {
+        &self.perm
+    }
+
+    /// Solve the system `A x = b`
+    ///
+    /// # Panics
+    ///
+    /// If the dimension of `b` does not match the matrix dimension.
+    pub fn solve(&self, b: ArrayView1<N>) -> Array1<N> {
+        assert_eq!(b.len(), self.l.rows(), "Dimension mismatch");
+        let mut x = self.perm.apply_to_dense(b);
+        // L is unit lower triangular, with its diagonal first in each column
+        for (j, col) in self.l.outer_iterator().enumerate() {
+            let x_j = x[j];
+            for (row, &val) in col.iter().skip(1) {
+                x[row] = x[row] - val * x_j;
+            }
+        }
+        // U is upper triangular, with its diagonal last in each column
+        for (j, col) in self.u.outer_iterator().enumerate().rev() {
+            let (&diag, above) = col.data().split_last().unwrap();
+            let x_j = x[j] / diag;
+            x[j] = x_j;
+            for (&row, &val) in col.indices().iter().zip(above) {
+                x[row.index()] = x[row.index()] - val * x_j;
+            }
+        }
+        x
+    }
+
+    /// The determinant of `A`
+    pub fn det(&self) -> N {
+        let det = self.u_diag().fold(N::one(), |det, val| det * val);
+        if self.perm_is_odd() {
+            -det
+        } else {
+            det
+        }
+    }
+
+    /// The natural logarithm of the absolute value of the determinant of
+    /// `A`. This does not overflow or underflow for large matrices, unlike
+    /// [`det`](Self::det), whose sign can be used if needed.
+    pub fn logdet(&self) -> N {
+        self.u_diag()
+            .fold(N::zero(), |logdet, val| logdet + val.abs().ln())
+    }
+
+    fn u_diag(&self) -> impl Iterator<Item = N> + '_ {
+        self.u
+            .outer_iterator()
+            .map(|col| *col.data().last().unwrap())
+    }
+
+    fn perm_is_odd(&self) -> bool {
+        let n = self.perm.dim();
+        let mut visited = vec![false; n];
+        let mut nb_cycles = 0;
+        for start in 0..n {
+            if visited[start] {
+                continue;
+            }
+            nb_cycles += 1;
+            let mut i = start;
+            while !visited[i] {
+                visited[i] = true;
+                i = self.perm.at(i);
+            }
+        }
+        (n - nb_cycles) % 2 == 1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::lu;
+    use crate::errors::LinalgError;
+    use crate::sparse::CsMat;
+    use crate::test_data::mat1;
+    use ndarray::arr1;
+
+    fn assert_factors_match(a: &CsMat<f64>, lu: &super::SparseLu<f64>) {
+        let lu_prod = &lu.l() * &lu.u();
+        let pa = a.permute_rows(lu.row_perm().view()).to_csc();
+        let diff = &lu_prod - &pa;
+        assert!(diff.data().iter().all(|x| x.abs() < 1e-12));
+        assert!(lu.l().is_csc() && lu.u().is_csc());
+        assert_eq!(lu.l().tril(0).nnz(), lu.l().nnz());
+        assert_eq!(lu.u().triu(0).nnz(), lu.u().nnz());
+        assert!(lu.l().diag_iter().all(|d| d == Some(&1.)));
+    }
+
+    #[test]
+    fn lu_pivoting() {
+        // | 0 1 2 |
+        // | 3 0 1 |
+        // | 1 4 0 |
+        let a = CsMat::new(
+            (3, 3),
+            vec![0, 2, 4, 6],
+            vec![1, 2, 0, 2, 0, 1],
+            vec![1., 2., 3., 1., 1., 4.],
+        );
+        let lu = lu(a.view()).unwrap();
+        assert_factors_match(&a, &lu);
+        // det = 0 - 1 * (0 - 1) + 2 * (12 - 0) = 25
+        assert!((lu.det() - 25.).abs() < 1e-12);
+        assert!((lu.logdet() - 25f64.ln()).abs() < 1e-12);
+        let x = arr1(&[1., -2., 3.]);
+        let b = &a * &x;
+        let res = lu.solve(b.view());
+        assert!(res.iter().zip(x.iter()).all(|(a, b)| (a - b).abs() < 1e-12));
+        // the same from a CSC input
+        let lu_csc = super::lu(a.to_csc().view()).unwrap();
+        assert_eq!(lu_csc.l(), lu.l());
+        assert_eq!(lu_csc.u(), lu.u());
+    }
+
+    #[test]
+    fn lu_fill_in() {
+        // arrow matrix, which fills completely when the dense row and
+        // column come first
+        let n = 8;
+        let mut tri = crate::TriMat::new((n, n));
+        for i in 0..n {
+            tri.add_triplet(i, i, 4. + i as f64);
+            if i > 0 {
+                tri.add_triplet(0, i, 1.);
+                tri.add_triplet(i, 0, -1.);
+            }
+        }
+        let a: CsMat<f64> = tri.to_csc();
+        let lu = lu(a.view()).unwrap();
+        assert_factors_match(&a, &lu);
+        let x = ndarray::Array1::from_iter((0..n).map(|i| i as f64 - 2.));
+        let b = &a * &x;
+        let res = lu.solve(b.view());
+        assert!(res.iter().zip(x.iter()).all(|(a, b)| (a - b).abs() < 1e-10));
+        assert!(lu.det() > 0.);
+        assert!((lu.logdet() - lu.det().ln()).abs() < 1e-10);
+    }
+
+    #[test]
+    fn lu_det_sign() {
+        // permutation matrix of an odd permutation, scaled by 2
+        let a = CsMat::new(
+            (3, 3),
+            vec![0, 1, 2, 3],
+            vec![1, 0, 2],
+            vec![2., 2., 2.],
+        );
+        let lu = lu(a.view()).unwrap();
+        assert_eq!(lu.det(), -8.);
+        assert!((lu.logdet() - 8f64.ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn lu_errors() {
+        // mat1 has an empty first column
+        let a = mat1();
+        match lu(a.view()) {
+            Err(LinalgError::SingularMatrix(info)) => assert_eq!(info.index, 0),
+            _ => panic!("expected a singular matrix error"),
+        }
+        // linearly dependent columns
+        let a = CsMat::new(
+            (2, 2),
+            vec![0, 2, 4],
+            vec![0, 1, 0, 1],
+            vec![1., 2., 2., 4.],
+        );
+        match lu(a.view()) {
+            Err(LinalgError::SingularMatrix(info)) => assert_eq!(info.index, 1),
+            _ => panic!("expected a singular matrix error"),
+        }
+        let a = CsMat::<f64>::zero((2, 3));
+        assert!(matches!(lu(a.view()), Err(LinalgError::NonSquareMatrix)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Dimension mismatch")]
+    fn lu_solve_fail() {
+        let a = CsMat::<f64>::eye(2);
+        let lu = lu(a.view()).unwrap();
+        let _ = lu.solve(arr1(&[1., 2., 3.]).view());
+    }
+}
@@ -2,7 +2,7 @@ use crate::dense_vector::{DenseVector, DenseVectorMut};
 use crate::errors::{LinalgError, SingularMatrixInfo};
 use crate::indexing::SpIndex;
 use crate::sparse::CsMatViewI;
-use crate::sparse::CsVecViewI;
+use crate::sparse::{CsVecI, CsVecViewI};
 use crate::stack::{self, DStack, StackVal};
 use num_traits::Num;
 /// Sparse triangular solves
@@ -192,30 +192,46 @@ where
     // U_0_0 x0 = b_0 - x1*u_0_1
 
     for (col_ind, col) in upper_tri_mat.outer_iterator().enumerate().rev() {
-        if let Some(diag_val) = col.get(col_ind) {
-            if *diag_val == N::zero() {
-                return Err(LinalgError::SingularMatrix(SingularMatrixInfo {
-                    index: col_ind,
-                    reason: "diagonal element is a numeric 0",
-                }));
-            }
-            let b = rhs.index(col_ind);
-            let x = b / diag_val;
-            *rhs.index_mut(col_ind) = x.clone();
-            for (row_ind, val) in col.iter() {
-                if row_ind >= col_ind {
-                    continue;
-                }
-                *rhs.index_mut(row_ind) -= val * &x;
-            }
-        } else {
+        uspsolve_csc_process_col(col, col_ind, &mut rhs)?;
+    }
+
+    Ok(())
+}
+
+fn uspsolve_csc_process_col<N, I, V>(
+    col: CsVecViewI<N, I>,
+    col_ind: usize,
+    rhs: &mut V,
+) -> Result<(), LinalgError>
+where
+    N: Clone + Num + std::ops::SubAssign,
+    for<'r> &'r N: std::ops::Mul<&'r N, Output = N>,
+    for<'r> &'r N: std::ops::Div<&'r N, Output = N>,
+    V: DenseVectorMut<Scalar = N>,
+    I: SpIndex,
+{
+    if let Some(diag_val) = col.get(col_ind) {
+        if *diag_val == N::zero() {
             return Err(LinalgError::SingularMatrix(SingularMatrixInfo {
                 index: col_ind,
-                reason: "diagonal element is a structural 0",
+                reason: "diagonal element is a numeric 0",
             }));
         }
+        let b = rhs.index(col_ind);
+        let x = b / diag_val;
+        *rhs.index_mut(col_ind) = x.clone();
+        for (row_ind, val) in col.iter() {
+            if row_ind >= col_ind {
+                continue;
+            }
+            *rhs.index_mut(row_ind) -= val * &x;
+        }
+    } else {
+        return Err(LinalgError::SingularMatrix(SingularMatrixInfo {
+            index: col_ind,
+            reason: "diagonal element is a structural 0",
+        }));
     }
-
     Ok(())
 }
 
@@ -280,8 +296,7 @@ where
 /// `dstack` is a double stack with capacity 2*n
 /// `x_workspace` is a workspace vector with length equal to the number of
 /// rows of `lower_tri_mat`. Its input values can be anything.
-/// visited is a workspace vector of same size as `upper_tri_mat.indptr()`,
-/// and should be all false.
+/// visited is a workspace vector of length n, and should be all false.
 ///
 /// On succesful execution, dstack will hold the non-zero pattern in its
 /// right stack, and `x_workspace` will contain the solve values at the indices
@@ -289,11 +304,17 @@ where
 /// to be sorted (they are sorted for each connected component of the matrix's
 /// graph).
 ///
+/// A `LinalgError::NonSquareMatrix` error is returned if `lower_tri_mat`
+/// is not square, and a `LinalgError::SingularMatrix` error if a diagonal
+/// element needed by the solve is missing or zero.
+///
 /// # Panics
 ///
+/// * if `lower_tri_mat` is not CSC
+/// * if `rhs` is not of dimension n
 /// * if dstack.capacity() is too small
 /// * if dstack is not empty
-/// * if `w_workspace` is not of length n
+/// * if `x_workspace` is not of length n
 ///
 pub fn lsolve_csc_sparse_rhs<N, I, Iptr, V>(
     lower_tri_mat: CsMatViewI<N, I, Iptr>,
@@ -314,6 +335,12 @@ where
         panic!("Storage mismatch");
     }
     let n = lower_tri_mat.rows();
+    if lower_tri_mat.cols() != n {
+        return Err(LinalgError::NonSquareMatrix);
+    }
+    if rhs.dim() != n {
+        panic!("Dimension mismatch");
+    }
     assert!(dstack.capacity() >= 2 * n, "dstack cap should be 2*n");
     assert!(
         dstack.is_left_empty() && dstack.is_right_empty(),
@@ -364,16 +391,223 @@ where
     // solve for the non-zero values into dense workspace
     rhs.scatter(&mut x_workspace);
     for &ind in dstack.iter_right().map(stack::extract_stack_val) {
-        println!("ind: {}", ind);
         let col = lower_tri_mat.outer_view(ind).expect("ind not in bounds");
         lspsolve_csc_process_col(col, ind, &mut x_workspace)?;
     }
     Ok(())
 }
 
+/// Sparse triangular CSC / sparse vector solve, for an upper triangular
+/// matrix
+///
+/// `upper_tri_mat` is a sparse upper triangular matrix of shape (n, n)
+/// `rhs` is a sparse vector of size n
+/// `dstack` is a double stack with capacity 2*n
+/// `x_workspace` is a workspace vector with length equal to the number of
+/// rows of `upper_tri_mat`. Its input values can be anything.
+/// visited is a workspace vector of length n, and should be all false.
+///
+/// On succesful execution, dstack will hold the non-zero pattern in its
+/// right stack, and `x_workspace` will contain the solve values at the indices
+/// contained in right stack. The non-zero pattern indices are not guaranteed
+/// to be sorted (they are sorted for each connected component of the matrix's
+/// graph).
+///
+/// A `LinalgError::NonSquareMatrix` error is returned if `upper_tri_mat`
+/// is not square, and a `LinalgError::SingularMatrix` error if a diagonal
+/// element needed by the solve is missing or zero.
+///
+/// # Panics
+///
+/// * if `upper_tri_mat` is not CSC
+/// * if `rhs` is not of dimension n
+/// * if dstack.capacity() is too small
+/// * if dstack is not empty
+/// * if `x_workspace` is not of length n
+///
+pub fn usolve_csc_sparse_rhs<N, I, Iptr, V>(
+    upper_tri_mat: CsMatViewI<N, I, Iptr>,
+    rhs: CsVecViewI<N, I>,
+    dstack: &mut DStack<StackVal<usize>>,
+    mut x_workspace: V,
+    visited: &mut [bool],
+) -> Result<(), LinalgError>
+where
+    N: Clone + Num + std::ops::SubAssign,
+    for<'r> &'r N: std::ops::Mul<&'r N, Output = N>,
+    for<'r> &'r N: std::ops::Div<&'r N, Output = N>,
+    V: DenseVectorMut + DenseVector<Scalar = N>,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    if !upper_tri_mat.is_csc() {
+        panic!("Storage mismatch");
+    }
+    let n = upper_tri_mat.rows();
+    if upper_tri_mat.cols() != n {
+        return Err(LinalgError::NonSquareMatrix);
+    }
+    if rhs.dim() != n {
+        panic!("Dimension mismatch");
+    }
+    assert!(dstack.capacity() >= 2 * n, "dstack cap should be 2*n");
+    assert!(
+        dstack.is_left_empty() && dstack.is_right_empty(),
+        "dstack should be empty"
+    );
+    assert!(x_workspace.dim() == n, "x should be of len n");
+
+    // The non-zero pattern of the solution is computed by depth first
+    // search on the matrix's graph, as in the lower triangular case. Here a
+    // non-zero x_j propagates to the rows above j in the j-th column.
+    for (root_ind, _) in rhs.iter() {
+        if visited[root_ind] {
+            continue;
+        }
+        dstack.push_left(StackVal::Enter(root_ind));
+        while let Some(stack_val) = dstack.pop_left() {
+            match stack_val {
+                StackVal::Enter(ind) => {
+                    if visited[ind] {
+                        continue;
+                    }
+                    visited[ind] = true;
+                    dstack.push_left(StackVal::Exit(ind));
+                    if let Some(column) = upper_tri_mat.outer_view(ind) {
+                        for (child_ind, _) in column.iter() {
+                            dstack.push_left(StackVal::Enter(child_ind));
+                        }
+                    } else {
+                        unreachable!();
+                    }
+                }
+                StackVal::Exit(ind) => {
+                    dstack.push_right(StackVal::Enter(ind));
+                }
+            }
+        }
+    }
+
+    // solve for the non-zero values into dense workspace
+    rhs.scatter(&mut x_workspace);
+    for &ind in dstack.iter_right().map(stack::extract_stack_val) {
+        let col = upper_tri_mat.outer_view(ind).expect("ind not in bounds");
+        uspsolve_csc_process_col(col, ind, &mut x_workspace)?;
+    }
+    Ok(())
+}
+
+/// Solve `L x = b` for a sparse lower triangular CSC matrix `L` and a
+/// sparse right hand side `b`, returning the sparse solution `x`.
+///
+/// The non-zero pattern of `x` is computed by a traversal of the graph of
+/// `L` starting from the non-zeros of `b`, so the cost of the solve only
+/// depends on the number of non-zeros involved, not on the dimension of the
+/// system. See [`lsolve_csc_sparse_rhs`] to reuse the workspaces between
+/// several solves.
+///
+/// A `LinalgError::NonSquareMatrix` error is returned if `L` is not square,
+/// and a `LinalgError::SingularMatrix` error if a diagonal element needed
+/// by the solve is missing or zero.
+///
+/// # Panics
+///
+/// * if `l` is not CSC
+/// * if `b` is not of dimension n
+pub fn trisolve_lower<N, I, Iptr>(
+    l: CsMatViewI<N, I, Iptr>,
+    b: &CsVecI<N, I>,
+) -> Result<CsVecI<N, I>, LinalgError>
+where
+    N: Clone + Num + std::ops::SubAssign,
+    for<'r> &'r N: std::ops::Mul<&'r N, Output = N>,
+    for<'r> &'r N: std::ops::Div<&'r N, Output = N>,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    let n = l.rows();
+    let mut dstack = DStack::with_capacity(2 * n);
+    let mut x_workspace = vec![N::zero(); n];
+    let mut visited = vec![false; n];
+    lsolve_csc_sparse_rhs(
+        l,
+        b.view(),
+        &mut dstack,
+        &mut x_workspace[..],
+        &mut visited,
+    )?;
+    Ok(gather_sparse_solution(n, &dstack, &x_workspace))
+}
+
+/// Solve `U x = b` for a sparse upper triangular CSC matrix `U` and a
+/// sparse right hand side `b`, returning the sparse solution `x`.
+///
+/// This is the upper triangular counterpart of [`trisolve_lower`]. See
+/// [`usolve_csc_sparse_rhs`] to reuse the workspaces between several
+/// solves.
+///
+/// A `LinalgError::NonSquareMatrix` error is returned if `U` is not square,
+/// and a `LinalgError::SingularMatrix` error if a diagonal element needed
+/// by the solve is missing or zero.
+///
+/// # Panics
+///
+/// * if `u` is not CSC
+/// * if `b` is not of dimension n
+pub fn trisolve_upper<N, I, Iptr>(
+    u: CsMatViewI<N, I, Iptr>,
+    b: &CsVecI<N, I>,
+) -> Result<CsVecI<N, I>, LinalgError>
+where
+    N: Clone + Num + std::ops::SubAssign,
+    for<'r> &'r N: std::ops::Mul<&'r N, Output = N>,
+    for<'r> &'r N: std::ops::Div<&'r N, Output = N>,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    let n = u.rows();
+    let mut dstack = DStack::with_capacity(2 * n);
+    let mut x_workspace = vec![N::zero(); n];
+    let mut visited = vec![false; n];
+    usolve_csc_sparse_rhs(
+        u,
+        b.view(),
+        &mut dstack,
+        &mut x_workspace[..],
+        &mut visited,
+    )?;
+    Ok(gather_sparse_solution(n, &dstack, &x_workspace))
+}
+
+/// Gather the solution of a sparse right hand side solve into a sparse
+/// vector with sorted indices
+fn gather_sparse_solution<N, I>(
+    n: usize,
+    dstack: &DStack<StackVal<usize>>,
+    x_workspace: &[N],
+) -> CsVecI<N, I>
+where
+    N: Clone,
+    I: SpIndex,
+{
+    let mut nnz_pattern: Vec<usize> = dstack
+        .iter_right()
+        .map(stack::extract_stack_val)
+        .copied()
+        .collect();
+    nnz_pattern.sort_unstable();
+    let data = nnz_pattern
+        .iter()
+        .map(|&i| x_workspace[i].clone())
+        .collect();
+    let indices = nnz_pattern.into_iter().map(I::from_usize).collect();
+    CsVecI::new_trusted(n, indices, data)
+}
+
 #[cfg(test)]
 mod test {
 
+    use crate::errors::LinalgError;
     use crate::sparse::{CsMat, CsVec};
     use crate::stack::{self, DStack};
     use ndarray::arr1;
@@ -529,4 +763,113 @@ mod test {
 
         assert_eq!(x, expected_output);
     }
+
+    #[test]
+    fn uspsolve_csc() {
+        // |1 1          | | |   |1|
+        // |  2     1    | |1| = |2|
+        // |    3     1  | |1|   |4|
+        // |      7      | | |   | |
+        // |        5    | | |   | |
+        // |          1  | |1|   |1|
+        // |            2| | |   | |
+        let u = CsMat::new_csc(
+            (7, 7),
+            vec![0, 1, 3, 4, 5, 7, 9, 10],
+            vec![0, 0, 1, 2, 3, 1, 4, 2, 5, 6],
+            vec![1, 1, 2, 3, 7, 1, 5, 1, 1, 2],
+        );
+        let b = CsVec::new(7, vec![0, 1, 2, 5], vec![1, 2, 4, 1]);
+        let mut dstack = DStack::with_capacity(2 * 7);
+        let mut xw = vec![1; 7]; // inital values should not matter
+        let mut visited = vec![false; 7]; // inital values matter here
+
+        super::usolve_csc_sparse_rhs(
+            u.view(),
+            b.view(),
+            &mut dstack,
+            &mut xw,
+            &mut visited,
+        )
+        .unwrap();
+        let x: HashSet<_> = dstack
+            .iter_right()
+            .map(stack::extract_stack_val)
+            .map(|&i| (i, xw[i]))
+            .collect();
+
+        let expected_output =
+            CsVec::new(7, vec![0, 1, 2, 5], vec![0, 1, 1, 1]).to_set();
+
+        assert_eq!(x, expected_output);
+    }
+
+    #[test]
+    fn uspsolve_csc_singular() {
+        // |1 1| |x|   |1|
+        // |  0| |y| = |1|
+        let u = CsMat::new_csc((2, 2), vec![0, 1, 2], vec![0, 0], vec![1, 1]);
+        let b = CsVec::new(2, vec![1], vec![1]);
+        let mut dstack = DStack::with_capacity(2 * 2);
+        let mut xw = vec![0; 2];
+        let mut visited = vec![false; 2];
+        let res = super::usolve_csc_sparse_rhs(
+            u.view(),
+            b.view(),
+            &mut dstack,
+            &mut xw,
+            &mut visited,
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn trisolve_lower_upper() {
+        // |1        | | |   | |
+        // |1 2      | |2| = |4|
+        // |  3 3    | |1|   |9|
+        // |      7  | | |   | |
+        // |  2   3 5| |1|   |9|
+        let l = CsMat::new_csc(
+            (5, 5),
+            vec![0, 2, 5, 6, 8, 9],
+            vec![0, 1, 1, 2, 4, 2, 3, 4, 4],
+            vec![1, 1, 2, 3, 2, 3, 7, 3, 5],
+        );
+        let b = CsVec::new(5, vec![1, 2, 4], vec![4, 9, 9]);
+        let x = super::trisolve_lower(l.view(), &b).unwrap();
+        assert_eq!(x, CsVec::new(5, vec![1, 2, 4], vec![2, 1, 1]));
+
+        // |1 1          | | |   |1|
+        // |  2     1    | |1| = |2|
+        // |    3     1  | |1|   |4|
+        // |      7      | | |   | |
+        // |        5    | | |   | |
+        // |          1  | |1|   |1|
+        // |            2| | |   | |
+        let u = CsMat::new_csc(
+            (7, 7),
+            vec![0, 1, 3, 4, 5, 7, 9, 10],
+            vec![0, 0, 1, 2, 3, 1, 4, 2, 5, 6],
+            vec![1, 1, 2, 3, 7, 1, 5, 1, 1, 2],
+        );
+        let b = CsVec::new(7, vec![0, 1, 2, 5], vec![1, 2, 4, 1]);
+        let x = super::trisolve_upper(u.view(), &b).unwrap();
+        assert_eq!(x, CsVec::new(7, vec![0, 1, 2, 5], vec![0, 1, 1, 1]));
+    }
+
+    #[test]
+    fn trisolve_non_square() {
+        let m =
+            CsMat::new_csc((2, 3), vec![0, 1, 2, 2], vec![0, 1], vec![1, 1]);
+        let b = CsVec::new(2, vec![0], vec![1]);
+        assert_eq!(
+            super::trisolve_lower(m.view(), &b),
+            Err(LinalgError::NonSquareMatrix)
+        );
+        assert_eq!(
+            super::trisolve_upper(m.view(), &b),
+            Err(LinalgError::NonSquareMatrix)
+        );
+    }
 }
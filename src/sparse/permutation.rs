@@ -3,9 +3,11 @@
 /// Both the permutation matrices and its inverse are stored
 use std::ops::{Deref, Mul};
 
+use ndarray::{Array1, ArrayView1};
+
 use crate::dense_vector::{DenseVector, DenseVectorMut};
 use crate::indexing::SpIndex;
-use crate::sparse::{CompressedStorage, CsMatI, CsMatViewI};
+use crate::sparse::{CompressedStorage, CsMatBase, CsMatI, CsMatViewI};
 
 #[derive(Debug, Clone)]
 enum PermStorage<I, IndStorage>
@@ -225,6 +227,45 @@ where
         }
     }
 
+    /// Compute the permuted dense vector `P x`, whose element `i` is
+    /// `x[self.at(i)]`.
+    ///
+    /// This is consistent with [`permute`](CsMatBase::permute): if `A x = b`,
+    /// then `P A P^T` (computed by `A.permute(perm)`) maps `P x` to `P b`.
+    /// This makes it possible to reorder a linear system, solve it, and map
+    /// its solution back using [`apply_inv_to_dense`](Self::apply_inv_to_dense).
+    ///
+    /// # Panics
+    ///
+    /// If the dimension of `x` does not match the permutation's.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ndarray::arr1;
+    /// use sprs::PermOwned;
+    /// let perm = PermOwned::new(vec![2, 0, 1]);
+    /// let x = arr1(&[1., 2., 3.]);
+    /// let px = perm.apply_to_dense(x.view());
+    /// assert_eq!(px, arr1(&[3., 1., 2.]));
+    /// assert_eq!(perm.apply_inv_to_dense(px.view()), x);
+    /// ```
+    pub fn apply_to_dense<N: Clone>(&self, x: ArrayView1<N>) -> Array1<N> {
+        assert_eq!(self.dim, x.len(), "Dimension mismatch");
+        (0..self.dim).map(|i| x[self.at(i)].clone()).collect()
+    }
+
+    /// Compute the dense vector `P^T x`, whose element `i` is
+    /// `x[self.at_inv(i)]`. This undoes [`apply_to_dense`](Self::apply_to_dense).
+    ///
+    /// # Panics
+    ///
+    /// If the dimension of `x` does not match the permutation's.
+    pub fn apply_inv_to_dense<N: Clone>(&self, x: ArrayView1<N>) -> Array1<N> {
+        assert_eq!(self.dim, x.len(), "Dimension mismatch");
+        (0..self.dim).map(|i| x[self.at_inv(i)].clone()).collect()
+    }
+
     pub fn to_other_idx_type<I2>(&self) -> PermOwnedI<I2>
     where
         I2: SpIndex,
@@ -304,47 +345,122 @@ where
 {
     assert!(mat.rows() == mat.cols());
     assert!(mat.rows() == perm.dim());
-    if perm.is_identity() || mat.rows() == 0 {
+    mat.permute(perm)
+}
+
+/// # Permutation of the rows and columns of a sparse matrix
+impl<N, I, Iptr, IptrStorage, IndStorage, DataStorage>
+    CsMatBase<N, I, IptrStorage, IndStorage, DataStorage, Iptr>
+where
+    N: Clone,
+    I: SpIndex,
+    Iptr: SpIndex,
+    IptrStorage: Deref<Target = [Iptr]>,
+    IndStorage: Deref<Target = [I]>,
+    DataStorage: Deref<Target = [N]>,
+{
+    /// Compute the matrix `P A P^T`, ie the matrix whose entry `(i, j)` is
+    /// the entry `(perm.at(i), perm.at(j))` of this matrix. The storage
+    /// of this matrix is preserved.
+    ///
+    /// # Panics
+    ///
+    /// - if this matrix is not square
+    /// - if the dimension of `perm` does not match the matrix dimension
+    pub fn permute(&self, perm: PermViewI<I>) -> CsMatI<N, I, Iptr> {
+        assert_eq!(self.rows(), self.cols(), "Non square matrix");
+        assert_eq!(self.rows(), perm.dim(), "Dimension mismatch");
+        permute_outer_inner(self.view(), perm.reborrow(), perm)
+    }
+
+    /// Compute the matrix `P A`, ie the matrix whose row `i` is the row
+    /// `perm.at(i)` of this matrix. The storage of this matrix is preserved.
+    ///
+    /// # Panics
+    ///
+    /// If the dimension of `perm` does not match the number of rows.
+    pub fn permute_rows(&self, perm: PermViewI<I>) -> CsMatI<N, I, Iptr> {
+        assert_eq!(self.rows(), perm.dim(), "Dimension mismatch");
+        let identity = PermViewI::identity(self.cols());
+        match self.storage() {
+            CompressedStorage::CSR => {
+                permute_outer_inner(self.view(), perm, identity)
+            }
+            CompressedStorage::CSC => {
+                permute_outer_inner(self.view(), identity, perm)
+            }
+        }
+    }
+
+    /// Compute the matrix `A P^T`, ie the matrix whose column `j` is the
+    /// column `perm.at(j)` of this matrix. The storage of this matrix is
+    /// preserved.
+    ///
+    /// # Panics
+    ///
+    /// If the dimension of `perm` does not match the number of columns.
+    pub fn permute_cols(&self, perm: PermViewI<I>) -> CsMatI<N, I, Iptr> {
+        assert_eq!(self.cols(), perm.dim(), "Dimension mismatch");
+        let identity = PermViewI::identity(self.rows());
+        match self.storage() {
+            CompressedStorage::CSR => {
+                permute_outer_inner(self.view(), identity, perm)
+            }
+            CompressedStorage::CSC => {
+                permute_outer_inner(self.view(), perm, identity)
+            }
+        }
+    }
+}
+
+/// Permute the outer and inner dimensions of a matrix: the outer slice `i`
+/// of the result is the outer slice `outer_perm.at(i)` of `mat`, and the
+/// inner index `j` of the result corresponds to the inner index
+/// `inner_perm.at(j)` of `mat`.
+fn permute_outer_inner<N, I, Iptr>(
+    mat: CsMatViewI<N, I, Iptr>,
+    outer_perm: PermViewI<I>,
+    inner_perm: PermViewI<I>,
+) -> CsMatI<N, I, Iptr>
+where
+    N: Clone,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    debug_assert_eq!(mat.outer_dims(), outer_perm.dim());
+    debug_assert_eq!(mat.inner_dims(), inner_perm.dim());
+    if outer_perm.is_identity() && inner_perm.is_identity() {
         return mat.to_owned();
     }
-    // We can apply the CSR algorithm even if A is CSC:
-    // indeed, (PAP^T)^T = PA^TP^T, and transposing means going from CSC to CSR
+    let inner_identity = inner_perm.is_identity();
     let mut indptr = Vec::with_capacity(mat.indptr().len());
     let mut indices = Vec::with_capacity(mat.indices().len());
     let mut data = Vec::with_capacity(mat.data().len());
-    let (p, p_) = match perm.storage {
-        Identity => unreachable!(),
-        FinitePerm {
-            perm: p,
-            perm_inv: p_,
-        } => (p, p_),
-    };
     let mut nnz = Iptr::zero();
     indptr.push(nnz);
     let mut tmp = Vec::with_capacity(mat.max_outer_nnz());
-    for in_outer in p {
-        nnz += mat.indptr().nnz_in_outer(in_outer.index());
+    for out_outer in 0..mat.outer_dims() {
+        let in_outer = outer_perm.at(out_outer);
+        nnz += mat.indptr().nnz_in_outer(in_outer);
         indptr.push(nnz);
-        tmp.clear();
-        let outer = mat.outer_view(in_outer.index()).unwrap();
-        for (ind, val) in outer.indices().iter().zip(outer.data()) {
-            tmp.push((p_[ind.index()], val.clone()))
+        let outer = mat.outer_view(in_outer).unwrap();
+        if inner_identity {
+            indices.extend_from_slice(outer.indices());
+            data.extend_from_slice(outer.data());
+            continue;
         }
-        tmp.sort_by_key(|(ind, _)| *ind);
-        for (ind, val) in &tmp {
-            indices.push(*ind);
-            data.push(val.clone());
-        }
-    }
-
-    match mat.storage() {
-        CompressedStorage::CSR => {
-            CsMatI::new(mat.shape(), indptr, indices, data)
+        // Permuting the inner indices breaks their ordering
+        tmp.clear();
+        for (pos, ind) in outer.indices().iter().enumerate() {
+            tmp.push((inner_perm.at_inv(ind.index()), pos));
         }
-        CompressedStorage::CSC => {
-            CsMatI::new_csc(mat.shape(), indptr, indices, data)
+        tmp.sort_unstable_by_key(|(ind, _)| *ind);
+        for &(ind, pos) in &tmp {
+            indices.push(I::from_usize(ind));
+            data.push(outer.data()[pos].clone());
         }
     }
+    CsMatI::new_trusted(mat.storage(), mat.shape(), indptr, indices, data)
 }
 
 #[cfg(test)]
@@ -369,6 +485,43 @@ mod test {
         assert_eq!(y, ndarray::arr1(&[2, 1, 3, 5, 4]));
     }
 
+    #[test]
+    fn apply_to_dense() {
+        let p = super::PermOwned::new(vec![2, 1, 3, 0, 4]);
+        let x = ndarray::arr1(&[5, 1, 2, 3, 4]);
+        let px = p.apply_to_dense(x.view());
+        assert_eq!(px, ndarray::arr1(&[2, 1, 3, 5, 4]));
+        assert_eq!(px, p.view() * x.view());
+        assert_eq!(p.apply_inv_to_dense(px.view()), x);
+        assert_eq!(p.apply_to_dense(p.apply_inv_to_dense(x.view()).view()), x);
+        let id = super::PermOwned::identity(5);
+        assert_eq!(id.apply_to_dense(x.view()), x);
+        assert_eq!(id.apply_inv_to_dense(x.view()), x);
+
+        // reorder -> solve -> unreorder workflow
+        let mat = CsMat::new(
+            (3, 3),
+            vec![0, 2, 3, 5],
+            vec![0, 2, 1, 0, 2],
+            vec![2., 1., 3., 1., 4.],
+        );
+        let x = ndarray::arr1(&[1., -2., 3.]);
+        let b = &mat * &x;
+        let p = super::PermOwned::new(vec![1, 2, 0]);
+        let papt = mat.permute(p.view());
+        let pb = p.apply_to_dense(b.view());
+        let px = p.apply_to_dense(x.view());
+        assert_eq!(&papt * &px, pb);
+        assert_eq!(p.apply_inv_to_dense(px.view()), x);
+    }
+
+    #[test]
+    #[should_panic(expected = "Dimension mismatch")]
+    fn apply_to_dense_fail() {
+        let p = super::PermOwned::new(vec![1, 0]);
+        let _ = p.apply_to_dense(ndarray::arr1(&[1, 2, 3]).view());
+    }
+
     #[test]
     fn transform_mat_papt() {
         // | 1 0 0 3 1 |
@@ -404,6 +557,63 @@ mod test {
         );
         let papt = super::transform_mat_papt(mat.view(), perm.view());
         assert_eq!(expected_papt, papt);
+        assert_eq!(expected_papt, mat.permute(perm.view()));
+        assert_eq!(expected_papt.to_csr(), mat.to_csr().permute(perm.view()));
+    }
+
+    #[test]
+    fn permute_identity_and_round_trip() {
+        let mat = crate::test_data::mat1();
+        let id = super::PermOwned::identity(5);
+        assert_eq!(mat.permute(id.view()), mat);
+        assert_eq!(mat.permute_rows(id.view()), mat);
+        assert_eq!(mat.permute_cols(id.view()), mat);
+        let perm = super::PermOwned::new(vec![3, 0, 4, 2, 1]);
+        let permuted = mat.permute(perm.view());
+        assert_ne!(permuted, mat);
+        assert_eq!(permuted.permute(perm.inv()), mat);
+        let permuted = mat.permute_rows(perm.view());
+        assert_eq!(permuted.permute_rows(perm.inv()), mat);
+        let permuted = mat.permute_cols(perm.view());
+        assert_eq!(permuted.permute_cols(perm.inv()), mat);
+        let mat = mat.to_csc();
+        let permuted = mat.permute(perm.view());
+        assert_eq!(permuted.permute(perm.inv()), mat);
+    }
+
+    #[test]
+    fn permute_rows_cols_rectangular() {
+        // | 1 0 2 |
+        // | 0 3 0 |
+        let mat =
+            CsMat::new((2, 3), vec![0, 2, 3], vec![0, 2, 1], vec![1, 2, 3]);
+        let row_perm = super::PermOwned::new(vec![1, 0]);
+        let col_perm = super::PermOwned::new(vec![2, 0, 1]);
+        // | 0 3 0 |
+        // | 1 0 2 |
+        let expected =
+            CsMat::new((2, 3), vec![0, 1, 3], vec![1, 0, 2], vec![3, 1, 2]);
+        assert_eq!(mat.permute_rows(row_perm.view()), expected);
+        assert_eq!(
+            mat.to_csc().permute_rows(row_perm.view()),
+            expected.to_csc()
+        );
+        // | 2 1 0 |
+        // | 0 0 3 |
+        let expected =
+            CsMat::new((2, 3), vec![0, 2, 3], vec![0, 1, 2], vec![2, 1, 3]);
+        assert_eq!(mat.permute_cols(col_perm.view()), expected);
+        assert_eq!(
+            mat.to_csc().permute_cols(col_perm.view()),
+            expected.to_csc()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn permute_non_square() {
+        let mat = CsMat::<f64>::zero((2, 3));
+        let _ = mat.permute(super::PermOwned::identity(2).view());
     }
 
     #[test]
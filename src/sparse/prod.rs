@@ -1,9 +1,16 @@
+//! Sparse matrix product
+//!
+//! The `*_mulacc_dense_*` functions accumulate the product into their
+//! `out` argument, which is not zeroed first, ie they compute
+//! `out += lhs * rhs`. This makes it possible to chain accumulations, for
+//! instance to compute a product by blocks, without allocating. `out` can
+//! have any memory layout.
+
 use crate::dense_vector::{DenseVector, DenseVectorMut};
 use crate::indexing::SpIndex;
 use crate::sparse::compressed::SpMatView;
-///! Sparse matrix product
 use crate::sparse::prelude::*;
-use crate::Ix2;
+use crate::{Ix2, CSR};
 use ndarray::{ArrayView, ArrayViewMut, Axis};
 use num_traits::Num;
 
@@ -189,6 +196,15 @@ where
 /// CSR-dense rowmaj multiplication
 ///
 /// Performs better if rhs has a decent number of colums.
+///
+/// The product is accumulated into `out`, see the
+/// [module documentation](crate::prod) for details.
+///
+/// # Panics
+///
+/// - if `lhs.cols() != rhs.rows()`, if `lhs.rows() != out.rows()` or if
+///   `rhs.cols() != out.cols()`.
+/// - if `lhs` is not CSR.
 pub fn csr_mulacc_dense_rowmaj<'a, N, A, B, I, Iptr>(
     lhs: CsMatViewI<A, I, Iptr>,
     rhs: ArrayView<B, Ix2>,
@@ -227,6 +243,15 @@ pub fn csr_mulacc_dense_rowmaj<'a, N, A, B, I, Iptr>(
 /// CSC-dense rowmaj multiplication
 ///
 /// Performs better if rhs has a decent number of colums.
+///
+/// The product is accumulated into `out`, see the
+/// [module documentation](crate::prod) for details.
+///
+/// # Panics
+///
+/// - if `lhs.cols() != rhs.rows()`, if `lhs.rows() != out.rows()` or if
+///   `rhs.cols() != out.cols()`.
+/// - if `lhs` is not CSC.
 pub fn csc_mulacc_dense_rowmaj<'a, N, A, B, I, Iptr>(
     lhs: CsMatViewI<A, I, Iptr>,
     rhs: ArrayView<B, Ix2>,
@@ -262,6 +287,15 @@ pub fn csc_mulacc_dense_rowmaj<'a, N, A, B, I, Iptr>(
 /// CSC-dense colmaj multiplication
 ///
 /// Performs better if rhs has few columns.
+///
+/// The product is accumulated into `out`, see the
+/// [module documentation](crate::prod) for details.
+///
+/// # Panics
+///
+/// - if `lhs.cols() != rhs.rows()`, if `lhs.rows() != out.rows()` or if
+///   `rhs.cols() != out.cols()`.
+/// - if `lhs` is not CSC.
 pub fn csc_mulacc_dense_colmaj<'a, N, A, B, I, Iptr>(
     lhs: CsMatViewI<A, I, Iptr>,
     rhs: ArrayView<B, Ix2>,
@@ -298,6 +332,15 @@ pub fn csc_mulacc_dense_colmaj<'a, N, A, B, I, Iptr>(
 /// CSR-dense colmaj multiplication
 ///
 /// Performs better if rhs has few columns.
+///
+/// The product is accumulated into `out`, see the
+/// [module documentation](crate::prod) for details.
+///
+/// # Panics
+///
+/// - if `lhs.cols() != rhs.rows()`, if `lhs.rows() != out.rows()` or if
+///   `rhs.cols() != out.cols()`.
+/// - if `lhs` is not CSR.
 pub fn csr_mulacc_dense_colmaj<'a, N, A, B, I, Iptr>(
     lhs: CsMatViewI<A, I, Iptr>,
     rhs: ArrayView<B, Ix2>,
@@ -331,6 +374,62 @@ pub fn csr_mulacc_dense_colmaj<'a, N, A, B, I, Iptr>(
     }
 }
 
+/// Compute the outer product `u v^T` of two sparse vectors, as a CSR
+/// matrix of shape `(u.dim(), v.dim())`.
+///
+/// The row `i` of the result is `u_i v`. Zero entries of `u` or `v` do
+/// not produce entries in the result, even when they are explicitly
+/// stored, so that the result stays as sparse as possible.
+///
+/// # Example
+///
+/// ```rust
+/// use sprs::{CsMat, CsVec};
+/// let u = CsVec::new(3, vec![0, 2], vec![1, 2]);
+/// let v = CsVec::new(2, vec![1], vec![3]);
+/// let prod: CsMat<i32> = sprs::outer_product(u.view(), v.view());
+/// let expected = CsMat::new((3, 2), vec![0, 1, 1, 2], vec![1, 1], vec![3, 6]);
+/// assert_eq!(prod, expected);
+/// ```
+pub fn outer_product<N, I, Iptr>(
+    u: CsVecViewI<N, I>,
+    v: CsVecViewI<N, I>,
+) -> CsMatI<N, I, Iptr>
+where
+    N: Num + Clone,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    let v_nnz: Vec<(I, &N)> = v
+        .indices()
+        .iter()
+        .zip(v.data().iter())
+        .filter(|(_, val)| !val.is_zero())
+        .map(|(&ind, val)| (ind, val))
+        .collect();
+    let nnz = v_nnz.len() * u.nnz();
+    let mut indptr = Vec::with_capacity(u.dim() + 1);
+    let mut indices = Vec::with_capacity(nnz);
+    let mut data = Vec::with_capacity(nnz);
+    indptr.push(Iptr::zero());
+    for (row, u_val) in u.iter() {
+        while indptr.len() <= row {
+            indptr.push(Iptr::from_usize(indices.len()));
+        }
+        if u_val.is_zero() {
+            continue;
+        }
+        for &(col, v_val) in &v_nnz {
+            indices.push(col);
+            data.push(u_val.clone() * v_val.clone());
+        }
+    }
+    while indptr.len() <= u.dim() {
+        indptr.push(Iptr::from_usize(indices.len()));
+    }
+    CsMatI::new_trusted(CSR, (u.dim(), v.dim()), indptr, indices, data)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -343,6 +442,24 @@ mod test {
     use ndarray::linalg::Dot;
     use ndarray::{arr1, arr2, s, Array, Array2, Dimension, ShapeBuilder};
 
+    #[test]
+    fn outer_product() {
+        let u = CsVec::new(4, vec![0, 1, 3], vec![1., 0., -2.]);
+        let v = CsVec::new(3, vec![0, 2], vec![2., 3.]);
+        let prod: CsMat<f64> = super::outer_product(u.view(), v.view());
+        assert_eq!(prod.shape(), (4, 3));
+        assert!(prod.is_csr());
+        // the explicit zero of u gives an empty row
+        assert_eq!(prod.indptr().raw_storage(), &[0, 2, 2, 2, 4]);
+        let dense_u = u.to_dense().into_shape((4, 1)).unwrap();
+        let dense_v = v.to_dense().into_shape((1, 3)).unwrap();
+        assert_eq!(prod.to_dense(), dense_u.dot(&dense_v));
+
+        let empty = CsVec::<f64>::empty(2);
+        let prod: CsMat<f64> = super::outer_product(u.view(), empty.view());
+        assert_eq!(prod, CsMat::zero((4, 2)));
+    }
+
     #[test]
     fn test_csvec_dot_by_binary_search() {
         let vec1 = CsVecI::new(8, vec![0, 2, 4, 6], vec![1.; 4]);
@@ -628,6 +745,64 @@ mod test {
         assert_eq!(c, expected_output);
     }
 
+    #[test]
+    fn mulacc_dense_accumulates() {
+        // out += lhs * rhs, chained over the two column blocks of lhs
+        let a = mat1();
+        let b = mat_dense1();
+        let expected = &a * &b;
+        let (a_left, a_right) = (
+            a.to_csc().slice_outer(0..2).to_csr(),
+            a.to_csc().slice_outer(2..5).to_csr(),
+        );
+        let (b_top, b_bottom) = (b.slice(s![0..2, ..]), b.slice(s![2..5, ..]));
+        let mut res_rowmaj = Array::<f64, _>::zeros((5, 5));
+        let mut res_colmaj = Array::<f64, _>::zeros((5, 5).f());
+        for &(lhs, rhs) in &[(&a_left, b_top), (&a_right, b_bottom)] {
+            super::csr_mulacc_dense_rowmaj(
+                lhs.view(),
+                rhs,
+                res_rowmaj.view_mut(),
+            );
+            super::csr_mulacc_dense_colmaj(
+                lhs.view(),
+                rhs,
+                res_colmaj.view_mut(),
+            );
+            let lhs = lhs.to_csc();
+            super::csc_mulacc_dense_rowmaj(
+                lhs.view(),
+                rhs,
+                res_rowmaj.view_mut(),
+            );
+            super::csc_mulacc_dense_colmaj(
+                lhs.view(),
+                rhs,
+                res_colmaj.view_mut(),
+            );
+        }
+        assert_eq!(res_rowmaj, &expected * 2.);
+        assert_eq!(res_colmaj, &expected * 2.);
+    }
+
+    #[test]
+    #[should_panic(expected = "Dimension mismatch")]
+    fn mulacc_dense_out_shape_fail() {
+        let a = mat1();
+        let b = mat_dense1();
+        let mut res = Array::<f64, _>::zeros((5, 4));
+        super::csr_mulacc_dense_rowmaj(a.view(), b.view(), res.view_mut());
+    }
+
+    #[test]
+    #[should_panic(expected = "Storage mismatch")]
+    fn mulacc_dense_storage_fail() {
+        let a = mat1_csc();
+        let b = mat_dense1();
+        let mut res = Array::<f64, _>::zeros((5, 5));
+        super::csr_mulacc_dense_colmaj(a.view(), b.view(), res.view_mut());
+    }
+
     // stolen from ndarray - not currently exported.
     fn assert_close<D>(a: ArrayView<f64, D>, b: ArrayView<f64, D>)
     where
@@ -0,0 +1,103 @@
+//! Reorderings of the rows and columns of square sparse matrices
+//!
+//! The functions in this module compute permutations based on the sparsity
+//! pattern of a matrix, which is interpreted as the adjacency structure of
+//! an undirected graph. The returned permutations can be applied to form
+//! `P A P^T` using [`transform_mat_papt`](../fn.transform_mat_papt.html).
+//!
+//! More customizable variants are available in
+//! [`linalg::ordering`](../linalg/ordering/index.html).
+
+use crate::indexing::SpIndex;
+use crate::sparse::graph::symmetrized_structure;
+use crate::sparse::linalg::ordering;
+use crate::sparse::permutation::PermOwnedI;
+use crate::sparse::CsMatViewI;
+
+/// Compute the reverse Cuthill-McKee permutation of a square matrix, which
+/// tends to reduce its bandwidth.
+///
+/// The permutation is computed on the symmetrized sparsity pattern of `mat`,
+/// ie on the pattern of `A + A^T`, which means the values of `mat` need not
+/// be symmetric. Each connected component of the graph is processed in turn,
+/// starting from a pseudo-peripheral vertex.
+///
+/// # Panics
+///
+/// If `mat` is not square.
+///
+/// # Example
+///
+/// ```rust
+/// use sprs::{reorder, transform_mat_papt, CsMat, TriMat};
+/// // A circulant sparsity pattern
+/// let mut tri = TriMat::new((5, 5));
+/// for i in 0..5 {
+///     tri.add_triplet(i, i, 2.);
+///     tri.add_triplet(i, (i + 2) % 5, 1.);
+/// }
+/// let mat: CsMat<f64> = tri.to_csr();
+/// let perm = reorder::reverse_cuthill_mckee(mat.view());
+/// let permuted = transform_mat_papt(mat.view(), perm.view());
+/// assert_eq!(permuted.nnz(), mat.nnz());
+/// ```
+pub fn reverse_cuthill_mckee<N, I, Iptr>(
+    mat: CsMatViewI<N, I, Iptr>,
+) -> PermOwnedI<I>
+where
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    let structure = symmetrized_structure(mat);
+    ordering::reverse_cuthill_mckee(structure.view()).perm
+}
+
+#[cfg(test)]
+mod test {
+    use super::reverse_cuthill_mckee;
+    use crate::sparse::linalg;
+    use crate::sparse::permutation::transform_mat_papt;
+    use crate::sparse::{CsMat, TriMat};
+
+    fn bandwidth(mat: &CsMat<f64>) -> usize {
+        let (lower, upper) = mat.bandwidth();
+        lower.max(upper)
+    }
+
+    #[test]
+    fn rcm_shuffled_path() {
+        // A path graph 0 - 4 - 1 - 3 - 2 - 5, with edges only stored once
+        let path = [0, 4, 1, 3, 2, 5];
+        let mut tri = TriMat::new((6, 6));
+        for i in 0..6 {
+            tri.add_triplet(i, i, 1.);
+        }
+        for w in path.windows(2) {
+            tri.add_triplet(w[0], w[1], 1.);
+        }
+        let mat: CsMat<f64> = tri.to_csr();
+        assert_eq!(bandwidth(&mat), 4);
+        let perm = reverse_cuthill_mckee(mat.view());
+        assert!(crate::perm_is_valid(perm.vec().as_slice()));
+        let permuted = transform_mat_papt(mat.view(), perm.view());
+        assert_eq!(bandwidth(&permuted), 1);
+        assert_eq!(permuted.nnz(), mat.nnz());
+    }
+
+    #[test]
+    fn rcm_disconnected() {
+        // Two components: 0 - 3 - 5 and 1 - 2 - 4
+        let mut tri = TriMat::new((6, 6));
+        for &(i, j) in &[(0, 3), (3, 5), (1, 2), (2, 4)] {
+            tri.add_triplet(i, j, 1.);
+            tri.add_triplet(j, i, 1.);
+        }
+        let mat: CsMat<f64> = tri.to_csc();
+        let perm = reverse_cuthill_mckee(mat.view());
+        let permuted = transform_mat_papt(mat.view(), perm.view());
+        assert_eq!(bandwidth(&permuted), 1);
+        // symmetric inputs give the same result as the linalg version
+        let ordering = linalg::reverse_cuthill_mckee(mat.view());
+        assert_eq!(perm.vec(), ordering.perm.vec());
+    }
+}
@@ -1,8 +1,15 @@
-//! This module implementations to slice a matrix along the desired dimension.
+//! This module implementations to slice a matrix along the desired dimension,
+//! or a vector.
 //! We're using a sealed trait to enable using ranges for an idiomatic API.
+//!
+//! The owned sub-matrices and sub-vectors are built with `new_trusted`:
+//! the retained indices are sorted and in bounds since they come from a
+//! valid structure and are all shifted by the same amount.
 
 use crate::range::Range;
-use crate::{CsMatBase, CsMatViewI, CsMatViewMutI, SpIndex};
+use crate::{
+    CsMatBase, CsMatI, CsMatViewI, CsMatViewMutI, CsVecBase, CsVecI, SpIndex,
+};
 use std::ops::{Deref, DerefMut};
 
 impl<N, I: SpIndex, Iptr: SpIndex, IptrStorage, IStorage, DStorage>
@@ -17,6 +24,68 @@ where
     pub fn slice_outer<S: Range>(&self, range: S) -> CsMatViewI<N, I, Iptr> {
         self.view().slice_outer_rbr(range)
     }
+
+    /// Extract the submatrix spanned by the specified outer and inner
+    /// ranges.
+    ///
+    /// Contrary to `slice_outer`, the ranges are clamped to the matrix
+    /// bounds, and an inverted range gives an empty dimension.
+    ///
+    /// Also contrary to `slice_outer`, this returns an owned matrix and
+    /// not a view: a view can only borrow the indices of `self` as they
+    /// are, while the inner indices of the result need to be re-based, and
+    /// the retained entries are not contiguous in `self`. The copy is
+    /// proportional to the number of non-zeros of the outer range.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let eye: CsMat<f64> = CsMat::eye(5);
+    /// let sub = eye.slice_outer_inner(1..4, 2..);
+    /// assert_eq!(sub.shape(), (3, 3));
+    /// assert_eq!(sub.nnz(), 2);
+    /// ```
+    pub fn slice_outer_inner<S1, S2>(
+        &self,
+        outer: S1,
+        inner: S2,
+    ) -> CsMatI<N, I, Iptr>
+    where
+        N: Clone,
+        S1: Range,
+        S2: Range,
+    {
+        let clamp = |start: usize, end: Option<usize>, dim: usize| {
+            let end = end.map_or(dim, |end| end.min(dim));
+            (start.min(end), end)
+        };
+        let (outer_start, outer_end) =
+            clamp(outer.start(), outer.end(), self.outer_dims());
+        let (inner_start, inner_end) =
+            clamp(inner.start(), inner.end(), self.inner_dims());
+
+        let mut indptr = Vec::with_capacity(outer_end - outer_start + 1);
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        indptr.push(Iptr::zero());
+        for vec in self.slice_outer(outer_start..outer_end).outer_iterator() {
+            for (ind, val) in vec.iter() {
+                if ind >= inner_start && ind < inner_end {
+                    indices.push(I::from_usize(ind - inner_start));
+                    data.push(val.clone());
+                }
+            }
+            indptr.push(Iptr::from_usize(indices.len()));
+        }
+        let (outer_dims, inner_dims) =
+            (outer_end - outer_start, inner_end - inner_start);
+        let shape = match self.storage() {
+            crate::CSR => (outer_dims, inner_dims),
+            crate::CSC => (inner_dims, outer_dims),
+        };
+        CsMatI::new_trusted(self.storage(), shape, indptr, indices, data)
+    }
 }
 
 impl<N, I: SpIndex, Iptr: SpIndex, IptrStorage, IStorage, DStorage>
@@ -88,6 +157,47 @@ where
     }
 }
 
+impl<N, I, IStorage, DStorage> CsVecBase<IStorage, DStorage, N, I>
+where
+    I: SpIndex,
+    IStorage: Deref<Target = [I]>,
+    DStorage: Deref<Target = [N]>,
+{
+    /// Extract the sub-vector spanned by the specified range, with its
+    /// indices re-based to start at the beginning of the range.
+    ///
+    /// Like `slice_outer_inner`, the range is clamped to the vector
+    /// dimension, and an inverted range gives an empty vector.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsVec;
+    /// let v = CsVec::new(30, vec![5, 12, 17, 25], vec![1., 2., 3., 4.]);
+    /// assert_eq!(v.slice(10..20), CsVec::new(10, vec![2, 7], vec![2., 3.]));
+    /// assert_eq!(v.slice(20..), CsVec::new(10, vec![5], vec![4.]));
+    /// ```
+    pub fn slice<S: Range>(&self, range: S) -> CsVecI<N, I>
+    where
+        N: Clone,
+    {
+        let end = range.end().map_or(self.dim(), |end| end.min(self.dim()));
+        let start = range.start().min(end);
+        let position = |ind: usize| {
+            self.indices()
+                .binary_search_by(|i| i.index().cmp(&ind))
+                .unwrap_or_else(|pos| pos)
+        };
+        let (first, last) = (position(start), position(end));
+        let indices = self.indices()[first..last]
+            .iter()
+            .map(|i| I::from_usize(i.index() - start))
+            .collect();
+        let data = self.data()[first..last].to_vec();
+        CsVecI::new_trusted(end - start, indices, data)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::CsMat;
@@ -105,4 +215,56 @@ mod tests {
         assert_eq!(iter.next().unwrap(), (&1., (4, 6)));
         assert!(iter.next().is_none());
     }
+
+    #[test]
+    fn slice_outer_inner() {
+        use crate::test_data::{mat1, mat1_csc};
+        // | 0 0 3 4 0 |
+        // | 0 0 0 2 5 |
+        // | 0 0 5 0 0 |
+        // | 0 8 0 0 0 |
+        // | 0 0 0 7 0 |
+        let mat = mat1();
+        let sub = mat.slice_outer_inner(1..4, 2..4);
+        let expected =
+            CsMat::new((3, 2), vec![0, 1, 2, 2], vec![1, 0], vec![2., 5.]);
+        assert_eq!(sub, expected);
+
+        let sub = mat1_csc().slice_outer_inner(1..4, 2..4);
+        // columns 1 to 3 and rows 2 to 3 of mat1
+        let expected =
+            CsMat::new_csc((2, 3), vec![0, 1, 2, 2], vec![1, 0], vec![8., 5.]);
+        assert_eq!(sub, expected);
+
+        // full ranges give back the matrix
+        assert_eq!(mat.slice_outer_inner(.., ..), mat);
+        // clamped ranges
+        assert_eq!(mat.slice_outer_inner(3..10, 3..=7).shape(), (2, 2));
+        assert_eq!(mat.slice_outer_inner(8.., ..).shape(), (0, 5));
+        // inverted ranges give empty dimensions
+        #[allow(clippy::reversed_empty_ranges)]
+        let sub = mat.slice_outer_inner(4..2, 3..1);
+        assert_eq!(sub.shape(), (0, 0));
+        assert_eq!(sub.nnz(), 0);
+    }
+
+    #[test]
+    fn slice_vec() {
+        use crate::CsVec;
+        let v = CsVec::new(8, vec![0, 1, 4, 5, 7], vec![1., 2., 3., 4., 5.]);
+        assert_eq!(v.slice(1..5), CsVec::new(4, vec![0, 3], vec![2., 3.]));
+        assert_eq!(v.slice(2..4), CsVec::empty(2));
+        assert_eq!(
+            v.slice(..=4),
+            CsVec::new(5, vec![0, 1, 4], vec![1., 2., 3.])
+        );
+        assert_eq!(v.view().slice(..), v);
+        // clamped ranges
+        assert_eq!(v.slice(6..12), CsVec::new(2, vec![1], vec![5.]));
+        assert_eq!(v.slice(10..), CsVec::empty(0));
+        // inverted ranges give empty vectors
+        #[allow(clippy::reversed_empty_ranges)]
+        let sub = v.slice(5..2);
+        assert_eq!(sub, CsVec::empty(0));
+    }
 }
@@ -1,6 +1,7 @@
 //! Implementation of the paper
 //! Bank and Douglas, 2001, Sparse Matrix Multiplication Package (SMPP)
 
+use crate::errors::ArithmeticError;
 use crate::indexing::SpIndex;
 use crate::sparse::prelude::*;
 use crate::sparse::CompressedStorage::CSR;
@@ -187,6 +188,51 @@ pub fn numeric<
     }
 }
 
+/// Split the rows of `lhs` into `nb_chunks` contiguous chunks having a
+/// similar workload for the product `lhs * rhs`.
+///
+/// The workload of a row is estimated by the number of multiplications it
+/// requires, ie the sum of the nnz of the rows of `rhs` it references.
+/// Returns the `nb_chunks + 1` row boundaries of the chunks. Some chunks
+/// may be empty if the workload cannot be split evenly.
+fn balanced_row_splits<B, I, Iptr>(
+    lhs: CsStructureViewI<I, Iptr>,
+    rhs: CsMatViewI<B, I, Iptr>,
+    nb_chunks: usize,
+) -> Vec<usize>
+where
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    let rhs_indptr = rhs.indptr();
+    // count one unit of work per row to handle rows without products
+    let row_costs: Vec<usize> = lhs
+        .outer_iterator()
+        .map(|row| {
+            row.indices().iter().fold(1, |cost, col| {
+                cost + rhs_indptr.nnz_in_outer_sz(col.index())
+            })
+        })
+        .collect();
+    let total_cost: usize = row_costs.iter().sum();
+    let mut splits = Vec::with_capacity(nb_chunks + 1);
+    splits.push(0);
+    let mut cost = 0;
+    for (row, row_cost) in row_costs.iter().enumerate() {
+        cost += row_cost;
+        // close all the chunks whose target cost has been reached
+        while splits.len() < nb_chunks
+            && cost * nb_chunks >= total_cost * splits.len()
+        {
+            splits.push(row + 1);
+        }
+    }
+    while splits.len() < nb_chunks + 1 {
+        splits.push(lhs.rows());
+    }
+    splits
+}
+
 /// Compute a sparse matrix product using the SMMP routines
 ///
 /// # Panics
@@ -271,25 +317,18 @@ where
     let mut res_indices = Vec::new();
     let nb_threads = seens.len();
     assert!(nb_threads > 0);
-    let chunk_size = lhs.indptr().len() / nb_threads;
+    let splits =
+        balanced_row_splits(lhs.structure_view(), rhs.view(), nb_threads);
     let mut lhs_chunks = Vec::with_capacity(nb_threads);
     let mut res_indptr_chunks = Vec::with_capacity(nb_threads);
     let mut res_indices_chunks = Vec::with_capacity(nb_threads);
-    for chunk_id in 0..nb_threads {
-        let start = if chunk_id == 0 {
-            0
-        } else {
-            chunk_id * chunk_size
-        };
-        let stop = if chunk_id + 1 < nb_threads {
-            (chunk_id + 1) * chunk_size
-        } else {
-            lhs.rows()
-        };
-        lhs_chunks.push(lhs.slice_outer(start..stop));
-        res_indptr_chunks.push(vec![Iptr::zero(); stop - start + 1]);
+    for bounds in splits.windows(2) {
+        let (start, stop) = (bounds[0], bounds[1]);
+        let lhs_chunk = lhs.slice_outer(start..stop);
         res_indices_chunks
-            .push(Vec::with_capacity(lhs.nnz() + rhs.nnz() / chunk_size));
+            .push(Vec::with_capacity(lhs_chunk.nnz() + rhs.nnz() / nb_threads));
+        lhs_chunks.push(lhs_chunk);
+        res_indptr_chunks.push(vec![Iptr::zero(); stop - start + 1]);
     }
     #[cfg(feature = "multi_thread")]
     let iter = lhs_chunks
@@ -415,6 +454,186 @@ where
     )
 }
 
+/// Compute a sparse matrix product using the SMMP routines, detecting
+/// overflows while accumulating the values of the product.
+///
+/// This is intended for exact integer computations, where a silent
+/// wraparound would corrupt the result. The computation is sequential.
+/// Note that the accumulation of the terms of a value is performed in the
+/// storage order of the inputs, so an intermediate overflow is reported even
+/// if the final value would have been representable.
+///
+/// # Errors
+///
+/// `ArithmeticError::Overflow` containing the location in the product of
+/// the first value whose computation overflowed.
+///
+/// # Panics
+///
+/// - if `lhs.cols() != rhs.rows()`.
+/// - if `lhs` or `rhs` is not CSR.
+pub fn checked_mul_csr_csr<N, I, Iptr>(
+    lhs: CsMatViewI<N, I, Iptr>,
+    rhs: CsMatViewI<N, I, Iptr>,
+) -> Result<CsMatI<N, I, Iptr>, ArithmeticError>
+where
+    N: crate::CheckedMulAcc + num_traits::Zero + Clone,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    assert_eq!(lhs.cols(), rhs.rows());
+    assert!(lhs.is_csr());
+    assert!(rhs.is_csr());
+    let mut res_indptr = vec![Iptr::zero(); lhs.rows() + 1];
+    let mut res_indices = Vec::new();
+    let mut seen = vec![false; rhs.cols()];
+    symbolic(
+        lhs.structure_view(),
+        rhs.structure_view(),
+        &mut res_indptr,
+        &mut res_indices,
+        &mut seen,
+    );
+    let mut res_data = Vec::with_capacity(res_indices.len());
+    let mut tmp = vec![N::zero(); rhs.cols()];
+    for (row, (lhs_row, res_range)) in
+        lhs.outer_iterator().zip(res_indptr.windows(2)).enumerate()
+    {
+        for (lhs_col, lhs_val) in lhs_row.iter() {
+            let rhs_row = rhs.outer_view(lhs_col).unwrap();
+            for (col, rhs_val) in rhs_row.iter() {
+                tmp[col]
+                    .checked_mul_acc(lhs_val, rhs_val)
+                    .ok_or(ArithmeticError::Overflow { row, col })?;
+            }
+        }
+        let res_range = res_range[0].index()..res_range[1].index();
+        for col in &res_indices[res_range] {
+            res_data.push(std::mem::replace(&mut tmp[col.index()], N::zero()));
+        }
+    }
+
+    // Correctness: same as mul_csr_csr_with_workspace
+    Ok(CsMatI::new_trusted(
+        CSR,
+        (lhs.rows(), rhs.cols()),
+        res_indptr,
+        res_indices,
+        res_data,
+    ))
+}
+
+/// Compute the structure of the product `lhs * rhs` of two CSR matrices,
+/// without computing its values.
+///
+/// Returns the `indptr` and `indices` arrays of the CSR product. They can
+/// be reused with [`numeric_product_into`] to compute several products of
+/// matrices sharing the structures of `lhs` and `rhs`, which avoids
+/// recomputing the structure when only the values change.
+///
+/// # Panics
+///
+/// - if `lhs.cols() != rhs.rows()`
+/// - if `lhs` or `rhs` is not CSR
+///
+/// # Example
+///
+/// ```rust
+/// use sprs::{smmp, CsMat};
+/// let a = CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 1], vec![1., 2., 3.]);
+/// let (indptr, indices) = smmp::symbolic_product(a.view(), a.view());
+/// let mut data = vec![0.; indices.len()];
+/// for step in 1..4 {
+///     let a_step = a.map(|x| x * step as f64);
+///     smmp::numeric_product_into(
+///         a_step.view(),
+///         a_step.view(),
+///         &indptr,
+///         &indices,
+///         &mut data,
+///     );
+///     let prod = &a_step * &a_step;
+///     assert_eq!(prod.indices(), &indices[..]);
+///     assert_eq!(prod.data(), &data[..]);
+/// }
+/// ```
+pub fn symbolic_product<A, B, I, Iptr>(
+    lhs: CsMatViewI<A, I, Iptr>,
+    rhs: CsMatViewI<B, I, Iptr>,
+) -> (Vec<Iptr>, Vec<I>)
+where
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    assert_eq!(lhs.cols(), rhs.rows(), "Dimension mismatch");
+    assert!(lhs.is_csr() && rhs.is_csr(), "Storage mismatch");
+    let mut indptr = vec![Iptr::zero(); lhs.rows() + 1];
+    let mut indices = Vec::new();
+    let mut seen = vec![false; rhs.cols()];
+    symbolic(
+        lhs.structure_view(),
+        rhs.structure_view(),
+        &mut indptr,
+        &mut indices,
+        &mut seen,
+    );
+    (indptr, indices)
+}
+
+/// Compute the values of the product `lhs * rhs` of two CSR matrices into
+/// `data`, given the structure `indptr` and `indices` of the product, as
+/// computed by [`symbolic_product`].
+///
+/// The structure can contain entries which are not produced by the
+/// product, their values are then set to zero.
+///
+/// # Panics
+///
+/// - if `lhs.cols() != rhs.rows()`
+/// - if `lhs` or `rhs` is not CSR
+/// - if `indptr.len() != lhs.rows() + 1`
+/// - if `indices` or `data` do not have the length given by `indptr`
+/// - if the product has a non-zero entry outside of the structure, which
+///   happens when the structure of `lhs` or `rhs` has changed since the
+///   call to `symbolic_product`
+pub fn numeric_product_into<N, A, B, I, Iptr>(
+    lhs: CsMatViewI<A, I, Iptr>,
+    rhs: CsMatViewI<B, I, Iptr>,
+    indptr: &[Iptr],
+    indices: &[I],
+    data: &mut [N],
+) where
+    N: crate::MulAcc<A, B> + num_traits::Zero,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    assert_eq!(lhs.cols(), rhs.rows(), "Dimension mismatch");
+    assert!(lhs.is_csr() && rhs.is_csr(), "Storage mismatch");
+    assert_eq!(indptr.len(), lhs.rows() + 1, "Dimension mismatch");
+    let nnz = indptr.last().unwrap().index();
+    assert_eq!(indices.len(), nnz, "Dimension mismatch");
+    assert_eq!(data.len(), nnz, "Dimension mismatch");
+    let mut tmp: Vec<N> = (0..rhs.cols()).map(|_| N::zero()).collect();
+    let mut in_pattern = vec![false; rhs.cols()];
+    for (lhs_row, range) in lhs.outer_iterator().zip(indptr.windows(2)) {
+        let range = range[0].index()..range[1].index();
+        for col in &indices[range.clone()] {
+            in_pattern[col.index()] = true;
+        }
+        for (lhs_col, lhs_val) in lhs_row.iter() {
+            let rhs_row = rhs.outer_view(lhs_col).unwrap();
+            for (col, rhs_val) in rhs_row.iter() {
+                assert!(in_pattern[col], "Structure mismatch");
+                tmp[col].mul_acc(lhs_val, rhs_val);
+            }
+        }
+        for (col, val) in indices[range.clone()].iter().zip(&mut data[range]) {
+            *val = std::mem::replace(&mut tmp[col.index()], N::zero());
+            in_pattern[col.index()] = false;
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::test_data;
@@ -512,6 +731,65 @@ mod test {
         let _ = &a * &b;
     }
 
+    #[test]
+    fn balanced_row_splits() {
+        // the first row is much more costly than the others
+        let mut tri = crate::TriMat::new((6, 6));
+        for col in 0..6 {
+            tri.add_triplet(0, col, 1.);
+        }
+        for row in 0..6 {
+            tri.add_triplet(row, row, 1.);
+        }
+        let a: crate::CsMat<f64> = tri.to_csr();
+        // row costs are 12, 2, 2, 2, 2, 2 for a total of 22
+        let splits =
+            super::balanced_row_splits(a.structure_view(), a.view(), 3);
+        assert_eq!(splits, vec![0, 1, 3, 6]);
+        let splits =
+            super::balanced_row_splits(a.structure_view(), a.view(), 1);
+        assert_eq!(splits, vec![0, 6]);
+        // more chunks than rows yield empty chunks
+        let splits =
+            super::balanced_row_splits(a.structure_view(), a.view(), 8);
+        assert_eq!(splits.len(), 9);
+        assert_eq!(splits[0], 0);
+        assert_eq!(*splits.last().unwrap(), 6);
+        assert!(splits.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn mul_csr_csr_with_workspace_unbalanced() {
+        let mut tri = crate::TriMat::new((50, 50));
+        for row in 0..50_usize {
+            let band = 1 + row / 5;
+            for col in row.saturating_sub(band)..(row + band + 1).min(50) {
+                tri.add_triplet(row, col, (row + 2 * col) as f64);
+            }
+        }
+        let a: crate::CsMat<f64> = tri.to_csr();
+        let mut seens = vec![vec![false; 50].into_boxed_slice()];
+        let mut tmps = vec![vec![0.; 50].into_boxed_slice()];
+        let expected = super::mul_csr_csr_with_workspace(
+            a.view(),
+            a.view(),
+            &mut seens,
+            &mut tmps,
+        );
+        for nb_threads in 2..12 {
+            let mut seens =
+                vec![vec![false; 50].into_boxed_slice(); nb_threads];
+            let mut tmps = vec![vec![0.; 50].into_boxed_slice(); nb_threads];
+            let res = super::mul_csr_csr_with_workspace(
+                a.view(),
+                a.view(),
+                &mut seens,
+                &mut tmps,
+            );
+            assert_eq!(res, expected);
+        }
+    }
+
     #[test]
     fn mul_complex() {
         use num_complex::Complex32;
@@ -553,4 +831,61 @@ mod test {
         let b = &a * &a;
         assert_eq!(b, expected);
     }
+
+    #[test]
+    fn symbolic_numeric_product() {
+        let a = test_data::mat1();
+        let b = test_data::mat2();
+        let (indptr, indices) = super::symbolic_product(a.view(), b.view());
+        let mut data = vec![0.; indices.len()];
+        super::numeric_product_into(
+            a.view(),
+            b.view(),
+            &indptr,
+            &indices,
+            &mut data,
+        );
+        let expected = &a * &b;
+        assert_eq!(expected.indptr().raw_storage(), &indptr[..]);
+        assert_eq!(expected.indices(), &indices[..]);
+        assert_eq!(expected.data(), &data[..]);
+
+        // the structure can be reused with new values
+        let a2 = test_data::mat1_times_2();
+        super::numeric_product_into(
+            a2.view(),
+            b.view(),
+            &indptr,
+            &indices,
+            &mut data,
+        );
+        assert_eq!((&a2 * &b).data(), &data[..]);
+
+        // a larger structure gets explicit zeros
+        let zero = crate::CsMat::<f64>::zero((5, 5));
+        super::numeric_product_into(
+            zero.view(),
+            b.view(),
+            &indptr,
+            &indices,
+            &mut data,
+        );
+        assert!(data.iter().all(|&x| x == 0.));
+    }
+
+    #[test]
+    #[should_panic(expected = "Structure mismatch")]
+    fn numeric_product_into_structure_mismatch() {
+        let a = test_data::mat1();
+        let b = test_data::mat2();
+        let (indptr, indices) = super::symbolic_product(a.view(), a.view());
+        let mut data = vec![0.; indices.len()];
+        super::numeric_product_into(
+            a.view(),
+            b.view(),
+            &indptr,
+            &indices,
+            &mut data,
+        );
+    }
 }
@@ -1,8 +1,12 @@
 /// Functions dealing with symmetric sparse matrices
 use std::ops::Deref;
 
+use ndarray::{Array, ArrayView1, Ix1};
+use num_traits::Num;
+
 use crate::indexing::SpIndex;
 use crate::sparse::prelude::*;
+use crate::{MulAcc, CSC};
 
 pub fn is_symmetric<N, I, Iptr, IpStorage, IStorage, DStorage>(
     mat: &CsMatBase<N, I, IpStorage, IStorage, DStorage, Iptr>,
@@ -33,10 +37,138 @@ where
     true
 }
 
+/// A symmetric sparse matrix, storing only its lower triangle.
+///
+/// The lower triangle (diagonal included) is stored as a CSC matrix, which
+/// halves the memory required for symmetric operators while still allowing
+/// products with the full matrix.
+#[derive(PartialEq, Debug, Clone)]
+pub struct SymCsMatI<N, I: SpIndex, Iptr: SpIndex = I> {
+    lower: CsMatI<N, I, Iptr>,
+}
+
+/// A symmetric sparse matrix using `usize` indices, see `SymCsMatI`
+pub type SymCsMat<N> = SymCsMatI<N, usize>;
+
+impl<N, I: SpIndex, Iptr: SpIndex> SymCsMatI<N, I, Iptr> {
+    /// Create a symmetric matrix from its lower triangle. The matrix is
+    /// converted to CSC storage if necessary.
+    ///
+    /// # Panics
+    ///
+    /// - if `lower` is not square
+    /// - if `lower` has a non-zero entry above its diagonal
+    pub fn from_lower(lower: CsMatI<N, I, Iptr>) -> Self
+    where
+        N: Clone + Default,
+    {
+        assert_eq!(lower.rows(), lower.cols(), "Non square matrix");
+        let lower = if lower.is_csc() {
+            lower
+        } else {
+            lower.to_other_storage()
+        };
+        for (col_ind, col) in lower.outer_iterator().enumerate() {
+            if let Some(first_row) = col.indices().first() {
+                assert!(
+                    first_row.index() >= col_ind,
+                    "Entry above the diagonal in lower triangle"
+                );
+            }
+        }
+        Self { lower }
+    }
+
+    /// The number of rows (and columns) of this matrix
+    pub fn rows(&self) -> usize {
+        self.lower.rows()
+    }
+
+    /// The number of columns (and rows) of this matrix
+    pub fn cols(&self) -> usize {
+        self.lower.cols()
+    }
+
+    /// The number of stored non-zeros, ie the non-zeros of the lower
+    /// triangle.
+    pub fn nnz(&self) -> usize {
+        self.lower.nnz()
+    }
+
+    /// A view of the stored lower triangle, in CSC storage.
+    pub fn lower(&self) -> CsMatViewI<'_, N, I, Iptr> {
+        self.lower.view()
+    }
+
+    /// Get back the stored lower triangle, in CSC storage.
+    pub fn into_lower(self) -> CsMatI<N, I, Iptr> {
+        self.lower
+    }
+
+    /// Compute the product of the full symmetric matrix with the dense
+    /// vector `x`.
+    ///
+    /// Each off-diagonal entry `a_ij` of the lower triangle contributes both
+    /// `a_ij * x[j]` to `y[i]` and `a_ij * x[i]` to `y[j]`, while diagonal
+    /// entries are only accounted for once.
+    ///
+    /// # Panics
+    ///
+    /// If the length of `x` does not match the dimension of the matrix.
+    pub fn matvec(&self, x: ArrayView1<N>) -> Array<N, Ix1>
+    where
+        N: Num + Clone + MulAcc,
+    {
+        assert_eq!(self.cols(), x.len(), "Dimension mismatch");
+        let mut y = Array::from_elem(self.rows(), N::zero());
+        for (col_ind, col) in self.lower.outer_iterator().enumerate() {
+            let x_col = &x[col_ind];
+            for (row_ind, value) in col.iter() {
+                y[row_ind].mul_acc(value, x_col);
+                if row_ind != col_ind {
+                    y[col_ind].mul_acc(value, &x[row_ind]);
+                }
+            }
+        }
+        y
+    }
+
+    /// Materialize the full symmetric matrix, in CSC storage.
+    pub fn to_full(&self) -> CsMatI<N, I, Iptr>
+    where
+        N: Clone + Default,
+    {
+        let n = self.rows();
+        // Column j of the full matrix is made of row j of the lower
+        // triangle (the diagonal excepted) followed by its column j.
+        let lower_rows = self.lower.to_other_storage();
+        let nnz = 2 * self.nnz() - self.lower.diag_iter().flatten().count();
+        let mut indptr = Vec::with_capacity(n + 1);
+        let mut indices = Vec::with_capacity(nnz);
+        let mut data = Vec::with_capacity(nnz);
+        indptr.push(Iptr::zero());
+        for (j, (row, col)) in lower_rows
+            .outer_iterator()
+            .zip(self.lower.outer_iterator())
+            .enumerate()
+        {
+            for (i, val) in row.iter().filter(|&(i, _)| i != j) {
+                indices.push(I::from_usize(i));
+                data.push(val.clone());
+            }
+            indices.extend_from_slice(col.indices());
+            data.extend_from_slice(col.data());
+            indptr.push(Iptr::from_usize(indices.len()));
+        }
+        CsMatI::new_trusted(CSC, (n, n), indptr, indices, data)
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::is_symmetric;
-    use crate::sparse::CsMatView;
+    use super::{is_symmetric, SymCsMat};
+    use crate::sparse::{CsMat, CsMatView};
+    use ndarray::arr1;
 
     #[test]
     fn is_symmetric_simple() {
@@ -57,4 +189,61 @@ mod test {
     }
 
     // TODO: symmetry test on A^T*A products
+
+    fn lower_and_full() -> (CsMat<f64>, CsMat<f64>) {
+        // | 4 1 0 2 |
+        // | 1 5 3 0 |
+        // | 0 3 6 0 |
+        // | 2 0 0 7 |
+        let lower = CsMat::new_csc(
+            (4, 4),
+            vec![0, 3, 5, 6, 7],
+            vec![0, 1, 3, 1, 2, 2, 3],
+            vec![4., 1., 2., 5., 3., 6., 7.],
+        );
+        let full = CsMat::new_csc(
+            (4, 4),
+            vec![0, 3, 6, 8, 10],
+            vec![0, 1, 3, 0, 1, 2, 1, 2, 0, 3],
+            vec![4., 1., 2., 1., 5., 3., 3., 6., 2., 7.],
+        );
+        (lower, full)
+    }
+
+    #[test]
+    fn sym_to_full() {
+        let (lower, full) = lower_and_full();
+        let sym = SymCsMat::from_lower(lower.clone());
+        assert_eq!(sym.nnz(), 7);
+        assert_eq!(sym.to_full(), full);
+        assert!(is_symmetric(&sym.to_full()));
+        let sym = SymCsMat::from_lower(lower.to_csr());
+        assert_eq!(sym.lower(), lower.view());
+        assert_eq!(sym.to_full(), full);
+    }
+
+    #[test]
+    fn sym_matvec() {
+        let (lower, full) = lower_and_full();
+        let sym = SymCsMat::from_lower(lower);
+        let x = arr1(&[1., -2., 3., 0.5]);
+        let expected = &full * &x;
+        assert_eq!(sym.matvec(x.view()), expected);
+        assert_eq!(sym.matvec(x.view()), arr1(&[3., 0., 12., 5.5]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn sym_from_lower_upper_entry() {
+        let (_, full) = lower_and_full();
+        let _ = SymCsMat::from_lower(full);
+    }
+
+    #[test]
+    #[should_panic]
+    fn sym_matvec_dim_mismatch() {
+        let (lower, _) = lower_and_full();
+        let sym = SymCsMat::from_lower(lower);
+        let _ = sym.matvec(arr1(&[1., 2.]).view());
+    }
 }
@@ -1,5 +1,6 @@
 use crate::indexing::SpIndex;
 use crate::sparse::prelude::*;
+use crate::CompressedStorage;
 use num_traits::Num;
 ///! Triplet format matrix
 ///!
@@ -11,7 +12,7 @@ use num_traits::Num;
 ///! the row indices, the column indices, and the values of the non-zero
 ///! entries. By convention, duplicate locations are summed up when converting
 ///! into `CsMat`.
-use std::ops::{Deref, DerefMut};
+use std::ops::{AddAssign, Deref, DerefMut};
 use std::slice::Iter;
 
 /// Indexing type into a Triplet
@@ -174,6 +175,46 @@ impl<N, I: SpIndex> TriMatI<N, I> {
         self.col_inds.reserve_exact(cap);
         self.data.reserve_exact(cap);
     }
+
+    /// The number of non-zeros this matrix can hold without reallocating
+    /// its backing storage
+    pub fn capacity(&self) -> usize {
+        self.row_inds
+            .capacity()
+            .min(self.col_inds.capacity())
+            .min(self.data.capacity())
+    }
+
+    /// Sort the triplets by row then column, and collapse the triplets
+    /// sharing the same location into a single one by summing their values.
+    ///
+    /// After this call, [`find_locations`](Self::find_locations) returns
+    /// at most one index for any location.
+    pub fn sum_duplicates(&mut self)
+    where
+        N: AddAssign,
+    {
+        let mut triplets: Vec<(I, I, N)> = self
+            .row_inds
+            .drain(..)
+            .zip(self.col_inds.drain(..))
+            .zip(self.data.drain(..))
+            .map(|((i, j), v)| (i, j, v))
+            .collect();
+        triplets.sort_by_key(|&(i, j, _)| (i, j));
+        for (i, j, v) in triplets {
+            let is_duplicate = self.row_inds.last() == Some(&i)
+                && self.col_inds.last() == Some(&j);
+            match self.data.last_mut() {
+                Some(last) if is_duplicate => *last += v,
+                _ => {
+                    self.row_inds.push(i);
+                    self.col_inds.push(j);
+                    self.data.push(v);
+                }
+            }
+        }
+    }
 }
 
 /// # Common methods shared by all variants of triplet matrices
@@ -233,6 +274,14 @@ where
             .collect()
     }
 
+    /// Check whether the triplets are sorted by increasing outer then inner
+    /// index for the given storage, without duplicated locations. When
+    /// this holds, the conversion into a compressed matrix with this
+    /// storage does not need to sort the triplets.
+    pub fn is_sorted_for(&self, storage: CompressedStorage) -> bool {
+        self.triplet_iter().is_sorted_for(storage)
+    }
+
     /// Get a transposed view of this matrix
     pub fn transpose_view(&self) -> TriMatViewI<N, I> {
         TriMatViewI {
@@ -257,6 +306,10 @@ where
     }
 
     /// Create a CSC matrix from this triplet matrix
+    ///
+    /// The CSC structure is built directly, by ordering the triplets by
+    /// column then row, without going through a CSR matrix. The row indices
+    /// of each column are sorted, and duplicated triplets are summed.
     pub fn to_csc<Iptr: SpIndex>(&self) -> CsMatI<N, I, Iptr>
     where
         N: Clone + Num,
@@ -265,6 +318,9 @@ where
     }
 
     /// Create a CSR matrix from this triplet matrix
+    ///
+    /// The column indices of each row are sorted, and duplicated triplets
+    /// are summed.
     pub fn to_csr<Iptr: SpIndex>(&self) -> CsMatI<N, I, Iptr>
     where
         N: Clone + Num,
@@ -336,6 +392,7 @@ mod test {
 
     use super::{TriMat, TriMatI};
     use crate::sparse::{CsMat, CsMatI};
+    use crate::{CSC, CSR};
 
     #[test]
     fn triplet_incremental() {
@@ -361,6 +418,21 @@ mod test {
         assert_eq!(csc, expected);
     }
 
+    #[test]
+    fn triplet_capacity() {
+        let mut triplet_mat = TriMat::<f64>::with_capacity((4, 4), 6);
+        assert!(triplet_mat.capacity() >= 6);
+        for i in 0..6 {
+            triplet_mat.add_triplet(i % 4, i / 2, 1.);
+        }
+        assert!(triplet_mat.capacity() >= 6);
+        triplet_mat.reserve(10);
+        assert!(triplet_mat.capacity() >= 16);
+        triplet_mat.reserve_exact(20);
+        assert!(triplet_mat.capacity() >= 26);
+        assert_eq!(TriMat::<f64>::new((2, 2)).capacity(), 0);
+    }
+
     #[test]
     fn triplet_unordered() {
         let mut triplet_mat = TriMat::with_capacity((4, 4), 6);
@@ -423,6 +495,30 @@ mod test {
         assert_eq!(csr, expected.to_csr());
     }
 
+    #[test]
+    fn triplet_to_csc_matches_to_csr() {
+        // | 0 0 7 0 0 |
+        // | 2 0 0 0 1 |
+        // | 0 0 0 0 0 |
+        // | 3 0 4 0 0 |
+        // with some duplicated entries, and empty rows and columns
+        let triplet_mat = TriMat::from_triplets(
+            (4, 5),
+            vec![3, 1, 0, 3, 1, 0, 3, 1],
+            vec![2, 4, 2, 0, 0, 2, 2, 0],
+            vec![1, 1, 5, 3, 1, 2, 3, 1],
+        );
+        let csc: CsMat<i32> = triplet_mat.to_csc();
+        let expected = CsMat::new_csc(
+            (4, 5),
+            vec![0, 2, 2, 4, 4, 5],
+            vec![1, 3, 0, 3, 1],
+            vec![2, 3, 7, 4, 1],
+        );
+        assert_eq!(csc, expected);
+        assert_eq!(csc, triplet_mat.to_csr().to_csc());
+    }
+
     #[test]
     fn triplet_from_vecs() {
         // |1 2    |
@@ -638,4 +734,104 @@ mod test {
         assert_eq!(m.indices(), &[1, 0]);
         assert_eq!(m.data(), &[1, 2]);
     }
+
+    #[test]
+    fn triplet_sum_duplicates() {
+        let mut triplet_mat = TriMat::new((3, 3));
+        triplet_mat.add_triplet(0, 0, 1.);
+        triplet_mat.add_triplet(2, 1, 4.);
+        triplet_mat.add_triplet(0, 0, 1.);
+        triplet_mat.add_triplet(1, 2, 5.);
+        triplet_mat.add_triplet(0, 0, 1.);
+        triplet_mat.add_triplet(2, 1, -1.);
+        let expected: CsMat<f64> = triplet_mat.to_csr();
+        triplet_mat.sum_duplicates();
+        assert_eq!(triplet_mat.nnz(), 3);
+        assert_eq!(triplet_mat.row_inds(), &[0, 1, 2]);
+        assert_eq!(triplet_mat.col_inds(), &[0, 2, 1]);
+        assert_eq!(triplet_mat.data(), &[3., 5., 3.]);
+        assert_eq!(triplet_mat.find_locations(0, 0).len(), 1);
+        assert_eq!(triplet_mat.find_locations(2, 1).len(), 1);
+        assert_eq!(triplet_mat.to_csr(), expected);
+
+        let mut empty = TriMat::<f64>::new((2, 2));
+        empty.sum_duplicates();
+        assert_eq!(empty.nnz(), 0);
+    }
+
+    #[test]
+    fn triplet_sorted_fast_path() {
+        // |. . . .|
+        // |1 2 . .|
+        // |3 . . .|
+        // |. . . .|
+        let mut triplet_mat = TriMat::new((4, 4));
+        triplet_mat.add_triplet(1, 0, 1);
+        triplet_mat.add_triplet(1, 1, 2);
+        triplet_mat.add_triplet(2, 0, 3);
+        assert!(triplet_mat.is_sorted_for(CSR));
+        assert!(!triplet_mat.is_sorted_for(CSC));
+        let expected = CsMat::new(
+            (4, 4),
+            vec![0, 0, 2, 3, 3],
+            vec![0, 1, 0],
+            vec![1, 2, 3],
+        );
+        assert_eq!(triplet_mat.to_csr(), expected);
+        assert_eq!(triplet_mat.to_csc(), expected.to_csc());
+        assert!(triplet_mat.transpose_view().is_sorted_for(CSC));
+        assert_eq!(
+            triplet_mat.transpose_view().to_csc(),
+            expected.transpose_view().to_owned()
+        );
+
+        // duplicates disable the fast path
+        triplet_mat.add_triplet(2, 0, 1);
+        assert!(!triplet_mat.is_sorted_for(CSR));
+        let expected = CsMat::new(
+            (4, 4),
+            vec![0, 0, 2, 3, 3],
+            vec![0, 1, 0],
+            vec![1, 2, 4],
+        );
+        assert_eq!(triplet_mat.to_csr(), expected);
+
+        let empty = TriMat::<f64>::new((3, 2));
+        assert!(empty.is_sorted_for(CSR));
+        assert_eq!(empty.to_csr(), CsMat::zero((3, 2)));
+        assert_eq!(empty.to_csc(), CsMat::zero((3, 2)).to_csc());
+    }
+
+    #[test]
+    #[cfg(feature = "multi_thread")]
+    fn triplet_into_cs_par() {
+        // Enough triplets to use the parallel path, with many duplicates
+        // whose floating point sum depends on the accumulation order
+        let (rows, cols) = (100, 80);
+        let mut triplet_mat = TriMat::with_capacity((rows, cols), 50_000);
+        let mut state = 12345usize;
+        for k in 0..50_000 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            let loc = (state >> 33) % (rows * cols / 4);
+            let val = 0.1 * (k % 7) as f64 + 1e-3 * (k % 13) as f64;
+            triplet_mat.add_triplet(loc % rows, loc / rows, val);
+        }
+        for &storage in &[CSR, CSC] {
+            let par: CsMat<f64> =
+                triplet_mat.triplet_iter().into_cs_par(storage);
+            let serial: CsMat<f64> =
+                triplet_mat.triplet_iter().into_cs(storage);
+            assert_eq!(par, serial);
+            assert_eq!(par.storage(), storage);
+            par.check_compressed_structure().unwrap();
+        }
+
+        let mut small = TriMat::new((3, 3));
+        small.add_triplet(2, 1, 1.);
+        small.add_triplet(0, 1, 2.);
+        small.add_triplet(2, 1, 3.);
+        let expected =
+            CsMat::new((3, 3), vec![0, 1, 1, 2], vec![1, 1], vec![2., 4.]);
+        assert_eq!(small.triplet_iter().into_cs_par(CSR), expected);
+    }
 }
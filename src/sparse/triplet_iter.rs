@@ -6,6 +6,12 @@ use num_traits::Num;
 use crate::indexing::SpIndex;
 use crate::sparse::{CsMatI, TriMatIter};
 use crate::CompressedStorage;
+#[cfg(feature = "multi_thread")]
+use rayon::prelude::*;
+
+/// Number of triplets under which `into_cs_par` performs a serial conversion
+#[cfg(feature = "multi_thread")]
+const PAR_CONVERSION_THRESHOLD: usize = 1 << 14;
 
 impl<'a, N, I, RI, CI, DI> Iterator for TriMatIter<RI, CI, DI>
 where
@@ -87,6 +93,35 @@ where
         self.data
     }
 
+    /// Check whether the triplets are sorted by increasing outer then inner
+    /// index for the given storage, without duplicated locations.
+    ///
+    /// When this holds, the conversion into a compressed matrix with this
+    /// storage can be performed without sorting.
+    pub fn is_sorted_for(&self, storage: CompressedStorage) -> bool
+    where
+        RI: Clone,
+        CI: Clone,
+    {
+        let rows = self.row_inds.clone();
+        let cols = self.col_inds.clone();
+        let mut locations = rows.zip(cols).map(|(&i, &j)| match storage {
+            CompressedStorage::CSR => (i, j),
+            CompressedStorage::CSC => (j, i),
+        });
+        let mut prev = match locations.next() {
+            Some(loc) => loc,
+            None => return true,
+        };
+        for loc in locations {
+            if loc <= prev {
+                return false;
+            }
+            prev = loc;
+        }
+        true
+    }
+
     pub fn transpose_into(self) -> TriMatIter<CI, RI, DI> {
         TriMatIter {
             rows: self.cols,
@@ -131,6 +166,10 @@ where
     where
         N: Num,
     {
+        if self.is_sorted_for(storage) {
+            return self.into_cs_sorted(storage);
+        }
+
         // (i,j, input position, output position)
         let mut rc: Vec<(I, I, N)> = Vec::new();
 
@@ -140,12 +179,14 @@ where
             nnz_max += 1;
         }
 
+        // The sort is stable so that duplicates are summed in input order,
+        // which makes the result match `into_cs_par`'s
         match storage {
             CompressedStorage::CSR => {
-                rc.sort_unstable_by_key(|i| (i.0, i.1));
+                rc.sort_by_key(|i| (i.0, i.1));
             }
             CompressedStorage::CSC => {
-                rc.sort_unstable_by_key(|i| (i.1, i.0));
+                rc.sort_by_key(|i| (i.1, i.0));
             }
         }
 
@@ -222,4 +263,136 @@ where
             data,
         }
     }
+
+    /// Build a compressed matrix in a single pass, requires the triplets
+    /// to be sorted for `storage` without duplicates.
+    fn into_cs_sorted<Iptr: SpIndex>(
+        self,
+        storage: CompressedStorage,
+    ) -> CsMatI<N, I, Iptr> {
+        let outer_dims = match storage {
+            CompressedStorage::CSR => self.rows(),
+            CompressedStorage::CSC => self.cols(),
+        };
+        let nnz = self.nnz();
+        let shape = self.shape();
+        let mut indptr = Vec::with_capacity(outer_dims + 1);
+        let mut indices = Vec::with_capacity(nnz);
+        let mut data = Vec::with_capacity(nnz);
+        indptr.push(Iptr::zero());
+        for (v, (i, j)) in self {
+            let (outer, inner) = match storage {
+                CompressedStorage::CSR => (i, j),
+                CompressedStorage::CSC => (j, i),
+            };
+            while indptr.len() <= outer.index() {
+                indptr.push(Iptr::from_usize(indices.len()));
+            }
+            indices.push(inner);
+            data.push(v.clone());
+        }
+        while indptr.len() <= outer_dims {
+            indptr.push(Iptr::from_usize(indices.len()));
+        }
+        CsMatI::new_trusted(storage, shape, indptr, indices, data)
+    }
+}
+
+#[cfg(feature = "multi_thread")]
+impl<'a, N, I, RI, CI, DI> TriMatIter<RI, CI, DI>
+where
+    I: 'a + SpIndex,
+    N: 'a + Num + Clone + Send + Sync,
+    RI: Clone + Iterator<Item = &'a I>,
+    CI: Clone + Iterator<Item = &'a I>,
+    DI: Clone + Iterator<Item = &'a N>,
+{
+    /// Consume `TriMatIter` and produce a `CsMat` matrix with the chosen
+    /// storage, using multiple threads to sort the triplets and to merge
+    /// the duplicated ones.
+    ///
+    /// Small inputs are converted serially. The result is identical to the
+    /// one of [`into_cs`](Self::into_cs), including the values obtained by
+    /// summing duplicates, which are accumulated in input order.
+    pub fn into_cs_par<Iptr: SpIndex>(
+        self,
+        storage: CompressedStorage,
+    ) -> CsMatI<N, I, Iptr> {
+        if self.nnz() < PAR_CONVERSION_THRESHOLD || self.is_sorted_for(storage)
+        {
+            return self.into_cs(storage);
+        }
+        let shape = self.shape();
+        let outer_dims = match storage {
+            CompressedStorage::CSR => shape.0,
+            CompressedStorage::CSC => shape.1,
+        };
+        let mut triplets: Vec<(I, I, N)> = self
+            .map(|(v, (i, j))| match storage {
+                CompressedStorage::CSR => (i, j, v.clone()),
+                CompressedStorage::CSC => (j, i, v.clone()),
+            })
+            .collect();
+        triplets.par_sort_by_key(|&(outer, inner, _)| (outer, inner));
+
+        // Split the triplets in chunks, without splitting the runs of
+        // duplicated locations, so the chunks can be merged independently
+        let nnz_max = triplets.len();
+        let nb_chunks = rayon::current_num_threads().max(1);
+        let mut bounds = Vec::with_capacity(nb_chunks + 1);
+        bounds.push(0);
+        for k in 1..nb_chunks {
+            let mut bound = (k * nnz_max / nb_chunks).max(bounds[k - 1]);
+            while bound > 0
+                && bound < nnz_max
+                && triplets[bound - 1].0 == triplets[bound].0
+                && triplets[bound - 1].1 == triplets[bound].1
+            {
+                bound += 1;
+            }
+            bounds.push(bound);
+        }
+        bounds.push(nnz_max);
+        let chunks: Vec<(Vec<I>, Vec<I>, Vec<N>)> = bounds
+            .par_windows(2)
+            .map(|bounds| {
+                let chunk = &triplets[bounds[0]..bounds[1]];
+                let mut outers: Vec<I> = Vec::with_capacity(chunk.len());
+                let mut inners: Vec<I> = Vec::with_capacity(chunk.len());
+                let mut data: Vec<N> = Vec::with_capacity(chunk.len());
+                for (outer, inner, val) in chunk {
+                    let is_duplicate = outers.last() == Some(outer)
+                        && inners.last() == Some(inner);
+                    match data.last_mut() {
+                        Some(last) if is_duplicate => {
+                            *last = last.clone() + val.clone();
+                        }
+                        _ => {
+                            outers.push(*outer);
+                            inners.push(*inner);
+                            data.push(val.clone());
+                        }
+                    }
+                }
+                (outers, inners, data)
+            })
+            .collect();
+
+        let nnz = chunks.iter().map(|chunk| chunk.2.len()).sum();
+        let mut indptr = vec![Iptr::zero(); outer_dims + 1];
+        let mut indices = Vec::with_capacity(nnz);
+        let mut data = Vec::with_capacity(nnz);
+        for (outers, inners, vals) in chunks {
+            for outer in outers {
+                indptr[outer.index() + 1] += Iptr::one();
+            }
+            indices.extend(inners);
+            data.extend(vals);
+        }
+        for i in 0..outer_dims {
+            let prev = indptr[i];
+            indptr[i + 1] += prev;
+        }
+        CsMatI::new_trusted(storage, shape, indptr, indices, data)
+    }
 }
@@ -1,7 +1,7 @@
 use crate::dense_vector::{DenseVector, DenseVectorMut};
 use crate::sparse::to_dense::assign_vector_to_dense;
 use crate::Ix1;
-use ndarray::Array;
+use ndarray::{Array, ArrayView1};
 use std::cmp;
 use std::collections::HashSet;
 use std::convert::AsRef;
@@ -562,6 +562,71 @@ impl<N, I: SpIndex> CsVecI<N, I> {
         Self::new_trusted(dim, vec![], vec![])
     }
 
+    /// Create a sparse vector from a dense one, storing its non-zero
+    /// elements. To also ignore the elements close to zero, see
+    /// [`from_dense_threshold`](Self::from_dense_threshold).
+    ///
+    /// The dense vector can be recovered with
+    /// [`to_dense`](CsVecBase::to_dense).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ndarray::arr1;
+    /// use sprs::CsVec;
+    /// let dense = arr1(&[0., 1., 0., 2.]);
+    /// let v = CsVec::from_dense(dense.view());
+    /// assert_eq!(v, CsVec::new(4, vec![1, 3], vec![1., 2.]));
+    /// assert_eq!(v.to_dense(), dense);
+    /// ```
+    pub fn from_dense(a: ArrayView1<N>) -> Self
+    where
+        N: Zero + Clone,
+    {
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        for (ind, val) in a.iter().enumerate() {
+            if !val.is_zero() {
+                indices.push(I::from_usize(ind));
+                data.push(val.clone());
+            }
+        }
+        Self::new_trusted(a.len(), indices, data)
+    }
+
+    /// Create a sparse vector from a dense one, only storing the elements
+    /// whose absolute value is greater than `threshold`. A negative
+    /// threshold is treated as zero.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ndarray::arr1;
+    /// use sprs::CsVec;
+    /// let dense = arr1(&[1e-12, 1., 0., -2., -1e-9]);
+    /// let v = CsVec::from_dense_threshold(dense.view(), 1e-8);
+    /// assert_eq!(v, CsVec::new(5, vec![1, 3], vec![1., -2.]));
+    /// ```
+    pub fn from_dense_threshold(a: ArrayView1<N>, threshold: N) -> Self
+    where
+        N: Num + Clone + cmp::PartialOrd + Signed,
+    {
+        let threshold = if threshold > N::zero() {
+            threshold
+        } else {
+            N::zero()
+        };
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        for (ind, val) in a.iter().enumerate() {
+            if val.abs() > threshold {
+                indices.push(I::from_usize(ind));
+                data.push(val.clone());
+            }
+        }
+        Self::new_trusted(a.len(), indices, data)
+    }
+
     /// Append an element to the sparse vector. Used for incremental
     /// building of the `CsVec`. The append should preserve the structure
     /// of the vector, ie the newly added index should be strictly greater
@@ -584,6 +649,42 @@ impl<N, I: SpIndex> CsVecI<N, I> {
         self.data.push(val);
     }
 
+    /// Append the sparse vector `other` at the end of this vector,
+    /// offsetting its indices by `self.dim()`. The dimension of this vector
+    /// becomes `self.dim() + other.dim()`.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the indices of `other` are not sorted
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsVec;
+    /// let mut v = CsVec::new(3, vec![0, 2], vec![1, 2]);
+    /// let w = CsVec::new(4, vec![1, 3], vec![3, 4]);
+    /// v.append_vec(&w);
+    /// assert_eq!(v, CsVec::new(7, vec![0, 2, 4, 6], vec![1, 2, 3, 4]));
+    /// ```
+    pub fn append_vec<IS, DS>(&mut self, other: &CsVecBase<IS, DS, N, I>)
+    where
+        N: Clone,
+        IS: Deref<Target = [I]>,
+        DS: Deref<Target = [N]>,
+    {
+        assert!(utils::sorted_indices(other.indices()), "unsorted append");
+        let offset = self.dim;
+        self.reserve(other.nnz());
+        self.indices.extend(
+            other
+                .indices()
+                .iter()
+                .map(|ind| I::from_usize(ind.index() + offset)),
+        );
+        self.data.extend_from_slice(other.data());
+        self.dim += other.dim();
+    }
+
     /// Reserve `size` additional non-zero values.
     pub fn reserve(&mut self, size: usize) {
         self.indices.reserve(size);
@@ -601,6 +702,50 @@ impl<N, I: SpIndex> CsVecI<N, I> {
         self.indices.clear();
         self.data.clear();
     }
+
+    /// Remove the explicitly stored zeros from this vector, eg after
+    /// updating its values with [`map_inplace`](Self::map_inplace).
+    ///
+    /// Returns the number of removed entries, the remaining indices are
+    /// kept sorted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsVec;
+    /// let mut v = CsVec::new(5, vec![0, 2, 4], vec![1, 2, 3]);
+    /// v.map_inplace(|&x| x % 2);
+    /// assert_eq!(v.prune(), 1);
+    /// assert_eq!(v, CsVec::new(5, vec![0, 4], vec![1, 1]));
+    /// ```
+    pub fn prune(&mut self) -> usize
+    where
+        N: Zero,
+    {
+        self.prune_by(|val| !val.is_zero())
+    }
+
+    /// Only keep the non-zero entries for which `keep` returns `true`,
+    /// removing the others from the storage.
+    ///
+    /// Returns the number of removed entries.
+    pub fn prune_by<F>(&mut self, mut keep: F) -> usize
+    where
+        F: FnMut(&N) -> bool,
+    {
+        let old_nnz = self.nnz();
+        let mut nnz = 0;
+        for k in 0..old_nnz {
+            if keep(&self.data[k]) {
+                self.indices.swap(nnz, k);
+                self.data.swap(nnz, k);
+                nnz += 1;
+            }
+        }
+        self.indices.truncate(nnz);
+        self.data.truncate(nnz);
+        old_nnz - nnz
+    }
 }
 
 /// # Common methods of sparse vectors
@@ -782,6 +927,19 @@ where
     }
 
     /// Access element at given index, with logarithmic complexity
+    ///
+    /// Returns `None` if no non-zero is stored at `index`, including when
+    /// `index` is out of the vector's dimension.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsVec;
+    /// let v = CsVec::new(5, vec![0, 2, 4], vec![1., 2., 3.]);
+    /// assert_eq!(v.get(2), Some(&2.));
+    /// assert_eq!(v.get(3), None);
+    /// assert_eq!(v.get(7), None);
+    /// ```
     pub fn get<'a>(&'a self, index: usize) -> Option<&'a N>
     where
         I: 'a,
@@ -794,8 +952,16 @@ where
     ///
     /// Looking for the `NnzIndex` is done with logarithmic complexity, but
     /// once it is available, the `NnzIndex` enables retrieving the data with
-    /// O(1) complexity.
+    /// O(1) complexity. The position of the non-zero in
+    /// [`indices`](Self::indices) and [`data`](Self::data) is the `usize`
+    /// wrapped in the `NnzIndex`.
+    ///
+    /// Returns `None` if `index` is out of the vector's dimension.
     pub fn nnz_index(&self, index: usize) -> Option<NnzIndex> {
+        // Out of bounds indices may not be representable by I
+        if index >= self.dim {
+            return None;
+        }
         self.indices
             .binary_search(&I::from_usize(index))
             .map(|i| NnzIndex(i.index_unchecked()))
@@ -896,7 +1062,7 @@ where
         N: Sum,
         for<'r> &'r N: Mul<&'r N, Output = N>,
     {
-        assert_eq!(self.dim(), rhs.dim());
+        assert_eq!(self.dim(), rhs.dim(), "Dimension mismatch");
         self.iter()
             .map(|(idx, val)| val * rhs.index(idx.index_unchecked()))
             .sum()
@@ -994,6 +1160,57 @@ where
         res.map_inplace(f);
         res
     }
+
+    /// Keep the `k` non-zero entries of largest magnitude, yielding a new
+    /// vector of the same dimension. Entries of equal magnitude are
+    /// selected by increasing index.
+    ///
+    /// If `k` is greater than the number of non-zeros, the whole vector is
+    /// kept. NaN values are considered to have the smallest magnitude, so
+    /// they are only kept if there are fewer than `k` other non-zeros.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsVec;
+    /// let v = CsVec::new(6, vec![0, 1, 3, 5], vec![1., -4., 2., 4.]);
+    /// assert_eq!(v.top_k(2), CsVec::new(6, vec![1, 5], vec![-4., 4.]));
+    /// ```
+    pub fn top_k(&self, k: usize) -> CsVecI<N, I>
+    where
+        N: Signed + PartialOrd + Clone,
+    {
+        if k >= self.nnz() {
+            return self.to_owned();
+        }
+        // NaN magnitudes are mapped to None, so that they compare as the
+        // smallest ones and the sort below uses a total order
+        let magnitudes: Vec<Option<N>> = self
+            .data
+            .iter()
+            .map(|x| {
+                let a = x.abs();
+                a.partial_cmp(&a).map(|_| a)
+            })
+            .collect();
+        let mut positions: Vec<usize> = (0..self.nnz()).collect();
+        // a stable sort keeps positions, hence indices, increasing in ties
+        positions.sort_by(|&p1, &p2| {
+            match (&magnitudes[p1], &magnitudes[p2]) {
+                (Some(a1), Some(a2)) => {
+                    a2.partial_cmp(a1).unwrap_or(cmp::Ordering::Equal)
+                }
+                (Some(_), None) => cmp::Ordering::Less,
+                (None, Some(_)) => cmp::Ordering::Greater,
+                (None, None) => cmp::Ordering::Equal,
+            }
+        });
+        positions.truncate(k);
+        positions.sort_unstable();
+        let indices = positions.iter().map(|&p| self.indices[p]).collect();
+        let data = positions.iter().map(|&p| self.data[p].clone()).collect();
+        CsVecI::new_trusted(self.dim, indices, data)
+    }
 }
 
 /// # Methods on sparse vectors with mutable access to their data
@@ -1131,6 +1348,34 @@ where
     }
 }
 
+/// Product of a matrix view with a sparse vector view, taking both operands
+/// by value since views are cheap to copy. The result is sparse, only the
+/// outer dimensions touched by the non-zeros of the vector are visited.
+///
+/// # Panics
+///
+/// If the number of columns of the matrix does not match the dimension of
+/// the vector.
+impl<'a, 'b, N, I, Iptr> Mul<CsVecViewI<'b, N, I>>
+    for CsMatViewI<'a, N, I, Iptr>
+where
+    N: Clone
+        + crate::MulAcc
+        + num_traits::Zero
+        + PartialEq
+        + Default
+        + Send
+        + Sync,
+    I: SpIndex,
+    Iptr: SpIndex,
+{
+    type Output = CsVecI<N, I>;
+
+    fn mul(self, rhs: CsVecViewI<'b, N, I>) -> Self::Output {
+        &self * &rhs
+    }
+}
+
 impl<Lhs, Rhs, Res, I, IS1, DS1, IS2, DS2> Add<CsVecBase<IS2, DS2, Rhs, I>>
     for CsVecBase<IS1, DS1, Lhs, I>
 where
@@ -1688,6 +1933,54 @@ mod test {
         vec1.dot(&dense_vec);
     }
 
+    #[test]
+    #[should_panic(expected = "Dimension mismatch")]
+    fn dot_dense_panics() {
+        let vec1 = CsVec::new(8, vec![0, 2, 4, 6], vec![1.; 4]);
+        let dense_vec = Array::from_elem(9, 1.);
+        vec1.dot_dense(dense_vec.view());
+    }
+
+    #[test]
+    fn prune() {
+        let mut v = CsVec::new(6, vec![0, 1, 3, 5], vec![1., 0., 2., 0.]);
+        assert_eq!(v.prune(), 2);
+        assert_eq!(v, CsVec::new(6, vec![0, 3], vec![1., 2.]));
+        assert_eq!(v.prune(), 0);
+        let mut v = test_vec2();
+        assert_eq!(v.prune_by(|&x| x > 3.), 2);
+        assert_eq!(v, CsVec::new(8, vec![4, 6, 7], vec![4.5, 6.5, 7.5]));
+        let mut v = CsVec::new(3, vec![1], vec![0]);
+        assert_eq!(v.prune(), 1);
+        assert_eq!(v, CsVec::empty(3));
+    }
+
+    #[test]
+    fn top_k() {
+        let v = CsVec::new(8, vec![0, 2, 3, 5, 7], vec![1, -3, 2, 3, -1]);
+        assert_eq!(v.top_k(0), CsVec::empty(8));
+        assert_eq!(v.top_k(1), CsVec::new(8, vec![2], vec![-3]));
+        assert_eq!(v.top_k(3), CsVec::new(8, vec![2, 3, 5], vec![-3, 2, 3]));
+        // ties are broken by index
+        assert_eq!(
+            v.top_k(4),
+            CsVec::new(8, vec![0, 2, 3, 5], vec![1, -3, 2, 3])
+        );
+        assert_eq!(v.top_k(5), v);
+        assert_eq!(v.view().top_k(10), v);
+    }
+
+    #[test]
+    fn top_k_nan() {
+        let nan = f64::NAN;
+        let v = CsVec::new(6, vec![0, 1, 2, 4, 5], vec![nan, 2., nan, -5., 1.]);
+        assert_eq!(v.top_k(2), CsVec::new(6, vec![1, 4], vec![2., -5.]));
+        assert_eq!(v.top_k(3), CsVec::new(6, vec![1, 4, 5], vec![2., -5., 1.]));
+        let top_4 = v.top_k(4);
+        assert_eq!(top_4.indices(), &[0, 1, 4, 5]);
+        assert!(top_4.data()[0].is_nan());
+    }
+
     #[test]
     fn squared_l2_norm() {
         // Should work with both float and integer data
@@ -1776,6 +2069,14 @@ mod test {
         let mut vec = vec;
         vec[index] = 2.;
         assert_eq!(vec[index], 2.);
+
+        // out of bounds indices which do not fit in the index type
+        let vec = CsVecI::<f64, u16>::new(8, vec![0, 2], vec![1.; 2]);
+        assert_eq!(vec.nnz_index(70_000), None);
+        assert_eq!(vec.get(70_000), None);
+        assert_eq!(vec.view().get_rbr(8), None);
+        assert_eq!(vec.get(2), Some(&1.));
+        assert_eq!(vec.nnz_index(2).unwrap().0, 1);
     }
 
     #[test]
@@ -2003,4 +2304,65 @@ mod test {
             ::approx::assert_abs_diff_eq!(v1.view(), v2.view());
         }
     }
+
+    #[test]
+    fn mat_view_mul_vec_view() {
+        let mat = crate::test_data::mat1();
+        let vec = CsVec::new(5, vec![1, 3], vec![2., -1.]);
+        let expected =
+            CsVec::new(5, vec![0, 1, 3, 4], vec![-4., -2., 16., -7.]);
+        assert_eq!(mat.view() * vec.view(), expected);
+        assert_eq!(mat.to_csc().view() * vec.view(), expected);
+        assert_eq!(mat.view() * vec.view(), &mat * &vec);
+    }
+
+    #[test]
+    #[should_panic(expected = "Dimension mismatch")]
+    fn mat_view_mul_vec_view_fail() {
+        let mat = crate::test_data::mat1();
+        let vec = CsVec::new(3, vec![1], vec![2.]);
+        let _ = mat.view() * vec.view();
+    }
+
+    #[test]
+    fn append_vec() {
+        let mut v = CsVec::empty(2);
+        v.append_vec(&test_vec1());
+        v.append_vec(&CsVec::<f64>::empty(3));
+        v.append_vec(&CsVec::new(4, vec![0, 3], vec![1., 2.]).view());
+        let expected = CsVec::new(
+            17,
+            vec![2, 3, 6, 7, 9, 13, 16],
+            vec![0., 1., 4., 5., 7., 1., 2.],
+        );
+        assert_eq!(v, expected);
+        assert!(v.check_structure().is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "unsorted append")]
+    fn append_vec_unsorted() {
+        let mut v = CsVec::new(2, vec![1], vec![1.]);
+        let w = CsVec::new_trusted(3, vec![2, 0], vec![1., 2.]);
+        v.append_vec(&w);
+    }
+
+    #[test]
+    fn from_dense() {
+        let v = test_vec1();
+        let dense = v.to_dense();
+        // the explicit zero of test_vec1 is not kept
+        let expected = CsVec::new(8, vec![1, 4, 5, 7], vec![1., 4., 5., 7.]);
+        assert_eq!(CsVec::from_dense(dense.view()), expected);
+        assert_eq!(CsVec::from_dense_threshold(dense.view(), 0.), expected);
+        assert_eq!(CsVec::from_dense_threshold(dense.view(), -1.), expected);
+        assert_eq!(
+            CsVec::from_dense_threshold(dense.view(), 4.),
+            CsVec::new(8, vec![5, 7], vec![5., 7.])
+        );
+        let empty = Array::<f64, _>::zeros(3);
+        assert_eq!(CsVecI::<f64, u16>::from_dense(empty.view()).nnz(), 0);
+        let empty = Array::<f64, _>::zeros(0);
+        assert_eq!(CsVec::from_dense(empty.view()), CsVec::empty(0));
+    }
 }